@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+
+use backend::accounting::AccountingTotals;
+use backend::events::BackendEvent;
+use eframe::egui;
+
+use crate::dashboard::{Dashboard, totals_rows};
+
+// Dashboard of each account worker, with the totals of every account
+pub struct AccountDashboards {
+    dashboards: BTreeMap<String, Dashboard>,
+    // None = totals of every account
+    selected: Option<String>,
+}
+
+impl AccountDashboards {
+    pub fn new() -> Self {
+        Self {
+            dashboards: BTreeMap::new(),
+            selected: None,
+        }
+    }
+
+    pub fn apply(&mut self, event: &BackendEvent) {
+        if let BackendEvent::Account { account, event } = event {
+            self.dashboards
+                .entry(account.clone())
+                .or_insert_with(Dashboard::new)
+                .apply(event);
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        // Single account: no selector
+        if self.dashboards.len() <= 1 {
+            match self.dashboards.values_mut().next() {
+                Some(dashboard) => dashboard.show(ui),
+                None => {
+                    ui.heading("📊 Dashboard");
+                    ui.label(egui::RichText::new("Waiting for the back-end...").color(egui::Color32::GRAY));
+                }
+            }
+            return;
+        }
+
+        ui.horizontal_wrapped(|ui| {
+            ui.selectable_value(&mut self.selected, None, "All");
+            for name in self.dashboards.keys() {
+                ui.selectable_value(&mut self.selected, Some(name.clone()), name);
+            }
+        });
+        ui.separator();
+        match self.selected.as_ref().and_then(|name| self.dashboards.get_mut(name)) {
+            Some(dashboard) => dashboard.show(ui),
+            None => self.show_totals(ui),
+        }
+    }
+
+    fn show_totals(&self, ui: &mut egui::Ui) {
+        ui.heading("📊 All accounts");
+        ui.add_space(5.0);
+
+        let mut today = AccountingTotals::default();
+        let mut session = AccountingTotals::default();
+        for dashboard in self.dashboards.values() {
+            today.merge(&dashboard.today());
+            session.merge(&dashboard.session());
+        }
+        egui::Grid::new("accounts_stats")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                ui.label(egui::RichText::new("Today").strong());
+                ui.label(egui::RichText::new("Session").strong());
+                ui.end_row();
+                totals_rows(ui, &[today, session]);
+            });
+
+        ui.add_space(10.0);
+        ui.label(egui::RichText::new("Today by account").strong());
+        egui::Grid::new("accounts_today")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Account");
+                ui.label("Volume");
+                ui.label("Net cost");
+                ui.label("Cost/$1k");
+                ui.label("Positions");
+                ui.end_row();
+                for (name, dashboard) in &self.dashboards {
+                    let totals = dashboard.today();
+                    ui.label(name);
+                    ui.label(format!("${:.2}", totals.volume));
+                    ui.label(format!("${:.2}", totals.net_cost()));
+                    ui.label(totals.cost_per_1k().map_or("-".to_string(), |cost| format!("${:.2}", cost)));
+                    ui.label(dashboard.positions().len().to_string());
+                    ui.end_row();
+                }
+            });
+
+        // Accounts which stopped quoting
+        for (name, dashboard) in &self.dashboards {
+            if let Some(reason) = dashboard.target().and_then(|target| target.stop_reason()) {
+                ui.colored_label(
+                    egui::Color32::YELLOW,
+                    format!("{}: quoting stopped ({:?})", name, reason),
+                );
+            }
+        }
+    }
+}
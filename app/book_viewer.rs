@@ -0,0 +1,212 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use backend::events::BackendEvent;
+use backend::strategy::{self, StrategyParams};
+use crossbeam::channel::Sender;
+use eframe::egui;
+use paradex::structs::{Level, OrderBook, OrderUpdate, Side};
+use rust_decimal::prelude::ToPrimitive;
+
+const OWN_ORDER_COLOR: egui::Color32 = egui::Color32::from_rgb(80, 160, 255);
+const EXIT_WINDOW_COLOR: egui::Color32 = egui::Color32::from_rgba_premultiplied(40, 70, 40, 120);
+
+pub struct BookViewer {
+    market_sender: Sender<Option<String>>,
+    selected: Option<String>,
+    typed_market: String,
+    markets: BTreeSet<String>,
+    // Open orders of each account
+    open_orders: BTreeMap<String, Vec<OrderUpdate>>,
+    book: Option<OrderBook>,
+    // Rules of the bot, for the exit window
+    params: StrategyParams,
+}
+
+impl BookViewer {
+    pub fn new(market_sender: Sender<Option<String>>, params: StrategyParams) -> Self {
+        Self {
+            market_sender,
+            selected: None,
+            typed_market: String::new(),
+            markets: BTreeSet::new(),
+            open_orders: BTreeMap::new(),
+            book: None,
+            params,
+        }
+    }
+
+    pub fn apply(&mut self, event: &BackendEvent) {
+        match event {
+            BackendEvent::Account { account, event } => match event.as_ref() {
+                BackendEvent::OpenOrders(orders) => {
+                    self.open_orders.insert(account.clone(), orders.clone());
+                    self.markets
+                        .extend(orders.iter().map(|order| order.market.clone()));
+                }
+                event => self.apply(event),
+            },
+            BackendEvent::Positions(positions) => {
+                self.markets
+                    .extend(positions.iter().map(|position| position.market.clone()));
+            }
+            BackendEvent::OrderBook(book)
+                if self.selected.as_deref() == Some(book.market.as_str()) =>
+            {
+                self.book = Some(book.clone());
+            }
+            _ => {}
+        }
+    }
+
+    fn select(&mut self, market: Option<String>) {
+        if market != self.selected {
+            self.book = None;
+            self.selected = market.clone();
+            let _ = self.market_sender.send(market);
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        // Market selector
+        ui.horizontal(|ui| {
+            let mut choice = self.selected.clone();
+            egui::ComboBox::from_label("Market")
+                .selected_text(choice.clone().unwrap_or_else(|| "None".into()))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut choice, None, "None");
+                    for market in &self.markets {
+                        ui.selectable_value(&mut choice, Some(market.clone()), market);
+                    }
+                });
+            if choice != self.selected {
+                self.select(choice);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.typed_market)
+                    .hint_text("BTC-USD-96000-C")
+                    .desired_width(200.0),
+            );
+            if ui.button("Watch").clicked() && !self.typed_market.trim().is_empty() {
+                let market = self.typed_market.trim().to_string();
+                self.markets.insert(market.clone());
+                self.select(Some(market));
+            }
+        });
+        ui.separator();
+
+        let Some(market) = self.selected.clone() else {
+            ui.label(egui::RichText::new("Select a market").color(egui::Color32::GRAY));
+            return;
+        };
+        let Some(book) = &self.book else {
+            ui.label(egui::RichText::new("Waiting for order book...").color(egui::Color32::GRAY));
+            return;
+        };
+
+        let mut bids: Vec<&Level> = book.inserts.iter().filter(|l| l.side == Side::BUY).collect();
+        let mut asks: Vec<&Level> = book.inserts.iter().filter(|l| l.side == Side::SELL).collect();
+        bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+        asks.sort_by(|a, b| b.price.total_cmp(&a.price));
+
+        let own_orders: Vec<&OrderUpdate> = self
+            .open_orders
+            .values()
+            .flatten()
+            .filter(|order| order.market == market && order.side == Side::BUY)
+            .collect();
+        let own_prices: Vec<f64> = own_orders
+            .iter()
+            .filter_map(|order| order.price.and_then(|price| price.to_f64()))
+            .collect();
+        // Exit window of our best bid
+        let best_own_price = own_prices.iter().cloned().fold(None, |best: Option<f64>, price| {
+            Some(best.map_or(price, |best| best.max(price)))
+        });
+        let max_spread = self.params.max_spread_price;
+        let in_exit_window = |price: f64| {
+            best_own_price.is_some_and(|own| own - price < max_spread && price <= own)
+        };
+
+        // Liquidity as summed by the bot (3 first bids inside the window)
+        if let Some(own_order) = own_orders
+            .iter()
+            .max_by(|a, b| a.price.cmp(&b.price))
+            && let Some(own_price) = own_order.price
+        {
+            let top_bids: Vec<&Level> = bids.iter().take(3).cloned().collect();
+            let liquidity = strategy::exit_window_liquidity(&top_bids, own_price, &self.params);
+            let required = (own_order.size * self.params.size_multiplier())
+                .to_f64()
+                .unwrap_or_default();
+            let color = if liquidity >= required {
+                egui::Color32::GREEN
+            } else {
+                egui::Color32::RED
+            };
+            ui.horizontal(|ui| {
+                ui.label("Exit liquidity:");
+                ui.colored_label(color, format!("{:.2} / {:.2} required", liquidity, required));
+            });
+            ui.label(
+                egui::RichText::new(format!(
+                    "Exit window: bids above {:.2}",
+                    own_price.to_f64().unwrap_or_default() - max_spread
+                ))
+                .color(egui::Color32::GRAY),
+            );
+        }
+
+        let max_size = book
+            .inserts
+            .iter()
+            .map(|level| level.size)
+            .fold(0.0, f64::max)
+            .max(f64::EPSILON);
+
+        ui.add_space(5.0);
+        egui::Grid::new("book_ladder")
+            .num_columns(3)
+            .striped(false)
+            .show(ui, |ui| {
+                ui.label(egui::RichText::new("Price").strong());
+                ui.label(egui::RichText::new("Size").strong());
+                ui.label("");
+                ui.end_row();
+                for ask in &asks {
+                    ladder_row(ui, ask, max_size, egui::Color32::RED, false, false);
+                }
+                for bid in &bids {
+                    let own = own_prices.iter().any(|price| (price - bid.price).abs() < 1e-9);
+                    ladder_row(ui, bid, max_size, egui::Color32::GREEN, own, in_exit_window(bid.price));
+                }
+            });
+    }
+}
+
+fn ladder_row(
+    ui: &mut egui::Ui,
+    level: &Level,
+    max_size: f64,
+    side_color: egui::Color32,
+    own: bool,
+    exit_window: bool,
+) {
+    let price_text = egui::RichText::new(format!("{:.2}", level.price)).color(side_color);
+    let price_text = if own {
+        price_text.strong().background_color(OWN_ORDER_COLOR)
+    } else if exit_window {
+        price_text.background_color(EXIT_WINDOW_COLOR)
+    } else {
+        price_text
+    };
+    ui.label(price_text);
+    ui.label(format!("{}", level.size));
+    ui.add(
+        egui::ProgressBar::new((level.size / max_size) as f32)
+            .desired_width(120.0)
+            .fill(side_color.gamma_multiply(0.5)),
+    );
+    ui.end_row();
+}
@@ -0,0 +1,266 @@
+use std::collections::BTreeMap;
+
+use backend::accounting::{Accounting, AccountingTotals};
+use backend::events::BackendEvent;
+use backend::fees::FeeModel;
+use backend::targets::TargetProgress;
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use eframe::egui;
+use paradex::structs::Position;
+
+pub struct Dashboard {
+    day: NaiveDate,
+    positions: Vec<Position>,
+    accounting: Accounting,
+    target: Option<TargetProgress>,
+    fee_model: Option<FeeModel>,
+    fills: usize,
+    liquidations: usize,
+    export_status: Option<String>,
+}
+
+impl Dashboard {
+    pub fn new() -> Self {
+        Self {
+            day: Local::now().date_naive(),
+            positions: Vec::new(),
+            accounting: Accounting::new(Utc::now()),
+            target: None,
+            fee_model: None,
+            fills: 0,
+            liquidations: 0,
+            export_status: None,
+        }
+    }
+
+    pub fn apply(&mut self, event: &BackendEvent) {
+        self.roll_day();
+        match event {
+            BackendEvent::Positions(positions) => {
+                self.positions = positions.clone();
+            }
+            BackendEvent::Fill(fill) => {
+                let fill_day = DateTime::from_timestamp_millis(fill.created_at as i64)
+                    .map(|created_at| created_at.with_timezone(&Local).date_naive());
+                if fill_day == Some(self.day) {
+                    self.fills += 1;
+                }
+                self.accounting.add_fill(fill);
+            }
+            BackendEvent::TargetProgress(progress) => {
+                self.target = Some(progress.clone());
+            }
+            BackendEvent::FeeModel(fee_model) => {
+                self.fee_model = Some(*fee_model);
+            }
+            BackendEvent::Liquidation { .. } => {
+                self.liquidations += 1;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn today(&self) -> AccountingTotals {
+        self.accounting.day(self.day)
+    }
+
+    pub fn session(&self) -> AccountingTotals {
+        self.accounting.session()
+    }
+
+    pub fn positions(&self) -> &[Position] {
+        &self.positions
+    }
+
+    pub fn target(&self) -> Option<&TargetProgress> {
+        self.target.as_ref()
+    }
+
+    // Reset daily counters at midnight
+    fn roll_day(&mut self) {
+        let today = Local::now().date_naive();
+        if today != self.day {
+            self.day = today;
+            self.fills = 0;
+            self.liquidations = 0;
+        }
+    }
+
+    fn export_csv(&self) -> std::io::Result<String> {
+        let path = format!("accounting_{}.csv", Local::now().format("%Y%m%d_%H%M%S"));
+        self.accounting.write_csv(std::path::Path::new(&path))?;
+        Ok(path)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.heading("📊 Dashboard");
+        ui.label(
+            egui::RichText::new(self.day.format("%Y-%m-%d").to_string())
+                .color(egui::Color32::GRAY),
+        );
+        ui.add_space(5.0);
+
+        let today = self.today();
+        let session = self.session();
+        egui::Grid::new("dashboard_stats")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("");
+                ui.label(egui::RichText::new("Today").strong());
+                ui.label(egui::RichText::new("Session").strong());
+                ui.end_row();
+                totals_rows(ui, &[today, session]);
+            });
+        ui.horizontal(|ui| {
+            ui.label(format!("Fills today: {}", self.fills));
+            ui.separator();
+            ui.label(format!("Liquidations today: {}", self.liquidations));
+        });
+
+        if let Some(fee_model) = &self.fee_model {
+            ui.label(
+                egui::RichText::new(format!(
+                    "Fees: maker {:.4}% / taker {:.4}%",
+                    fee_model.maker_rate * 100.0,
+                    fee_model.taker_rate * 100.0
+                ))
+                .color(egui::Color32::GRAY),
+            );
+        }
+
+        if let Some(target) = &self.target {
+            ui.add_space(10.0);
+            show_target(ui, target);
+        }
+
+        ui.add_space(5.0);
+        ui.horizontal(|ui| {
+            if ui.button("💾 Export CSV").clicked() {
+                self.export_status = Some(match self.export_csv() {
+                    Ok(path) => format!("Exported to {}", path),
+                    Err(err) => format!("Export failed: {}", err),
+                });
+            }
+            if let Some(status) = &self.export_status {
+                ui.label(egui::RichText::new(status).color(egui::Color32::GRAY));
+            }
+        });
+
+        ui.add_space(10.0);
+        ui.label(egui::RichText::new(format!("Positions ({})", self.positions.len())).strong());
+
+        if self.positions.is_empty() {
+            ui.label(egui::RichText::new("No open position").color(egui::Color32::GRAY));
+        } else {
+            egui::Grid::new("dashboard_positions")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("Market");
+                    ui.label("Size");
+                    ui.label("Entry");
+                    ui.label("uPnL");
+                    ui.end_row();
+                    for position in &self.positions {
+                        ui.label(&position.market);
+                        ui.label(format!("{}", position.size));
+                        ui.label(format!("{:.2}", position.average_entry_price));
+                        let color = if position.unrealized_pnl < 0.0 {
+                            egui::Color32::RED
+                        } else {
+                            egui::Color32::GREEN
+                        };
+                        ui.colored_label(color, format!("{:.2}", position.unrealized_pnl));
+                        ui.end_row();
+                    }
+                });
+        }
+
+        ui.add_space(10.0);
+        egui::CollapsingHeader::new("By underlying")
+            .default_open(true)
+            .show(ui, |ui| grouped_grid(ui, "by_underlying", self.accounting.by_underlying()));
+        egui::CollapsingHeader::new("By market")
+            .show(ui, |ui| grouped_grid(ui, "by_market", self.accounting.by_market()));
+        egui::CollapsingHeader::new("By day").show(ui, |ui| {
+            let by_day = self
+                .accounting
+                .by_day()
+                .into_iter()
+                .map(|(day, totals)| (day.format("%Y-%m-%d").to_string(), totals))
+                .collect();
+            grouped_grid(ui, "by_day", by_day)
+        });
+    }
+}
+
+fn show_target(ui: &mut egui::Ui, target: &TargetProgress) {
+    ui.label(
+        egui::RichText::new(format!(
+            "Target of the {} (since {})",
+            target.period.as_str(),
+            target.period_start.with_timezone(&Local).format("%Y-%m-%d")
+        ))
+        .strong(),
+    );
+    if let Some(target_volume) = target.target_volume {
+        ui.add(
+            egui::ProgressBar::new((target.volume / target_volume).clamp(0.0, 1.0) as f32)
+                .text(format!("Volume ${:.0} / ${:.0}", target.volume, target_volume)),
+        );
+    }
+    if let Some(max_cost) = target.max_cost {
+        let used = if max_cost > 0.0 { target.cost / max_cost } else { 1.0 };
+        ui.add(
+            egui::ProgressBar::new(used.clamp(0.0, 1.0) as f32)
+                .fill(if used >= 1.0 { egui::Color32::RED } else { egui::Color32::DARK_GREEN })
+                .text(format!("Budget ${:.2} / ${:.2}", target.cost, max_cost)),
+        );
+    }
+    if let Some(reason) = target.stop_reason() {
+        ui.colored_label(
+            egui::Color32::YELLOW,
+            format!("Quoting stopped: {:?}", reason),
+        );
+    }
+}
+
+pub fn totals_rows(ui: &mut egui::Ui, columns: &[AccountingTotals]) {
+    let mut row = |name: &str, value: &dyn Fn(&AccountingTotals) -> String| {
+        ui.label(name);
+        for totals in columns {
+            ui.label(value(totals));
+        }
+        ui.end_row();
+    };
+    row("Volume", &|t| format!("${:.2}", t.volume));
+    row("Fees", &|t| format!("${:.2}", t.fees));
+    row("Spread loss", &|t| format!("${:.2}", t.spread_loss));
+    row("Net cost", &|t| format!("${:.2}", t.net_cost()));
+    row("Cost per $1k", &|t| {
+        t.cost_per_1k().map_or("-".to_string(), |cost| format!("${:.2}", cost))
+    });
+    row("Round trips", &|t| t.round_trips.to_string());
+}
+
+fn grouped_grid(ui: &mut egui::Ui, id: &str, groups: BTreeMap<String, AccountingTotals>) {
+    if groups.is_empty() {
+        ui.label(egui::RichText::new("No round trip").color(egui::Color32::GRAY));
+        return;
+    }
+    egui::Grid::new(id).num_columns(4).striped(true).show(ui, |ui| {
+        ui.label("");
+        ui.label("Volume");
+        ui.label("Net cost");
+        ui.label("Cost/$1k");
+        ui.end_row();
+        for (key, totals) in groups {
+            ui.label(key);
+            ui.label(format!("${:.2}", totals.volume));
+            ui.label(format!("${:.2}", totals.net_cost()));
+            ui.label(totals.cost_per_1k().map_or("-".to_string(), |cost| format!("${:.2}", cost)));
+            ui.end_row();
+        }
+    });
+}
@@ -0,0 +1,346 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use backend::{LogLevel, LogMessage, SharedLogLevel};
+use chrono::Local;
+use eframe::egui;
+
+pub const DEFAULT_LOG_CAPACITY: usize = 10_000;
+const MIN_LOG_CAPACITY: usize = 1_000;
+const MAX_LOG_CAPACITY: usize = 500_000;
+
+// File of the log directory receiving the logs evicted from the buffer, created at the first eviction
+struct LogSpill {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    count: usize,
+    error: Option<String>,
+}
+
+impl LogSpill {
+    fn new(directory: PathBuf) -> Self {
+        Self {
+            path: directory.join(format!("logs_spill_{}.txt", Local::now().format("%Y%m%d_%H%M%S"))),
+            writer: None,
+            count: 0,
+            error: None,
+        }
+    }
+
+    fn write(&mut self, log: &LogMessage) {
+        if self.writer.is_none() && self.error.is_none() {
+            // the directory is only created by the daily log files when they are enabled
+            let created = self.path.parent().map_or(Ok(()), std::fs::create_dir_all);
+            match created.and_then(|()| File::create(&self.path)) {
+                Ok(file) => self.writer = Some(BufWriter::new(file)),
+                Err(err) => self.error = Some(format!("Cannot create {}: {}", self.path.display(), err)),
+            }
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            match writeln!(writer, "{} [{}] {}", log.timestamp, log.level, log.message) {
+                Ok(()) => self.count += 1,
+                Err(err) => self.error = Some(format!("Cannot write {}: {}", self.path.display(), err)),
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writer.flush();
+        }
+    }
+}
+
+// Levels and searches of the view
+#[derive(Clone, PartialEq)]
+struct LogFilter {
+    // Shown levels, same order as LogLevel::ALL
+    levels: [bool; 4],
+    // Lowercase
+    search: String,
+    id_search: String,
+}
+
+impl LogFilter {
+    fn matches(&self, log: &LogMessage) -> bool {
+        let level_shown = LogLevel::ALL
+            .iter()
+            .position(|level| level.as_str() == log.level)
+            .is_none_or(|index| self.levels[index]);
+        level_shown
+            && (self.search.is_empty() || log.message.to_lowercase().contains(&self.search))
+            && (self.id_search.is_empty() || log.message.contains(&self.id_search))
+    }
+}
+
+pub struct LogPanel {
+    // Most recent logs, oldest ones are spilled to disk
+    logs: VecDeque<LogMessage>,
+    // Logs removed from the front of the buffer since the start: log n is at index n - removed
+    removed: usize,
+    // Numbers of the logs passing `shown_filter`, checked up to log `checked` (excluded). Only the
+    // new logs are filtered at each frame, all of them again when the filter changes.
+    shown: VecDeque<usize>,
+    shown_filter: Option<LogFilter>,
+    checked: usize,
+    capacity: usize,
+    spill: LogSpill,
+    // Minimum level of the back-end loggers
+    log_level: SharedLogLevel,
+    // Logs received while the view is paused
+    pending: VecDeque<LogMessage>,
+    auto_scroll: bool,
+    paused: bool,
+    // Shown levels, same order as LogLevel::ALL
+    shown_levels: [bool; 4],
+    search: String,
+    id_search: String,
+    export_status: Option<String>,
+}
+
+impl LogPanel {
+    pub fn new(log_level: SharedLogLevel, capacity: usize, spill_directory: PathBuf) -> Self {
+        Self {
+            logs: VecDeque::new(),
+            removed: 0,
+            shown: VecDeque::new(),
+            shown_filter: None,
+            checked: 0,
+            capacity: capacity.clamp(MIN_LOG_CAPACITY, MAX_LOG_CAPACITY),
+            spill: LogSpill::new(spill_directory),
+            log_level,
+            pending: VecDeque::new(),
+            auto_scroll: true,
+            paused: false,
+            shown_levels: [true; 4],
+            search: String::new(),
+            id_search: String::new(),
+            export_status: None,
+        }
+    }
+
+    pub fn log_level(&self) -> LogLevel {
+        self.log_level.get()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn push(&mut self, log: LogMessage) {
+        if self.paused {
+            self.pending.push_back(log);
+            // Held logs are bounded too
+            if self.pending.len() > self.capacity
+                && let Some(oldest) = self.pending.pop_front()
+            {
+                self.store(oldest);
+            }
+        } else {
+            self.store(log);
+        }
+    }
+
+    // Push into the buffer, spilling the oldest logs beyond capacity
+    fn store(&mut self, log: LogMessage) {
+        self.logs.push_back(log);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.logs.len() > self.capacity {
+            if let Some(oldest) = self.logs.pop_front() {
+                self.removed += 1;
+                self.spill.write(&oldest);
+            }
+        }
+    }
+
+    // Called once per frame, after the new logs are pushed
+    pub fn flush(&mut self) {
+        self.spill.flush();
+    }
+
+    fn filter(&self) -> LogFilter {
+        LogFilter {
+            levels: self.shown_levels,
+            search: self.search.trim().to_lowercase(),
+            id_search: self.id_search.trim().to_string(),
+        }
+    }
+
+    // Bring the shown logs up to date with the buffer and the filter
+    fn refresh_shown(&mut self) {
+        let filter = self.filter();
+        if self.shown_filter.as_ref() != Some(&filter) {
+            self.shown.clear();
+            self.checked = self.removed;
+        }
+        while self.shown.front().is_some_and(|number| *number < self.removed) {
+            self.shown.pop_front();
+        }
+        self.checked = self.checked.max(self.removed);
+        let end = self.removed + self.logs.len();
+        for number in self.checked..end {
+            if filter.matches(&self.logs[number - self.removed]) {
+                self.shown.push_back(number);
+            }
+        }
+        self.checked = end;
+        self.shown_filter = Some(filter);
+    }
+
+    fn shown_log(&self, row: usize) -> &LogMessage {
+        &self.logs[self.shown[row] - self.removed]
+    }
+
+    // Write the filtered view into a text file of the working directory
+    fn export(&self) -> std::io::Result<String> {
+        let path = format!("logs_export_{}.txt", Local::now().format("%Y%m%d_%H%M%S"));
+        let mut file = std::fs::File::create(&path)?;
+        for row in 0..self.shown.len() {
+            let log = self.shown_log(row);
+            writeln!(file, "{} [{}] {}", log.timestamp, log.level, log.message)?;
+        }
+        Ok(path)
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        ui.heading("📝 Logs");
+
+        // Logs passing the filters
+        self.refresh_shown();
+
+        ui.horizontal(|ui| {
+            ui.label(format!("Shown: {} / {} logs", self.shown.len(), self.logs.len()));
+            ui.separator();
+            ui.label("Buffer:");
+            let mut capacity = self.capacity;
+            ui.add(
+                egui::DragValue::new(&mut capacity)
+                    .range(MIN_LOG_CAPACITY..=MAX_LOG_CAPACITY)
+                    .speed(100),
+            );
+            if capacity != self.capacity {
+                self.capacity = capacity;
+                self.evict();
+            }
+            ui.checkbox(&mut self.auto_scroll, "Auto-scroll");
+            let mut level = self.log_level.get();
+            egui::ComboBox::from_label("Min level")
+                .selected_text(level.as_str())
+                .show_ui(ui, |ui| {
+                    for choice in LogLevel::ALL {
+                        ui.selectable_value(&mut level, choice, choice.as_str());
+                    }
+                });
+            if level != self.log_level.get() {
+                self.log_level.set(level);
+            }
+            if ui.button("🗑 Clean").clicked() {
+                self.removed += self.logs.len();
+                self.logs.clear();
+                self.pending.clear();
+            }
+            if ui.button("💾 Export").clicked() {
+                self.export_status = Some(match self.export() {
+                    Ok(path) => format!("Exported to {}", path),
+                    Err(err) => format!("Export failed: {}", err),
+                });
+            }
+            if let Some(status) = &self.export_status {
+                ui.label(egui::RichText::new(status).color(egui::Color32::GRAY));
+            }
+        });
+
+        // Older logs are retrievable in the spill file
+        if let Some(error) = &self.spill.error {
+            ui.colored_label(egui::Color32::RED, error);
+        } else if self.spill.count > 0 {
+            ui.label(
+                egui::RichText::new(format!(
+                    "{} older logs spilled to {}",
+                    self.spill.count,
+                    self.spill.path.display()
+                ))
+                .color(egui::Color32::GRAY),
+            );
+        }
+        // Logs sent faster than the frames receive them
+        let dropped = backend::logging::dropped_logs();
+        if dropped > 0 {
+            ui.colored_label(
+                egui::Color32::YELLOW,
+                format!("{} logs dropped by a full channel, see the daily log file", dropped),
+            );
+        }
+
+        ui.horizontal(|ui| {
+            for (index, level) in LogLevel::ALL.iter().enumerate() {
+                ui.toggle_value(&mut self.shown_levels[index], level.as_str());
+            }
+            ui.separator();
+            ui.add(
+                egui::TextEdit::singleline(&mut self.search)
+                    .hint_text("🔍 Search")
+                    .desired_width(180.0),
+            );
+            ui.add(
+                egui::TextEdit::singleline(&mut self.id_search)
+                    .hint_text("Market / order id")
+                    .desired_width(180.0),
+            );
+            if self.paused {
+                ui.label(
+                    egui::RichText::new(format!("⏸ Paused ({} new)", self.pending.len()))
+                        .color(egui::Color32::YELLOW),
+                );
+            }
+        });
+
+        ui.add_space(5.0);
+
+        // Buffer resized or cleaned, filters edited above
+        self.refresh_shown();
+
+        // Log zone with scrolling, only visible rows are laid out
+        let row_height = ui.text_style_height(&egui::TextStyle::Body) + ui.spacing().item_spacing.y;
+        let output = egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .stick_to_bottom(self.auto_scroll && !self.paused)
+            .show_rows(ui, row_height, self.shown.len(), |ui, row_range| {
+                for row in row_range {
+                    let log = self.shown_log(row);
+                    ui.horizontal(|ui| {
+                        // Different color for levels
+                        let (color, emoji) = match log.level.as_str() {
+                            "ERROR" => (egui::Color32::RED, "❌"),
+                            "WARN" => (egui::Color32::YELLOW, "⚠️"),
+                            "INFO" => (egui::Color32::GREEN, "ℹ️"),
+                            "DEBUG" => (egui::Color32::GRAY, "🔍"),
+                            _ => (egui::Color32::WHITE, "📝"),
+                        };
+
+                        ui.colored_label(egui::Color32::DARK_GRAY, &log.timestamp);
+                        ui.label(emoji);
+                        ui.colored_label(color, format!("[{}]", log.level));
+                        // One line per log so that rows keep the same height
+                        ui.add(egui::Label::new(&log.message).truncate());
+                    });
+                }
+            });
+
+        // Pause while the pointer is over the logs, then release held logs
+        let hovered = ui.rect_contains_pointer(output.inner_rect);
+        if hovered != self.paused {
+            self.paused = hovered;
+            if !hovered {
+                while let Some(log) = self.pending.pop_front() {
+                    self.store(log);
+                }
+            }
+        }
+    }
+}
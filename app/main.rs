@@ -1,75 +1,421 @@
 use eframe::egui;
-use crossbeam::channel::{unbounded, Receiver};
-use backend::LogMessage;
+use crossbeam::channel::{bounded, unbounded, Receiver, Sender};
+use backend::config::{BotConfig, CONFIG_FILE, Environment};
+use backend::{LogLevel, LogMessage, SharedLogLevel};
+use backend::events::BackendEvent;
+use backend::keystore::{self, AccountKeys, KeystoreConfig};
+use backend::strategy::StrategyParams;
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use zeroize::{Zeroize, Zeroizing};
+
+mod book_viewer;
+mod accounts;
+mod dashboard;
+mod log_panel;
+use book_viewer::BookViewer;
+use accounts::AccountDashboards;
+use log_panel::{DEFAULT_LOG_CAPACITY, LogPanel};
 
 const PROG_VERSION: &str = env!("CARGO_PKG_VERSION");
+// Keys of the persisted log settings
+const LOG_LEVEL_KEY: &str = "log_level";
+const LOG_CAPACITY_KEY: &str = "log_capacity";
+// Maximum wait of the back-end shutdown sequence when the window is closed
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15);
+// Logs waiting for the front-end, the next ones are dropped (still in the daily file)
+const LOG_CHANNEL_CAPACITY: usize = 50_000;
 
 fn main() -> Result<(), eframe::Error> {
     // receive back-end logs
-    let (log_sender, log_receiver) = unbounded::<LogMessage>();
-    
+    let (log_sender, log_receiver) = bounded::<LogMessage>(LOG_CHANNEL_CAPACITY);
+    // receive back-end events (positions, fills...)
+    let (event_sender, event_receiver) = unbounded::<BackendEvent>();
+    // market selected in the order book viewer
+    let (market_sender, market_receiver) = unbounded::<Option<String>>();
+    let viewer_log_sender = log_sender.clone();
+    let viewer_event_sender = event_sender.clone();
+    // minimum log level, changed at runtime by the frontend
+    let log_level = SharedLogLevel::new(LogLevel::Info);
+    let backend_log_level = log_level.clone();
+
+    // daily log files + logs of dependencies
+    let (config, config_error) = match BotConfig::load(std::path::Path::new(CONFIG_FILE)) {
+        Ok(config) => (config, None),
+        Err(err) => (BotConfig::default(), Some(err)),
+    };
+    if let Err(err) = backend::logging::init_logging(log_sender.clone(), log_level.clone(), &config.logging) {
+        eprintln!("{}", err);
+    }
+    if let Some(err) = config_error {
+        log::error!("{}, using default configuration", err);
+    }
+    let viewer_log_level = log_level.clone();
+
+    // stop the back-end on window close or signal
+    let shutdown = CancellationToken::new();
+    let backend_shutdown = shutdown.clone();
+    let stopped_sender = event_sender.clone();
+    // keys unlocked and production trading confirmed in the front-end (Some) or refused (None)
+    let environment = config.exchange.environment;
+    let log_directory = config.logging.directory.clone();
+    // once: the URLs of a custom endpoint live until the end of the process
+    let url = config.exchange.leak_url();
+    let strategy = config.strategy;
+    let needs_confirmation = environment == Environment::Production && config.exchange.confirm_production;
+    let locked = keystore::is_locked(&config.keystore).unwrap_or_else(|err| {
+        log::error!("{}", err);
+        false
+    });
+    let (start_sender, start_receiver) = bounded::<Option<AccountKeys>>(1);
+    let start_dialog = if needs_confirmation || locked {
+        Some(StartDialog {
+            sender: start_sender,
+            production: needs_confirmation,
+            keystore: config.keystore.clone(),
+            locked,
+            password: Zeroizing::new(String::new()),
+            error: None,
+        })
+    } else {
+        match keystore::unlock_keys(&config.keystore, None) {
+            Ok(keys) => {
+                let _ = start_sender.send(Some(keys));
+            }
+            Err(err) => {
+                log::error!("{}", err);
+                let _ = start_sender.send(None);
+            }
+        }
+        None
+    };
+
     // launch back end in seperated thread
-    std::thread::spawn(|| {
+    let backend_thread = std::thread::spawn(move || {
         let rt = tokio::runtime::Runtime::new().unwrap();
-        rt.block_on(async {
-            tokio::task::spawn_blocking(|| {
+        rt.block_on(async move {
+            let signal_shutdown = backend_shutdown.clone();
+            tokio::spawn(async move {
+                let signal = backend::shutdown::shutdown_signal().await;
+                log::info!("{} received.", signal);
+                signal_shutdown.cancel();
+            });
+            tokio::spawn(backend::book_viewer::run_book_viewer(
+                viewer_log_sender,
+                market_receiver,
+                viewer_event_sender,
+                viewer_log_level,
+                url,
+                backend_shutdown.clone(),
+            ));
+            let recorder = config.recorder.enabled.then(|| {
+                let logger = backend::Logger::with_shared_level(log_sender.clone(), backend_log_level.clone());
+                let recorder_config = config.recorder.clone();
+                let recorder_shutdown = backend_shutdown.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = backend::recorder::run_recorder(
+                        logger.clone(),
+                        recorder_config,
+                        url,
+                        backend::clock::Clock::System,
+                        recorder_shutdown,
+                    )
+                    .await
+                    {
+                        logger.error(err);
+                    }
+                })
+            });
+            let recorder_shutdown = backend_shutdown.clone();
+            let _ = tokio::task::spawn_blocking(move || {
+                let Ok(Some(keys)) = start_receiver.recv() else {
+                    log::warn!("Bot not started.");
+                    return;
+                };
                 let rt2 = tokio::runtime::Handle::current();
                 rt2.block_on(async {
-                    backend::run_backend_logic(log_sender).await;
+                    if let Err(err) = backend::run_accounts(
+                        log_sender,
+                        event_sender,
+                        backend_log_level,
+                        config,
+                        url,
+                        keys,
+                        backend_shutdown,
+                    )
+                    .await
+                    {
+                        log::error!("{}", err);
+                    }
                 });
-            }).await.unwrap();
+            }).await;
+            let _ = stopped_sender.send(BackendEvent::Stopped);
+            // the recording goes on until the window is closed
+            if let Some(recorder) = recorder {
+                recorder_shutdown.cancelled().await;
+                let _ = recorder.await;
+            }
         });
     });
 
     // launch frontend
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([900.0, 700.0])
-            .with_title("Bot Paradex"),
+            .with_inner_size([1200.0, 700.0])
+            .with_title(format!("Bot Paradex ({})", environment.as_str())),
         ..Default::default()
     };
 
     eframe::run_native(
         "Bot Paradex",
         options,
-        Box::new(|_cc| Ok(Box::new(MyApp::new(log_receiver)))),
+        Box::new(|cc| {
+            // restore the level selected during the previous launch
+            if let Some(level) = cc
+                .storage
+                .and_then(|storage| storage.get_string(LOG_LEVEL_KEY))
+                .and_then(|name| LogLevel::from_name(&name))
+            {
+                log_level.set(level);
+            }
+            let log_capacity = cc
+                .storage
+                .and_then(|storage| storage.get_string(LOG_CAPACITY_KEY))
+                .and_then(|capacity| capacity.parse().ok())
+                .unwrap_or(DEFAULT_LOG_CAPACITY);
+            let backend = BackendLink {
+                log_receiver,
+                event_receiver,
+                market_sender,
+                strategy,
+                shutdown,
+                backend_thread,
+                environment,
+                log_directory,
+                start_dialog,
+            };
+            Ok(Box::new(MyApp::new(backend, log_level, log_capacity)))
+        }),
     )
 }
 
+// Channels and controls of the back-end thread
+struct BackendLink {
+    log_receiver: Receiver<LogMessage>,
+    event_receiver: Receiver<BackendEvent>,
+    market_sender: Sender<Option<String>>,
+    strategy: StrategyParams,
+    shutdown: CancellationToken,
+    backend_thread: JoinHandle<()>,
+    environment: Environment,
+    // Directory of the log spill file
+    log_directory: PathBuf,
+    start_dialog: Option<StartDialog>,
+}
+
+// Keystore password and production confirmation asked before starting the bot
+struct StartDialog {
+    sender: Sender<Option<AccountKeys>>,
+    production: bool,
+    keystore: KeystoreConfig,
+    // Password required
+    locked: bool,
+    password: Zeroizing<String>,
+    error: Option<String>,
+}
+
 struct MyApp {
     log_receiver: Receiver<LogMessage>,
-    logs: Vec<LogMessage>,
-    auto_scroll: bool,
+    event_receiver: Receiver<BackendEvent>,
+    log_panel: LogPanel,
+    dashboards: AccountDashboards,
+    book_viewer: BookViewer,
     show_readme: bool,
+    show_book_viewer: bool,
     readme_content: String,
+    shutdown: CancellationToken,
+    // Close requested, waiting for the back-end shutdown sequence
+    closing_since: Option<Instant>,
+    // Bot loop ended, the back-end thread ends on close (recorder)
+    bot_stopped: bool,
+    backend_thread: JoinHandle<()>,
+    closed: bool,
+    environment: Environment,
+    // Start waiting for the password or the confirmation of the user
+    start_dialog: Option<StartDialog>,
 }
 
 impl MyApp {
-    fn new(log_receiver: Receiver<LogMessage>) -> Self {
+    fn new(backend: BackendLink, log_level: SharedLogLevel, log_capacity: usize) -> Self {
         // Load README content
         let readme_content = std::fs::read_to_string("README.md")
             .unwrap_or_else(|_| "README.md not found".to_string());
 
         Self {
-            log_receiver,
-            logs: Vec::new(),
-            auto_scroll: true,
+            log_receiver: backend.log_receiver,
+            event_receiver: backend.event_receiver,
+            log_panel: LogPanel::new(log_level, log_capacity, backend.log_directory),
+            dashboards: AccountDashboards::new(),
+            book_viewer: BookViewer::new(backend.market_sender, backend.strategy),
             show_readme: false,
+            show_book_viewer: false,
             readme_content,
+            shutdown: backend.shutdown,
+            closing_since: None,
+            bot_stopped: false,
+            backend_thread: backend.backend_thread,
+            closed: false,
+            environment: backend.environment,
+            start_dialog: backend.start_dialog,
         }
     }
 }
 
+impl MyApp {
+    fn show_start_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = self.start_dialog.as_mut() else {
+            return;
+        };
+        let mut answer = None;
+        let title = if dialog.locked { "Unlock keystore" } else { "Production" };
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                if dialog.production {
+                    ui.label(environment_badge(self.environment));
+                    ui.label("The bot will trade on Paradex production with real funds.");
+                    ui.add_space(10.0);
+                }
+                let mut submitted = false;
+                if dialog.locked {
+                    ui.label(format!("Password of {}", dialog.keystore.path.display()));
+                    let response = ui.add(egui::TextEdit::singleline(&mut *dialog.password).password(true));
+                    submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if let Some(error) = &dialog.error {
+                        ui.colored_label(egui::Color32::from_rgb(220, 50, 50), error);
+                    }
+                    ui.add_space(10.0);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button("Start the bot").clicked() || submitted {
+                        answer = Some(true);
+                    }
+                    if ui.button("Don't start").clicked() {
+                        answer = Some(false);
+                    }
+                });
+            });
+        let keys = match answer {
+            None => return,
+            Some(false) => None,
+            Some(true) => {
+                let password = dialog.locked.then_some(dialog.password.as_str());
+                match keystore::unlock_keys(&dialog.keystore, password) {
+                    Ok(keys) => Some(keys),
+                    // wrong password: ask again
+                    Err(err) if dialog.locked => {
+                        dialog.error = Some(err);
+                        dialog.password.zeroize();
+                        return;
+                    }
+                    Err(err) => {
+                        log::error!("{}", err);
+                        None
+                    }
+                }
+            }
+        };
+        if let Some(dialog) = self.start_dialog.take() {
+            let _ = dialog.sender.send(keys);
+        }
+    }
+}
+
+// Environment shown in the header, production in red
+fn environment_badge(environment: Environment) -> egui::RichText {
+    let color = match environment {
+        Environment::Production => egui::Color32::from_rgb(220, 50, 50),
+        Environment::Testnet => egui::Color32::from_rgb(50, 170, 80),
+    };
+    egui::RichText::new(environment.as_str().to_uppercase())
+        .size(14.0)
+        .strong()
+        .color(color)
+}
+
 impl eframe::App for MyApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(LOG_LEVEL_KEY, self.log_panel.log_level().as_str().to_string());
+        storage.set_string(LOG_CAPACITY_KEY, self.log_panel.capacity().to_string());
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.shutdown.cancel();
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Get new logs
         while let Ok(log) = self.log_receiver.try_recv() {
-            self.logs.push(log);
+            self.log_panel.push(log);
+        }
+        self.log_panel.flush();
+        // Get new events
+        while let Ok(event) = self.event_receiver.try_recv() {
+            if let BackendEvent::Stopped = event {
+                self.bot_stopped = true;
+            }
+            self.dashboards.apply(&event);
+            self.book_viewer.apply(&event);
+        }
+
+        // Window close: keep the window until the back-end has stopped (or timeout)
+        if ctx.input(|i| i.viewport().close_requested()) && !self.closed {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.shutdown.cancel();
+            if let Some(dialog) = self.start_dialog.take() {
+                let _ = dialog.sender.send(None);
+            }
+        }
+
+        // Keystore password, production start confirmation
+        self.show_start_dialog(ctx);
+        if self.shutdown.is_cancelled() && self.closing_since.is_none() {
+            self.closing_since = Some(Instant::now());
+        }
+        if let Some(closing_since) = self.closing_since {
+            if self.backend_thread.is_finished() || closing_since.elapsed() > SHUTDOWN_TIMEOUT {
+                self.closed = true;
+                ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+            }
+            egui::Window::new("Shutting down")
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+                .show(ctx, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.spinner();
+                        ui.label("Stopping the bot: cancelling orders, closing subscriptions...");
+                    });
+                });
         }
 
         // Refresh
         ctx.request_repaint_after(std::time::Duration::from_millis(100));
 
+        // Dashboard on the right side
+        egui::SidePanel::right("dashboard_panel")
+            .resizable(true)
+            .default_width(320.0)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.dashboards.show(ui);
+                });
+            });
+
         egui::CentralPanel::default().show(ctx, |ui| {
             let available_height = ui.available_height();
             
@@ -101,57 +447,50 @@ impl eframe::App for MyApp {
                         ui.label(egui::RichText::new("MIT License")
                             .size(14.0)
                             .color(egui::Color32::DARK_GRAY));
+                        ui.label("|");
+                        ui.label(environment_badge(self.environment));
+                        if self.bot_stopped {
+                            ui.label("|");
+                            ui.label(egui::RichText::new("BOT STOPPED")
+                                .size(14.0)
+                                .strong()
+                                .color(egui::Color32::GRAY));
+                        }
                     });
                     
                     ui.add_space(15.0);
                     
-                    if ui.button(egui::RichText::new("📖 Show README").size(16.0)).clicked() {
-                        self.show_readme = !self.show_readme;
-                    }
+                    ui.horizontal(|ui| {
+                        if ui.button(egui::RichText::new("📖 Show README").size(16.0)).clicked() {
+                            self.show_readme = !self.show_readme;
+                        }
+                        if ui.button(egui::RichText::new("📚 Order book").size(16.0)).clicked() {
+                            self.show_book_viewer = !self.show_book_viewer;
+                        }
+                    });
                 },
             );
             
             ui.separator();
 
             // Logs section (1/4 of space at bottom)
-            ui.heading("📝 Logs");
-            
-            ui.horizontal(|ui| {
-                ui.label(format!("Total: {} logs", self.logs.len()));
-                ui.separator();
-                ui.checkbox(&mut self.auto_scroll, "Auto-scroll");
-                if ui.button("🗑 Clean").clicked() {
-                    self.logs.clear();
-                }
-            });
-            
-            ui.add_space(5.0);
-
-            // Log zone with scrolling
-            egui::ScrollArea::vertical()
-                .auto_shrink([false; 2])
-                .stick_to_bottom(self.auto_scroll)
-                .show(ui, |ui| {
-                    for log in &self.logs {
-                        ui.horizontal(|ui| {
-                            // Different color for levels
-                            let (color, emoji) = match log.level.as_str() {
-                                "ERROR" => (egui::Color32::RED, "❌"),
-                                "WARN" => (egui::Color32::YELLOW, "⚠️"),
-                                "INFO" => (egui::Color32::GREEN, "ℹ️"),
-                                "DEBUG" => (egui::Color32::GRAY, "🔍"),
-                                _ => (egui::Color32::WHITE, "📝"),
-                            };
-
-                            ui.colored_label(egui::Color32::DARK_GRAY, &log.timestamp);
-                            ui.label(emoji);
-                            ui.colored_label(color, format!("[{}]", log.level));
-                            ui.label(&log.message);
-                        });
-                    }
-                });
+            self.log_panel.show(ui);
         });
 
+        // Order book viewer window
+        if self.show_book_viewer {
+            egui::Window::new("📚 Order book")
+                .open(&mut self.show_book_viewer)
+                .resizable(true)
+                .default_width(420.0)
+                .default_height(500.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        self.book_viewer.show(ui);
+                    });
+                });
+        }
+
         // README window
         if self.show_readme {
             egui::Window::new("📖 README")
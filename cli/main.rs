@@ -0,0 +1,537 @@
+use backend::LogLevel;
+use backend::clock::Clock;
+use backend::config::{Account, BotConfig, CONFIG_FILE, Environment};
+use backend::headless::{ConsoleLogs, read_secret};
+use backend::fees::FeeModel;
+use backend::journal::ReasonCode;
+use backend::keystore::{AccountKeys, Keystore};
+use backend::strategy::QuoteRules;
+use backend::sweep::{Dataset, SweepResult};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use paradex::url::URL;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use tokio_util::sync::CancellationToken;
+
+// Command line interface: routine operations without launching the trading loop
+
+const USAGE: &str = "Usage: cli [--config <file>] [--testnet | --production] [--account <name>] [--log-level <level>] [--yes] <command>
+
+Commands:
+  run                   run the bot loop (same as the daemon)
+  status                account summary, Option positions and open orders
+  cancel-all            cancel the Option orders
+  flatten               cancel the Option orders and sell every Option position at market
+  markets [underlying]  Option markets with best bid / ask and spread (e.g. markets BTC)
+  backtest <market> [from [to]]
+                        replay the recorded data of the market (dates YYYY-MM-DD, default all)
+  sweep <market...>     search the strategy parameters over the recorded days ([sweep] of the config)
+  record [market...]    record the market data of the markets (default recorder.markets) until Ctrl-C
+  config check          validate the configuration file
+  keys list             accounts with a key in the encrypted keystore
+  keys import <account> encrypt the L2 private key of an account into the keystore
+  keys remove <account> remove the key of an account from the keystore
+
+Options:
+  --config <file>       configuration file (default bot_paradex.toml)
+  --testnet             use Paradex testnet (key in PARADEX_TESTNET_L2_KEY)
+  --production          use Paradex production (key in PARADEX_L2_KEY)
+  --account <name>      only this account of the [[accounts]] (default: every account)
+  --log-level <level>   DEBUG, INFO, WARN or ERROR (default INFO for run and record, WARN otherwise)
+  --yes                 do not ask confirmation for cancel-all, flatten and run on production
+";
+
+struct Args {
+    config: PathBuf,
+    environment: Option<Environment>,
+    account: Option<String>,
+    log_level: Option<LogLevel>,
+    yes: bool,
+    command: Vec<String>,
+}
+
+impl Args {
+    fn parse(args: impl Iterator<Item = String>) -> Result<Args, String> {
+        let mut parsed = Args {
+            config: PathBuf::from(CONFIG_FILE),
+            environment: None,
+            account: None,
+            log_level: None,
+            yes: false,
+            command: Vec::new(),
+        };
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--config" => {
+                    let path = args.next().ok_or("--config requires a file")?;
+                    parsed.config = PathBuf::from(path);
+                }
+                "--account" => {
+                    let name = args.next().ok_or("--account requires a name")?;
+                    parsed.account = Some(name);
+                }
+                "--log-level" => {
+                    let name = args.next().ok_or("--log-level requires a level")?;
+                    let level = LogLevel::from_name(&name)
+                        .ok_or_else(|| format!("Unknown log level {}", name))?;
+                    parsed.log_level = Some(level);
+                }
+                "--testnet" => parsed.environment = Some(Environment::Testnet),
+                "--production" => parsed.environment = Some(Environment::Production),
+                "--yes" | "-y" => parsed.yes = true,
+                option if option.starts_with('-') => {
+                    return Err(format!("Unknown option {}", option));
+                }
+                _ => parsed.command.push(arg),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = match Args::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+    let command: Vec<&str> = args.command.iter().map(String::as_str).collect();
+    if matches!(command.as_slice(), [] | ["help"]) {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+    if command == ["config", "check"] {
+        return config_check(&args);
+    }
+
+    let mut config = match load_config(&args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    if let ["keys", ..] = command.as_slice() {
+        return match keys_command(&args, &config, &command[1..]) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(err) => {
+                eprintln!("{}", err);
+                ExitCode::FAILURE
+            }
+        };
+    }
+    // --account: the other accounts are left out of every command, run included
+    if let Some(name) = args.account.as_deref()
+        && let Err(err) = config.keep_account(name)
+    {
+        eprintln!("{}", err);
+        return ExitCode::FAILURE;
+    }
+    // once: the URLs of a custom endpoint live until the end of the process
+    let url = config.exchange.leak_url();
+    if command == ["run"] {
+        let log_level = args.log_level.unwrap_or(LogLevel::Info);
+        return backend::headless::run(config, url, log_level, args.yes).await;
+    }
+    let accounts = config.accounts();
+    // private commands
+    let keys = if matches!(command.as_slice(), ["status"] | ["cancel-all"] | ["flatten"]) {
+        match backend::headless::unlock_keys(&config.keystore) {
+            Ok(keys) => keys,
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::FAILURE;
+            }
+        }
+    } else {
+        AccountKeys::default()
+    };
+
+    let default_level = if let ["record", ..] = command.as_slice() { LogLevel::Info } else { LogLevel::Warn };
+    let console = ConsoleLogs::start(args.log_level.unwrap_or(default_level), &config.logging);
+    let result = match command.as_slice() {
+        ["status"] => {
+            for_each_account(&accounts, |account| status(&config, url, account, keys.get(account))).await
+        }
+        ["cancel-all"] => {
+            if confirm(&args, "Cancel every Option order?") {
+                for_each_account(&accounts, |account| {
+                    cancel_all(&console, url, account, keys.get(account))
+                })
+                .await
+            } else {
+                Ok(())
+            }
+        }
+        ["flatten"] => {
+            if confirm(&args, "Cancel the Option orders and sell every Option position at market?") {
+                for_each_account(&accounts, |account| {
+                    flatten(&console, url, account, keys.get(account))
+                })
+                .await
+            } else {
+                Ok(())
+            }
+        }
+        ["markets"] => markets(url, None).await,
+        ["markets", underlying] => markets(url, Some(underlying)).await,
+        ["backtest", market, dates @ ..] if dates.len() <= 2 => backtest(&config, &accounts[0], market, dates),
+        ["sweep", markets @ ..] if !markets.is_empty() => sweep(&config, &accounts[0], markets),
+        ["record", markets @ ..] => record(&console, &config, url, markets).await,
+        _ => Err(format!("Unknown command {}\n\n{}", command.join(" "), USAGE)),
+    };
+    console.finish();
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+// Config file with the environment of the command line
+fn load_config(args: &Args) -> Result<BotConfig, String> {
+    let mut config = BotConfig::load(&args.config)?;
+    if let Some(environment) = args.environment {
+        config.exchange.environment = environment;
+    }
+    Ok(config)
+}
+
+// Run the command for each account, going on after an error
+async fn for_each_account<'a, F, Fut>(accounts: &'a [Account], command: F) -> Result<(), String>
+where
+    F: Fn(&'a Account) -> Fut,
+    Fut: std::future::Future<Output = Result<(), String>>,
+{
+    let mut errors = Vec::new();
+    for account in accounts {
+        if accounts.len() > 1 {
+            println!("== Account {} ==", account.name);
+        }
+        if let Err(err) = command(account).await {
+            errors.push(format!("{}: {}", account.name, err));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("\n"))
+    }
+}
+
+fn confirm(args: &Args, question: &str) -> bool {
+    if args.yes || backend::headless::ask_confirmation(question) {
+        true
+    } else {
+        println!("Aborted.");
+        false
+    }
+}
+
+fn config_check(args: &Args) -> ExitCode {
+    if !args.config.exists() {
+        println!("{} not found, default configuration used.", args.config.display());
+    }
+    let config = match load_config(args) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    println!("{:#?}", config);
+    match Keystore::load(&config.keystore.path) {
+        Ok(keystore) => {
+            for account in config.accounts() {
+                let in_keystore = keystore.entries().iter().any(|entry| entry.name == account.name);
+                let in_env = config.keystore.env_fallback && std::env::var(&account.key_var).is_ok();
+                if !in_keystore && !in_env {
+                    println!("Warning: no key for account {}.", account.name);
+                }
+            }
+        }
+        Err(err) => println!("Warning: {}", err),
+    }
+    let problems = config.problems();
+    if problems.is_empty() {
+        println!("Configuration OK.");
+        ExitCode::SUCCESS
+    } else {
+        for problem in problems {
+            eprintln!("Error: {}", problem);
+        }
+        ExitCode::FAILURE
+    }
+}
+
+async fn status(config: &BotConfig, url: URL, account: &Account, l2_key: Option<String>) -> Result<(), String> {
+    let l2_key = l2_key.ok_or_else(|| format!("No L2 key for account {}", account.name))?;
+    let client_private = backend::connect_client(url, Some(l2_key)).await?;
+
+    let information = client_private
+        .account_information()
+        .await
+        .map_err(|err| format!("Failed to fetch account: {}", err))?;
+    println!(
+        "Account {} ({:?}) on {}",
+        information.account,
+        information.status,
+        config.exchange.describe()
+    );
+    println!("  account value:   {:.2} {}", information.account_value, information.settlement_asset);
+    println!("  free collateral: {:.2} {}", information.free_collateral, information.settlement_asset);
+    println!("  margin cushion:  {:.2}", information.margin_cushion);
+
+    let positions = client_private
+        .positions()
+        .await
+        .map_err(|err| format!("Failed to fetch positions: {}", err))?;
+    let positions = backend::open_option_positions(positions);
+    println!("\nOption open positions: {}", positions.len());
+    for position in positions {
+        println!(
+            "  {:<24} {:?} {:>10} @ {:>10.2}  uPnL {:>10.2}",
+            position.market,
+            position.side,
+            position.size,
+            position.average_entry_price,
+            position.unrealized_pnl
+        );
+    }
+
+    let orders = client_private
+        .open_orders()
+        .await
+        .map_err(|err| format!("Failed to fetch orders: {}", err))?;
+    println!("\nOpen orders: {}", orders.results.len());
+    for order in orders.results {
+        println!(
+            "  {:<24} {:?} {:>10} @ {:>10}  {}",
+            order.market,
+            order.side,
+            order.remaining_size,
+            order.price.map(|price| price.to_string()).unwrap_or_else(|| "market".to_string()),
+            order.id
+        );
+    }
+    Ok(())
+}
+
+async fn cancel_all(
+    console: &ConsoleLogs,
+    url: URL,
+    account: &Account,
+    l2_key: Option<String>,
+) -> Result<(), String> {
+    let logger = console.logger();
+    let l2_key = l2_key.ok_or_else(|| format!("No L2 key for account {}", account.name))?;
+    backend::headless::cancel_bot_orders(&logger, url, account, Some(l2_key), ReasonCode::Manual)
+        .await?;
+    println!("Option orders cancelled.");
+    Ok(())
+}
+
+async fn flatten(
+    console: &ConsoleLogs,
+    url: URL,
+    account: &Account,
+    l2_key: Option<String>,
+) -> Result<(), String> {
+    let logger = console.logger();
+    let journal = backend::open_journal(&logger, &account.journal, &Clock::System, Clock::System.now());
+    let l2_key = l2_key.ok_or_else(|| format!("No L2 key for account {}", account.name))?;
+    let mut exchange = backend::exchange::connect(&logger, url, Some(l2_key), Clock::System).await?;
+    let sold = backend::flatten_option_positions(&logger, &journal, &mut exchange, ReasonCode::Manual).await?;
+    println!("Option positions sold: {}", sold);
+    Ok(())
+}
+
+// Public data, no key needed
+async fn markets(url: URL, underlying: Option<&str>) -> Result<(), String> {
+    let client_private = backend::connect_client(url, None).await?;
+    let mut markets: Vec<String> = client_private
+        .markets()
+        .await
+        .map_err(|err| format!("Failed to fetch markets: {}", err))?
+        .into_iter()
+        .filter(|market| market.option_type.is_some())
+        .filter(|market| underlying.is_none_or(|underlying| market.base_currency.eq_ignore_ascii_case(underlying)))
+        .map(|market| market.symbol)
+        .collect();
+    markets.sort();
+
+    println!(
+        "{:<24} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "market", "bid size", "bid", "ask", "ask size", "spread"
+    );
+    for market in markets {
+        match client_private.bbo(market.clone()).await {
+            Ok(bbo) => println!(
+                "{:<24} {:>10} {:>10.2} {:>10.2} {:>10} {:>10.2}",
+                market,
+                bbo.bid_size,
+                bbo.bid,
+                bbo.ask,
+                bbo.ask_size,
+                bbo.ask - bbo.bid
+            ),
+            Err(err) => println!("{:<24} {}", market, err),
+        }
+    }
+    Ok(())
+}
+
+// Strategy of the config over the recorded data, fees of the account
+fn backtest(config: &BotConfig, account: &Account, market: &str, dates: &[&str]) -> Result<(), String> {
+    let day = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map(|date| date.and_time(NaiveTime::MIN).and_utc())
+            .map_err(|err| format!("Invalid date {}: {}", date, err))
+    };
+    let from = match dates.first() {
+        Some(date) => day(date)?,
+        None => DateTime::<Utc>::MIN_UTC,
+    };
+    let to = match dates.get(1) {
+        Some(date) => day(date)? + chrono::Duration::days(1),
+        None => DateTime::<Utc>::MAX_UTC,
+    };
+    let directory = &config.recorder.directory;
+    let records = backend::recorder::read_records(directory, Some(market), from, to)
+        .map_err(|err| format!("Cannot read recordings in {}: {}", directory.display(), err))?;
+    if records.is_empty() {
+        return Err(format!("No recorded data of {} in {}", market, directory.display()));
+    }
+    let rules = QuoteRules {
+        params: config.strategy,
+        fee_model: FeeModel::from_config(&account.fees),
+        max_cost_per_1k: account.fees.max_cost_per_1k,
+    };
+    let stats = backend::backtest::run_backtest(&records, &rules, &config.backtest);
+    println!(
+        "{} from {} to {}, {} records",
+        market,
+        records[0].received_at.format("%Y-%m-%d %H:%M:%S"),
+        records[records.len() - 1].received_at.format("%Y-%m-%d %H:%M:%S"),
+        records.len()
+    );
+    println!("{:?}", rules.params);
+    print!("{}", stats);
+    Ok(())
+}
+
+// Grid / random search of the strategy parameters with out-of-sample validation
+fn sweep(config: &BotConfig, account: &Account, markets: &[&str]) -> Result<(), String> {
+    let directory = &config.recorder.directory;
+    let mut records = Vec::new();
+    for market in markets {
+        records.extend(
+            backend::recorder::read_records(directory, Some(market), DateTime::<Utc>::MIN_UTC, DateTime::<Utc>::MAX_UTC)
+                .map_err(|err| format!("Cannot read recordings in {}: {}", directory.display(), err))?,
+        );
+    }
+    let dataset = Dataset::split(records, config.sweep.validation_share)?;
+    let rules = QuoteRules {
+        params: config.strategy,
+        fee_model: FeeModel::from_config(&account.fees),
+        max_cost_per_1k: account.fees.max_cost_per_1k,
+    };
+    let candidates = backend::sweep::candidates(&config.sweep).len();
+    println!(
+        "{} candidates ({:?} search), training {} day(s) from {}, validation {} day(s) from {}",
+        candidates,
+        config.sweep.method,
+        dataset.training_days.len(),
+        dataset.training_days[0],
+        dataset.validation_days.len(),
+        dataset.validation_days[0]
+    );
+    let results = backend::sweep::run_sweep(&config.sweep, &dataset, &rules, &config.backtest);
+    let current = SweepResult {
+        params: config.strategy,
+        training: backend::sweep::evaluate(config.strategy, &rules, &config.backtest, &dataset.training),
+        validation: Some(backend::sweep::evaluate(
+            config.strategy,
+            &rules,
+            &config.backtest,
+            &dataset.validation,
+        )),
+    };
+    print!("{}", backend::sweep::report(&results, config.sweep.top, &current));
+    Ok(())
+}
+
+// Market data recorder alone, stopped by SIGINT / SIGTERM
+async fn record(console: &ConsoleLogs, config: &BotConfig, url: URL, markets: &[&str]) -> Result<(), String> {
+    let mut recorder = config.recorder.clone();
+    if !markets.is_empty() {
+        recorder.markets = markets.iter().map(|market| market.to_string()).collect();
+    }
+    let logger = console.logger();
+    let shutdown = CancellationToken::new();
+    let signal_logger = logger.clone();
+    let signal_shutdown = shutdown.clone();
+    tokio::spawn(async move {
+        let signal = backend::shutdown::shutdown_signal().await;
+        signal_logger.info(format!("{} received.", signal));
+        signal_shutdown.cancel();
+    });
+    backend::recorder::run_recorder(logger, recorder, url, Clock::System, shutdown).await
+}
+
+fn keys_command(args: &Args, config: &BotConfig, command: &[&str]) -> Result<(), String> {
+    let mut keystore = Keystore::load(&config.keystore.path)?;
+    match command {
+        ["list"] => {
+            if keystore.is_empty() {
+                println!("No key in {}.", keystore.path().display());
+            }
+            for entry in keystore.entries() {
+                println!("{:<20} imported {}", entry.name, entry.created_at.format("%Y-%m-%d %H:%M"));
+            }
+            Ok(())
+        }
+        ["import", name] => {
+            if !config.accounts().iter().any(|account| account.name == *name) {
+                println!("Warning: no account {} in the configuration.", name);
+            }
+            let key = read_secret(&format!("L2 private key of {} (hex): ", name))?;
+            if key.trim().is_empty() {
+                return Err("Empty key".to_string());
+            }
+            let password = read_secret("Keystore password: ")?;
+            if keystore.is_empty() {
+                // new password
+                if password.is_empty() {
+                    return Err("Empty password".to_string());
+                }
+                if read_secret("Confirm password: ")? != password {
+                    return Err("Passwords do not match".to_string());
+                }
+            }
+            keystore.insert(name, key.trim(), &password)?;
+            keystore.save()?;
+            println!("Key of {} saved in {}.", name, keystore.path().display());
+            Ok(())
+        }
+        ["remove", name] => {
+            if !keystore.entries().iter().any(|entry| entry.name == *name) {
+                return Err(format!("No key {} in {}", name, keystore.path().display()));
+            }
+            if confirm(args, &format!("Remove the key of {}?", name)) {
+                keystore.remove(name);
+                keystore.save()?;
+                println!("Key of {} removed.", name);
+            }
+            Ok(())
+        }
+        _ => Err(format!("Unknown keys command\n\n{}", USAGE)),
+    }
+}
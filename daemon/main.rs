@@ -0,0 +1,43 @@
+use backend::LogLevel;
+use backend::config::{BotConfig, CONFIG_FILE, Environment};
+use std::process::ExitCode;
+
+// Headless bot for servers, see backend::headless
+
+// Optional minimum log level (DEBUG, INFO, WARN, ERROR)
+const LOG_LEVEL_VAR: &str = "BOT_PARADEX_LOG_LEVEL";
+const USAGE: &str = "Usage: daemon [--testnet | --production] [--yes]";
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let log_level = std::env::var(LOG_LEVEL_VAR)
+        .ok()
+        .and_then(|name| LogLevel::from_name(&name))
+        .unwrap_or(LogLevel::Info);
+
+    let mut config = match BotConfig::load(std::path::Path::new(CONFIG_FILE)) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    // production confirmed by the operator
+    let mut confirmed = false;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--testnet" => config.exchange.environment = Environment::Testnet,
+            "--production" => config.exchange.environment = Environment::Production,
+            "--yes" | "-y" => confirmed = true,
+            _ => {
+                eprintln!("Unknown argument {}\n{}", arg, USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    // once: the URLs of a custom endpoint live until the end of the process
+    let url = config.exchange.leak_url();
+    backend::headless::run(config, url, log_level, confirmed).await
+}
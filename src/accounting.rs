@@ -0,0 +1,281 @@
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use paradex::structs::{Fill, FillLiquidity, Side};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::Write;
+use std::path::Path;
+
+// Farming cost accounting: each Option buy fill (our bid) is paired FIFO with the sell fills
+// (our liquidation) of the same market. A pair is a round trip:
+// - volume: notional traded on both legs (price * size)
+// - fees: fees of both legs, prorated to the paired size (negative = rebate)
+// - spread loss: (buy price - sell price) * size
+// - net cost: spread loss + fees
+
+#[derive(Clone, Debug)]
+pub struct RoundTrip {
+    pub market: String,
+    pub opened_at: DateTime<Utc>,
+    pub closed_at: DateTime<Utc>,
+    pub size: f64,
+    pub buy_price: f64,
+    pub sell_price: f64,
+    pub buy_fee: f64,
+    pub sell_fee: f64,
+    // Liquidity of each leg (maker = resting order filled)
+    pub buy_maker: bool,
+    pub sell_maker: bool,
+}
+
+impl RoundTrip {
+    pub fn underlying(&self) -> &str {
+        underlying(&self.market)
+    }
+
+    pub fn volume(&self) -> f64 {
+        (self.buy_price + self.sell_price) * self.size
+    }
+
+    pub fn fees(&self) -> f64 {
+        self.buy_fee + self.sell_fee
+    }
+
+    pub fn spread_loss(&self) -> f64 {
+        (self.buy_price - self.sell_price) * self.size
+    }
+
+    pub fn net_cost(&self) -> f64 {
+        self.spread_loss() + self.fees()
+    }
+}
+
+// "BTC-USD-96000-C" -> "BTC"
+pub fn underlying(market: &str) -> &str {
+    market.split('-').next().unwrap_or(market)
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AccountingTotals {
+    pub round_trips: usize,
+    pub volume: f64,
+    pub fees: f64,
+    pub spread_loss: f64,
+    // Split of volume and fees by liquidity, for the fee model
+    pub maker_volume: f64,
+    pub maker_fees: f64,
+    pub taker_volume: f64,
+    pub taker_fees: f64,
+}
+
+impl AccountingTotals {
+    fn add(&mut self, round_trip: &RoundTrip) {
+        self.round_trips += 1;
+        self.volume += round_trip.volume();
+        self.fees += round_trip.fees();
+        self.spread_loss += round_trip.spread_loss();
+        let legs = [
+            (round_trip.buy_maker, round_trip.buy_price, round_trip.buy_fee),
+            (round_trip.sell_maker, round_trip.sell_price, round_trip.sell_fee),
+        ];
+        for (maker, price, fee) in legs {
+            if maker {
+                self.maker_volume += price * round_trip.size;
+                self.maker_fees += fee;
+            } else {
+                self.taker_volume += price * round_trip.size;
+                self.taker_fees += fee;
+            }
+        }
+    }
+
+    // Sum with the totals of another account
+    pub fn merge(&mut self, other: &AccountingTotals) {
+        self.round_trips += other.round_trips;
+        self.volume += other.volume;
+        self.fees += other.fees;
+        self.spread_loss += other.spread_loss;
+        self.maker_volume += other.maker_volume;
+        self.maker_fees += other.maker_fees;
+        self.taker_volume += other.taker_volume;
+        self.taker_fees += other.taker_fees;
+    }
+
+    pub fn net_cost(&self) -> f64 {
+        self.spread_loss + self.fees
+    }
+
+    // Net cost for each $1k of volume
+    pub fn cost_per_1k(&self) -> Option<f64> {
+        if self.volume > 0.0 {
+            Some(self.net_cost() / self.volume * 1000.0)
+        } else {
+            None
+        }
+    }
+}
+
+// Part of a buy fill not yet paired with a sell
+#[derive(Clone, Debug)]
+struct OpenLeg {
+    created_at: DateTime<Utc>,
+    price: f64,
+    remaining_size: f64,
+    // fee per unit of size
+    fee_per_size: f64,
+    maker: bool,
+}
+
+pub struct Accounting {
+    session_start: DateTime<Utc>,
+    fill_ids: HashSet<String>,
+    open_legs: HashMap<String, VecDeque<OpenLeg>>,
+    round_trips: Vec<RoundTrip>,
+}
+
+impl Accounting {
+    pub fn new(session_start: DateTime<Utc>) -> Self {
+        Self {
+            session_start,
+            fill_ids: HashSet::new(),
+            open_legs: HashMap::new(),
+            round_trips: Vec::new(),
+        }
+    }
+
+    // Fills must be added in chronological order, duplicates are ignored
+    pub fn add_fill(&mut self, fill: &Fill) {
+        if !self.fill_ids.insert(fill.id.clone()) || fill.size <= 0.0 {
+            return;
+        }
+        let created_at = DateTime::from_timestamp_millis(fill.created_at as i64).unwrap_or_else(Utc::now);
+        let fee_per_size = fill.fee / fill.size;
+        let maker = fill.liquidity == FillLiquidity::MAKER;
+        let legs = self.open_legs.entry(fill.market.clone()).or_default();
+
+        match fill.side {
+            Side::BUY => legs.push_back(OpenLeg {
+                created_at,
+                price: fill.price,
+                remaining_size: fill.size,
+                fee_per_size,
+                maker,
+            }),
+            Side::SELL => {
+                let mut to_match = fill.size;
+                while to_match > 0.0 {
+                    let Some(leg) = legs.front_mut() else {
+                        // sell of a position opened before the accounting started
+                        break;
+                    };
+                    let matched = to_match.min(leg.remaining_size);
+                    self.round_trips.push(RoundTrip {
+                        market: fill.market.clone(),
+                        opened_at: leg.created_at,
+                        closed_at: created_at,
+                        size: matched,
+                        buy_price: leg.price,
+                        sell_price: fill.price,
+                        buy_fee: leg.fee_per_size * matched,
+                        sell_fee: fee_per_size * matched,
+                        buy_maker: leg.maker,
+                        sell_maker: maker,
+                    });
+                    leg.remaining_size -= matched;
+                    to_match -= matched;
+                    if leg.remaining_size <= f64::EPSILON {
+                        legs.pop_front();
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn round_trips(&self) -> &[RoundTrip] {
+        &self.round_trips
+    }
+
+    // Size bought and not sold yet
+    pub fn open_size(&self, market: &str) -> f64 {
+        self.open_legs
+            .get(market)
+            .map(|legs| legs.iter().map(|leg| leg.remaining_size).sum())
+            .unwrap_or_default()
+    }
+
+    pub fn total(&self) -> AccountingTotals {
+        self.totals_where(|_| true)
+    }
+
+    // Round trips closed since the accounting was created
+    pub fn session(&self) -> AccountingTotals {
+        self.totals_where(|round_trip| round_trip.closed_at >= self.session_start)
+    }
+
+    // Round trips closed since the given time
+    pub fn since(&self, start: DateTime<Utc>) -> AccountingTotals {
+        self.totals_where(|round_trip| round_trip.closed_at >= start)
+    }
+
+    // Round trips closed during the given local day
+    pub fn day(&self, day: NaiveDate) -> AccountingTotals {
+        self.totals_where(|round_trip| local_day(round_trip) == day)
+    }
+
+    fn totals_where(&self, keep: impl Fn(&RoundTrip) -> bool) -> AccountingTotals {
+        let mut totals = AccountingTotals::default();
+        for round_trip in self.round_trips.iter().filter(|round_trip| keep(round_trip)) {
+            totals.add(round_trip);
+        }
+        totals
+    }
+
+    pub fn by_market(&self) -> BTreeMap<String, AccountingTotals> {
+        self.group_by(|round_trip| round_trip.market.clone())
+    }
+
+    pub fn by_underlying(&self) -> BTreeMap<String, AccountingTotals> {
+        self.group_by(|round_trip| round_trip.underlying().to_string())
+    }
+
+    pub fn by_day(&self) -> BTreeMap<NaiveDate, AccountingTotals> {
+        self.group_by(local_day)
+    }
+
+    fn group_by<K: Ord>(&self, key: impl Fn(&RoundTrip) -> K) -> BTreeMap<K, AccountingTotals> {
+        let mut groups: BTreeMap<K, AccountingTotals> = BTreeMap::new();
+        for round_trip in &self.round_trips {
+            groups.entry(key(round_trip)).or_default().add(round_trip);
+        }
+        groups
+    }
+
+    // One line per round trip
+    pub fn write_csv(&self, path: &Path) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(
+            file,
+            "market,underlying,opened_at,closed_at,size,buy_price,sell_price,volume,fees,spread_loss,net_cost"
+        )?;
+        for round_trip in &self.round_trips {
+            writeln!(
+                file,
+                "{},{},{},{},{},{},{},{:.6},{:.6},{:.6},{:.6}",
+                round_trip.market,
+                round_trip.underlying(),
+                round_trip.opened_at.to_rfc3339(),
+                round_trip.closed_at.to_rfc3339(),
+                round_trip.size,
+                round_trip.buy_price,
+                round_trip.sell_price,
+                round_trip.volume(),
+                round_trip.fees(),
+                round_trip.spread_loss(),
+                round_trip.net_cost(),
+            )?;
+        }
+        file.flush()
+    }
+}
+
+fn local_day(round_trip: &RoundTrip) -> NaiveDate {
+    round_trip.closed_at.with_timezone(&Local).date_naive()
+}
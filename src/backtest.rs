@@ -0,0 +1,448 @@
+use chrono::{DateTime, Utc};
+use paradex::structs::{Level, OrderBook, Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use serde::Deserialize;
+use std::fmt;
+
+use crate::recorder::{MarketData, Record};
+use crate::strategy::{self, BookTop, QuoteRules};
+
+// Replay of recorded market data through the strategy rules of the bot.
+// One bid of `order_size` is kept on the market (placed at the first bid when there is none, like the
+// operator does), the rules run every `refresh_seconds` on the last snapshot with our bid merged in,
+// fills are simulated from the trades with our position in the queue of our price level, and filled
+// size is sold at market into the recorded bids `liquidation_delay_seconds` later.
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BacktestConfig {
+    // Size of the simulated bid
+    pub order_size: f64,
+    // Delay between two passes of the bot over the bid
+    pub refresh_seconds: u64,
+    // Delay between a fill and its market sell (positions are checked at each pass)
+    pub liquidation_delay_seconds: u64,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            order_size: 1.0,
+            refresh_seconds: crate::REFRESH_TIME_SEC,
+            liquidation_delay_seconds: crate::REFRESH_TIME_SEC,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BacktestStats {
+    pub snapshots: u64,
+    pub trades: u64,
+    // Passes of the bot over the bid
+    pub cycles: u64,
+    pub orders_placed: u64,
+    pub reprices: u64,
+    pub cancels_low_liquidity: u64,
+    pub cancels_too_expensive: u64,
+    // Bids cancelled by the liquidation of a partial fill
+    pub cancels_liquidation: u64,
+    pub fills: u64,
+    pub filled_size: f64,
+    // Sum of the delays between placement and fill
+    pub fill_wait_seconds: f64,
+    pub liquidations: u64,
+    // Market sells which could not be completed with the recorded bids
+    pub incomplete_liquidations: u64,
+    pub entry_volume: f64,
+    pub exit_volume: f64,
+    pub maker_fees: f64,
+    pub taker_fees: f64,
+    // Size still held at the end of the replay (counted as sold into the last book)
+    pub open_position: f64,
+}
+
+impl BacktestStats {
+    // Totals of several replays (days, markets)
+    pub fn merge(&mut self, other: &BacktestStats) {
+        self.snapshots += other.snapshots;
+        self.trades += other.trades;
+        self.cycles += other.cycles;
+        self.orders_placed += other.orders_placed;
+        self.reprices += other.reprices;
+        self.cancels_low_liquidity += other.cancels_low_liquidity;
+        self.cancels_too_expensive += other.cancels_too_expensive;
+        self.cancels_liquidation += other.cancels_liquidation;
+        self.fills += other.fills;
+        self.filled_size += other.filled_size;
+        self.fill_wait_seconds += other.fill_wait_seconds;
+        self.liquidations += other.liquidations;
+        self.incomplete_liquidations += other.incomplete_liquidations;
+        self.entry_volume += other.entry_volume;
+        self.exit_volume += other.exit_volume;
+        self.maker_fees += other.maker_fees;
+        self.taker_fees += other.taker_fees;
+        self.open_position += other.open_position;
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.entry_volume + self.exit_volume
+    }
+
+    pub fn spread_loss(&self) -> f64 {
+        self.entry_volume - self.exit_volume
+    }
+
+    pub fn fees(&self) -> f64 {
+        self.maker_fees + self.taker_fees
+    }
+
+    pub fn net_cost(&self) -> f64 {
+        self.spread_loss() + self.fees()
+    }
+
+    pub fn cost_per_1k(&self) -> Option<f64> {
+        if self.volume() > 0.0 {
+            Some(self.net_cost() / self.volume() * 1000.0)
+        } else {
+            None
+        }
+    }
+
+    pub fn average_fill_wait(&self) -> Option<f64> {
+        if self.fills > 0 {
+            Some(self.fill_wait_seconds / self.fills as f64)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for BacktestStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Snapshots / trades     {} / {}", self.snapshots, self.trades)?;
+        writeln!(f, "Cycles                 {}", self.cycles)?;
+        writeln!(f, "Orders placed          {}", self.orders_placed)?;
+        writeln!(f, "Reprices               {}", self.reprices)?;
+        writeln!(
+            f,
+            "Cancels                {} low liquidity, {} too expensive, {} liquidation",
+            self.cancels_low_liquidity, self.cancels_too_expensive, self.cancels_liquidation
+        )?;
+        writeln!(f, "Fills                  {} ({:.4} filled)", self.fills, self.filled_size)?;
+        if let Some(wait) = self.average_fill_wait() {
+            writeln!(f, "Average wait to fill   {:.0} s", wait)?;
+        }
+        writeln!(
+            f,
+            "Liquidations           {} ({} incomplete)",
+            self.liquidations, self.incomplete_liquidations
+        )?;
+        writeln!(f, "Volume                 ${:.2}", self.volume())?;
+        writeln!(f, "Spread loss            ${:.2}", self.spread_loss())?;
+        writeln!(f, "Fees                   ${:.2}", self.fees())?;
+        writeln!(f, "Net cost               ${:.2}", self.net_cost())?;
+        match self.cost_per_1k() {
+            Some(cost) => writeln!(f, "Cost per $1k           ${:.2}", cost)?,
+            None => writeln!(f, "Cost per $1k           -")?,
+        }
+        if self.open_position > 0.0 {
+            writeln!(f, "Open position at end   {:.4}", self.open_position)?;
+        }
+        Ok(())
+    }
+}
+
+struct SimOrder {
+    price: Decimal,
+    size: Decimal,
+    // Size of the level ahead of us in the queue
+    queue_ahead: f64,
+    placed_at: DateTime<Utc>,
+}
+
+struct SimPosition {
+    size: f64,
+    last_fill_at: DateTime<Utc>,
+}
+
+struct Simulation<'a> {
+    rules: &'a QuoteRules,
+    config: &'a BacktestConfig,
+    book: Option<OrderBook>,
+    order: Option<SimOrder>,
+    position: Option<SimPosition>,
+    next_cycle: Option<DateTime<Utc>>,
+    stats: BacktestStats,
+}
+
+// Replay the records of one market (in reception order)
+pub fn run_backtest(records: &[Record], rules: &QuoteRules, config: &BacktestConfig) -> BacktestStats {
+    let mut simulation = Simulation {
+        rules,
+        config,
+        book: None,
+        order: None,
+        position: None,
+        next_cycle: None,
+        stats: BacktestStats::default(),
+    };
+    for record in records {
+        simulation.apply(record);
+    }
+    // Position left at the end valued as a market sell into the last book
+    if let (Some(position), Some(book)) = (&simulation.position, &simulation.book) {
+        let (notional, _) = market_sell(book, position.size);
+        simulation.stats.open_position = position.size;
+        simulation.stats.exit_volume += notional;
+        simulation.stats.taker_fees += rules.fee_model.taker_fee(notional);
+    }
+    simulation.stats
+}
+
+// Recorded level size at a price, our bid is not in the recording
+fn level_size(book: &OrderBook, side: Side, price: Decimal) -> f64 {
+    book.inserts
+        .iter()
+        .filter(|level| level.side == side && Decimal::from_f64(level.price) == Some(price))
+        .map(|level| level.size)
+        .sum()
+}
+
+fn best_ask(book: &OrderBook) -> Option<f64> {
+    book.inserts
+        .iter()
+        .filter(|level| level.side == Side::SELL)
+        .map(|level| level.price)
+        .min_by(f64::total_cmp)
+}
+
+// Snapshot as the bot receives it while our bid is on the book
+fn with_own_bid(book: &OrderBook, order: &SimOrder) -> OrderBook {
+    let mut book = book.clone();
+    let price = order.price.to_f64().unwrap_or_default();
+    let size = order.size.to_f64().unwrap_or_default();
+    if let Some(level) = book
+        .inserts
+        .iter_mut()
+        .find(|level| level.side == Side::BUY && Decimal::from_f64(level.price) == Some(order.price))
+    {
+        level.size += size;
+    } else {
+        let index = book
+            .inserts
+            .iter()
+            .position(|level| level.side == Side::SELL || level.price < price)
+            .unwrap_or(book.inserts.len());
+        book.inserts.insert(index, Level { side: Side::BUY, price, size });
+    }
+    book
+}
+
+// Notional of a market sell of `size` into the bids, with the size which could not be sold
+fn market_sell(book: &OrderBook, size: f64) -> (f64, f64) {
+    let mut bids: Vec<&Level> = book.inserts.iter().filter(|level| level.side == Side::BUY).collect();
+    bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+    let mut remaining = size;
+    let mut notional = 0.0;
+    for bid in bids {
+        let sold = remaining.min(bid.size);
+        notional += sold * bid.price;
+        remaining -= sold;
+        if remaining <= f64::EPSILON {
+            return (notional, 0.0);
+        }
+    }
+    (notional, remaining)
+}
+
+impl Simulation<'_> {
+    fn apply(&mut self, record: &Record) {
+        let now = record.received_at;
+        match &record.data {
+            MarketData::OrderBook(book) => {
+                self.stats.snapshots += 1;
+                self.book = Some(book.clone());
+                self.on_book();
+                if let Some(ask) = best_ask(book) {
+                    self.on_crossing_ask(ask, now);
+                }
+                self.liquidate(now);
+                self.run_cycles(now);
+            }
+            MarketData::Trade(trade) => {
+                self.stats.trades += 1;
+                self.on_trade(trade, now);
+            }
+            MarketData::Bbo(bbo) => self.on_crossing_ask(bbo.ask, now),
+            // snapshots only, deltas need the book they apply to
+            MarketData::OrderBookDeltas(_) => {}
+        }
+    }
+
+    // Orders ahead of us cancelled: we move up in the queue
+    fn on_book(&mut self) {
+        if let Some(order) = &mut self.order
+            && let Some(book) = &self.book
+        {
+            order.queue_ahead = order.queue_ahead.min(level_size(book, Side::BUY, order.price));
+        }
+    }
+
+    // Ask at or below our bid: the whole bid is taken
+    fn on_crossing_ask(&mut self, ask: f64, now: DateTime<Utc>) {
+        if let Some(order) = &self.order
+            && ask > 0.0
+            && ask <= order.price.to_f64().unwrap_or_default()
+        {
+            let size = order.size;
+            self.fill(size, now);
+        }
+    }
+
+    // Sell taker trades fill the queue of the level in time priority
+    fn on_trade(&mut self, trade: &Trade, now: DateTime<Utc>) {
+        let Some(order) = &mut self.order else {
+            return;
+        };
+        if trade.side != Side::SELL {
+            return;
+        }
+        let Some(trade_price) = Decimal::from_f64(trade.price) else {
+            return;
+        };
+        let filled = if trade_price < order.price {
+            order.size
+        } else if trade_price == order.price {
+            let after_queue = trade.size - order.queue_ahead;
+            order.queue_ahead = (-after_queue).max(0.0);
+            Decimal::from_f64(after_queue.max(0.0)).unwrap_or_default().min(order.size)
+        } else {
+            Decimal::ZERO
+        };
+        if filled > Decimal::ZERO {
+            self.fill(filled, now);
+        }
+    }
+
+    fn fill(&mut self, size: Decimal, now: DateTime<Utc>) {
+        let Some(order) = &mut self.order else {
+            return;
+        };
+        let price = order.price.to_f64().unwrap_or_default();
+        let filled = size.to_f64().unwrap_or_default();
+        let notional = price * filled;
+        self.stats.fills += 1;
+        self.stats.filled_size += filled;
+        self.stats.fill_wait_seconds += (now - order.placed_at).num_milliseconds() as f64 / 1000.0;
+        self.stats.entry_volume += notional;
+        self.stats.maker_fees += self.rules.fee_model.maker_fee(notional);
+        order.size -= size;
+        if order.size <= Decimal::ZERO {
+            self.order = None;
+        }
+        let position = self.position.get_or_insert(SimPosition {
+            size: 0.0,
+            last_fill_at: now,
+        });
+        position.size += filled;
+        position.last_fill_at = now;
+    }
+
+    // Market sell of the position once the bot has seen it, the rest of the bid is cancelled first
+    fn liquidate(&mut self, now: DateTime<Utc>) {
+        let delay = chrono::Duration::seconds(self.config.liquidation_delay_seconds as i64);
+        let (Some(position), Some(book)) = (&mut self.position, &self.book) else {
+            return;
+        };
+        if now < position.last_fill_at + delay {
+            return;
+        }
+        if self.order.take().is_some() {
+            self.stats.cancels_liquidation += 1;
+        }
+        let (notional, remaining) = market_sell(book, position.size);
+        self.stats.liquidations += 1;
+        self.stats.exit_volume += notional;
+        self.stats.taker_fees += self.rules.fee_model.taker_fee(notional);
+        if remaining > f64::EPSILON {
+            self.stats.incomplete_liquidations += 1;
+            position.size = remaining;
+        } else {
+            self.position = None;
+        }
+    }
+
+    fn run_cycles(&mut self, now: DateTime<Utc>) {
+        let refresh = chrono::Duration::seconds(self.config.refresh_seconds.max(1) as i64);
+        let next_cycle = *self.next_cycle.get_or_insert(now);
+        if now < next_cycle {
+            return;
+        }
+        self.cycle(now);
+        let mut next_cycle = next_cycle + refresh;
+        while next_cycle <= now {
+            next_cycle += refresh;
+        }
+        self.next_cycle = Some(next_cycle);
+    }
+
+    // One pass of the bot: place the bid if needed, then reprice / liquidity / exit cost rules
+    fn cycle(&mut self, now: DateTime<Utc>) {
+        let Some(book) = &self.book else {
+            return;
+        };
+        self.stats.cycles += 1;
+        if self.order.is_none() && self.position.is_none() {
+            let first_bid = book
+                .inserts
+                .iter()
+                .filter(|level| level.side == Side::BUY)
+                .map(|level| level.price)
+                .max_by(f64::total_cmp);
+            if let Some(price) = first_bid.and_then(Decimal::from_f64)
+                && let Some(size) = Decimal::from_f64(self.config.order_size)
+            {
+                self.stats.orders_placed += 1;
+                self.order = Some(SimOrder {
+                    price,
+                    size,
+                    queue_ahead: level_size(book, Side::BUY, price),
+                    placed_at: now,
+                });
+            }
+            return;
+        }
+        let Some(order) = &mut self.order else {
+            return;
+        };
+
+        let params = &self.rules.params;
+        let book_price = order.price;
+        let top = BookTop::from_snapshot(&with_own_bid(book, order));
+        let new_price = strategy::determine_new_bid_price(Some(order.price), order.size, &top, params);
+        if let Some(price) = new_price
+            && price != order.price
+        {
+            self.stats.reprices += 1;
+            order.price = price;
+            order.queue_ahead = level_size(book, Side::BUY, price);
+        }
+
+        if let Some(liquidity) = strategy::exit_liquidity(&top, new_price, params)
+            && strategy::is_liquidity_low(order.size, liquidity, params)
+        {
+            self.stats.cancels_low_liquidity += 1;
+            self.order = None;
+            return;
+        }
+        if let Some(max_cost_per_1k) = self.rules.max_cost_per_1k {
+            let estimate =
+                strategy::estimate_bid_exit(Some(book_price), order.size, &top, new_price, &self.rules.fee_model);
+            if strategy::is_too_expensive(estimate.as_ref(), max_cost_per_1k) {
+                self.stats.cancels_too_expensive += 1;
+                self.order = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use paradex::url::URL;
+use paradex::ws::{Channel, Identifier, Message, WebsocketManager};
+
+use crossbeam::channel::{Receiver, Sender, TryRecvError};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::events::BackendEvent;
+use crate::{LogMessage, Logger, SharedLogLevel};
+
+// Delay between two checks of the market selected in the front-end
+const SELECTION_POLL_MS: u64 = 200;
+
+// Live order book of the market selected in the front-end (None = no market).
+// Runs on its own websocket manager so that the trading loop subscriptions are not disturbed.
+pub async fn run_book_viewer(
+    log_sender: Sender<LogMessage>,
+    market_receiver: Receiver<Option<String>>,
+    event_sender: Sender<BackendEvent>,
+    log_level: SharedLogLevel,
+    url: URL,
+    shutdown: CancellationToken,
+) {
+    let logger = Logger::with_shared_level(log_sender, log_level);
+    let manager = WebsocketManager::new(url, None).await;
+    let mut subscription: Option<Identifier> = None;
+
+    while !shutdown.is_cancelled() {
+        // Only keep the last selection
+        let mut selection = None;
+        loop {
+            match market_receiver.try_recv() {
+                Ok(market) => selection = Some(market),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    let _ = manager.stop().await;
+                    return;
+                }
+            }
+        }
+
+        if let Some(market) = selection {
+            if let Some(id) = subscription.take()
+                && let Err(err) = manager.unsubscribe(id).await
+            {
+                logger.error(format!("Failed to unsubscribe order book viewer: {}", err));
+            }
+            if let Some(market_symbol) = market {
+                logger.debug(format!("Order book viewer on market {}", market_symbol));
+                let sender = event_sender.clone();
+                let result = manager
+                    .subscribe(
+                        Channel::OrderBook {
+                            market_symbol,
+                            channel_name: None,
+                            refresh_rate: "100ms".into(),
+                            price_tick: None,
+                        },
+                        Box::new(move |message| {
+                            if let Message::OrderBook(ob_snapshot) = message {
+                                let _ = sender.send(BackendEvent::OrderBook(ob_snapshot.clone()));
+                            }
+                        }),
+                    )
+                    .await;
+                match result {
+                    Ok(id) => subscription = Some(id),
+                    Err(err) => {
+                        logger.error(format!("Failed to subscribe order book viewer: {}", err))
+                    }
+                }
+            }
+        }
+
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = tokio::time::sleep(Duration::from_millis(SELECTION_POLL_MS)) => {}
+        }
+    }
+    let _ = manager.stop().await;
+}
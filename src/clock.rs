@@ -0,0 +1,167 @@
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+// Time of the back-end: the system clock, or a clock moved by hand so that the time-dependent rules
+// (refresh period, day and target periods, timestamps) run deterministically in the tests
+
+#[derive(Clone, Debug, Default)]
+pub enum Clock {
+    #[default]
+    System,
+    Manual(ManualClock),
+}
+
+impl Clock {
+    pub fn now(&self) -> DateTime<Utc> {
+        match self {
+            Clock::System => Utc::now(),
+            Clock::Manual(clock) => clock.now(),
+        }
+    }
+
+    pub fn local_now(&self) -> DateTime<Local> {
+        self.now().with_timezone(&Local)
+    }
+
+    pub async fn sleep(&self, duration: Duration) {
+        match self {
+            Clock::System => tokio::time::sleep(duration).await,
+            Clock::Manual(clock) => clock.sleep(duration).await,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ManualState {
+    now: DateTime<Utc>,
+    // Deadlines of the pending sleeps, by sleep id
+    sleeps: Vec<(u64, DateTime<Utc>)>,
+    next_sleep_id: u64,
+}
+
+#[derive(Debug)]
+struct ManualShared {
+    state: Mutex<ManualState>,
+    // Time moved or sleep added / removed
+    changed: Notify,
+}
+
+// Clock that only moves when told to, shared by its clones
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    shared: Arc<ManualShared>,
+}
+
+impl ManualClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self {
+            shared: Arc::new(ManualShared {
+                state: Mutex::new(ManualState {
+                    now: start,
+                    sleeps: Vec::new(),
+                    next_sleep_id: 0,
+                }),
+                changed: Notify::new(),
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, ManualState> {
+        self.shared.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn now(&self) -> DateTime<Utc> {
+        self.state().now
+    }
+
+    // Move the time forward, waking the sleeps that are due
+    pub fn advance(&self, duration: Duration) {
+        self.state().now += to_delta(duration);
+        self.shared.changed.notify_waiters();
+    }
+
+    // Set the time (backward too), waking the sleeps that are due
+    pub fn set(&self, now: DateTime<Utc>) {
+        self.state().now = now;
+        self.shared.changed.notify_waiters();
+    }
+
+    // Number of tasks sleeping on the clock
+    pub fn pending_sleeps(&self) -> usize {
+        self.state().sleeps.len()
+    }
+
+    // Wait for a task to sleep on the clock then move the time to the end of the first sleep.
+    // Returns the new time.
+    pub async fn advance_to_next_sleep(&self) -> DateTime<Utc> {
+        loop {
+            let changed = self.shared.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            {
+                let mut state = self.state();
+                let now = state.now;
+                // sleeps already due end by themselves
+                if let Some(deadline) = state
+                    .sleeps
+                    .iter()
+                    .map(|(_, deadline)| *deadline)
+                    .filter(|deadline| *deadline > now)
+                    .min()
+                {
+                    state.now = deadline;
+                    drop(state);
+                    self.shared.changed.notify_waiters();
+                    return deadline;
+                }
+            }
+            changed.await;
+        }
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        let sleep = PendingSleep::register(self, duration);
+        loop {
+            let changed = self.shared.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            if self.now() >= sleep.deadline {
+                return;
+            }
+            changed.await;
+        }
+    }
+}
+
+// Sleep registered on the manual clock until its end or its cancellation (future dropped)
+struct PendingSleep<'a> {
+    clock: &'a ManualClock,
+    id: u64,
+    deadline: DateTime<Utc>,
+}
+
+impl<'a> PendingSleep<'a> {
+    fn register(clock: &'a ManualClock, duration: Duration) -> Self {
+        let mut state = clock.state();
+        let id = state.next_sleep_id;
+        let deadline = state.now + to_delta(duration);
+        state.next_sleep_id += 1;
+        state.sleeps.push((id, deadline));
+        drop(state);
+        clock.shared.changed.notify_waiters();
+        Self { clock, id, deadline }
+    }
+}
+
+impl Drop for PendingSleep<'_> {
+    fn drop(&mut self) {
+        self.clock.state().sleeps.retain(|(id, _)| *id != self.id);
+        self.clock.shared.changed.notify_waiters();
+    }
+}
+
+fn to_delta(duration: Duration) -> TimeDelta {
+    TimeDelta::from_std(duration).unwrap_or(TimeDelta::MAX)
+}
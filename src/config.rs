@@ -0,0 +1,345 @@
+use paradex::url::URL;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::backtest::BacktestConfig;
+use crate::fees::FeeConfig;
+use crate::keystore::KeystoreConfig;
+use crate::metrics::{self, MetricsConfig};
+use crate::recorder::RecorderConfig;
+use crate::shutdown::ShutdownConfig;
+use crate::strategy::StrategyParams;
+use crate::sweep::SweepConfig;
+use crate::targets::TargetConfig;
+
+// Configuration file read from the working directory (optional, defaults otherwise)
+pub const CONFIG_FILE: &str = "bot_paradex.toml";
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotConfig {
+    pub exchange: ExchangeConfig,
+    pub strategy: StrategyParams,
+    pub keystore: KeystoreConfig,
+    pub logging: LoggingConfig,
+    pub journal: JournalConfig,
+    pub target: TargetConfig,
+    pub fees: FeeConfig,
+    pub shutdown: ShutdownConfig,
+    pub recorder: RecorderConfig,
+    pub backtest: BacktestConfig,
+    pub sweep: SweepConfig,
+    pub metrics: MetricsConfig,
+    // Accounts run concurrently (one worker each), none = single account with the global settings
+    pub accounts: Vec<AccountConfig>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    #[default]
+    Production,
+    Testnet,
+}
+
+impl Environment {
+    pub fn url(&self) -> URL {
+        match self {
+            Environment::Production => URL::Production,
+            Environment::Testnet => URL::Testnet,
+        }
+    }
+
+    // Env variable of the L2 private key, one per environment so that keys are not mixed up
+    pub fn key_var(&self) -> &'static str {
+        match self {
+            Environment::Production => "PARADEX_L2_KEY",
+            Environment::Testnet => "PARADEX_TESTNET_L2_KEY",
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Environment::Production => "production",
+            Environment::Testnet => "testnet",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Environment> {
+        [Environment::Production, Environment::Testnet]
+            .into_iter()
+            .find(|environment| environment.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExchangeConfig {
+    pub environment: Environment,
+    // Ask a confirmation at startup when trading on production
+    pub confirm_production: bool,
+    // Other server than the one of the environment (local mock, proxy)
+    pub endpoint: Option<Endpoint>,
+}
+
+impl Default for ExchangeConfig {
+    fn default() -> Self {
+        Self {
+            environment: Environment::Production,
+            confirm_production: true,
+            endpoint: None,
+        }
+    }
+}
+
+impl ExchangeConfig {
+    // Server of the REST client and of the websockets. paradex::url::URL is Copy: the URLs of an
+    // endpoint are leaked to live until the end of the process, the binaries call this once at startup.
+    pub fn leak_url(&self) -> URL {
+        match &self.endpoint {
+            Some(endpoint) => URL::Custom {
+                rest: endpoint.rest.clone().leak(),
+                websocket: endpoint.websocket.clone().leak(),
+            },
+            None => self.environment.url(),
+        }
+    }
+
+    // Environment, with the server when it is not the one of the environment
+    pub fn describe(&self) -> String {
+        match &self.endpoint {
+            Some(endpoint) => format!("{} at {}", self.environment.as_str(), endpoint.rest),
+            None => self.environment.as_str().to_string(),
+        }
+    }
+}
+
+// [exchange.endpoint]: base of the REST api ("http://127.0.0.1:8080") and websocket ("ws://127.0.0.1:8080/v1")
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Endpoint {
+    pub rest: String,
+    pub websocket: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingConfig {
+    // Write logs into daily files
+    pub file_enabled: bool,
+    // Directory of the daily log files
+    pub directory: PathBuf,
+    // Number of daily files kept, older ones are deleted
+    pub retention_days: u32,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            file_enabled: true,
+            directory: PathBuf::from("logs"),
+            retention_days: 14,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct JournalConfig {
+    // Record order actions and fills
+    pub enabled: bool,
+    // JSONL file, appended across sessions
+    pub path: PathBuf,
+}
+
+impl Default for JournalConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: PathBuf::from("journal/trades.jsonl"),
+        }
+    }
+}
+
+// [[accounts]] entry: settings missing here are taken from the global sections
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountConfig {
+    pub name: String,
+    // Env variable of the L2 private key when the keystore env fallback is enabled
+    // (default: variable of the environment)
+    #[serde(default)]
+    pub key_var: Option<String>,
+    // Default: global journal file suffixed with the account name
+    #[serde(default)]
+    pub journal: Option<JournalConfig>,
+    #[serde(default)]
+    pub target: Option<TargetConfig>,
+    #[serde(default)]
+    pub fees: Option<FeeConfig>,
+}
+
+// Resolved settings of one account worker
+#[derive(Clone, Debug)]
+pub struct Account {
+    pub name: String,
+    pub key_var: String,
+    pub journal: JournalConfig,
+    pub target: TargetConfig,
+    pub fees: FeeConfig,
+}
+
+// Name of the account when no [[accounts]] is configured
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+impl BotConfig {
+    // One account per [[accounts]] entry, or the default account
+    pub fn accounts(&self) -> Vec<Account> {
+        if self.accounts.is_empty() {
+            return vec![Account {
+                name: DEFAULT_ACCOUNT.to_string(),
+                key_var: self.exchange.environment.key_var().to_string(),
+                journal: self.journal.clone(),
+                target: self.target.clone(),
+                fees: self.fees.clone(),
+            }];
+        }
+        self.accounts
+            .iter()
+            .map(|account| Account {
+                name: account.name.clone(),
+                key_var: account
+                    .key_var
+                    .clone()
+                    .unwrap_or_else(|| self.exchange.environment.key_var().to_string()),
+                journal: account.journal.clone().unwrap_or_else(|| JournalConfig {
+                    enabled: self.journal.enabled,
+                    path: suffixed_path(&self.journal.path, &account.name),
+                }),
+                target: account.target.clone().unwrap_or_else(|| self.target.clone()),
+                fees: account.fees.clone().unwrap_or_else(|| self.fees.clone()),
+            })
+            .collect()
+    }
+
+    // Only the named account, an error when it is not configured
+    pub fn keep_account(&mut self, name: &str) -> Result<(), String> {
+        if !self.accounts().iter().any(|account| account.name == name) {
+            return Err(format!("Unknown account {}", name));
+        }
+        self.accounts.retain(|account| account.name == name);
+        Ok(())
+    }
+
+    // Missing file = default configuration
+    pub fn load(path: &Path) -> Result<BotConfig, String> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|err| format!("Invalid config file {}: {}", path.display(), err)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BotConfig::default()),
+            Err(err) => Err(format!("Cannot read config file {}: {}", path.display(), err)),
+        }
+    }
+}
+
+impl BotConfig {
+    // Values accepted by the parser but making no sense
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.logging.file_enabled && self.logging.retention_days == 0 {
+            problems.push("logging.retention_days must be at least 1".to_string());
+        }
+        if self.journal.enabled && self.journal.path.as_os_str().is_empty() {
+            problems.push("journal.path is empty".to_string());
+        }
+        if self.strategy.step_size <= 0.0 {
+            problems.push("strategy.step_size must be positive".to_string());
+        }
+        if self.strategy.max_spread_price <= 0.0 {
+            problems.push("strategy.max_spread_price must be positive".to_string());
+        }
+        if self.strategy.size_multiplier < 0.0 {
+            problems.push("strategy.size_multiplier must not be negative".to_string());
+        }
+        if self.backtest.order_size <= 0.0 {
+            problems.push("backtest.order_size must be positive".to_string());
+        }
+        if !(0.0..1.0).contains(&self.sweep.validation_share) {
+            problems.push("sweep.validation_share must be in [0, 1)".to_string());
+        }
+        for (name, range) in [
+            ("sweep.step_size", self.sweep.step_size),
+            ("sweep.max_spread_price", self.sweep.max_spread_price),
+            ("sweep.size_multiplier", self.sweep.size_multiplier),
+        ] {
+            if range.min > range.max || range.step <= 0.0 {
+                problems.push(format!("{} needs min <= max and a positive step", name));
+            }
+        }
+        if self.target.volume.is_some_and(|volume| volume <= 0.0) {
+            problems.push("target.volume must be positive".to_string());
+        }
+        if self.target.max_cost.is_some_and(|max_cost| max_cost <= 0.0) {
+            problems.push("target.max_cost must be positive".to_string());
+        }
+        for (name, rate) in [("fees.maker_rate", self.fees.maker_rate), ("fees.taker_rate", self.fees.taker_rate)] {
+            if rate.abs() >= 0.01 {
+                problems.push(format!("{} {} is a fraction of the notional, not a percentage", name, rate));
+            }
+        }
+        if self.fees.max_cost_per_1k.is_some_and(|max_cost| max_cost <= 0.0) {
+            problems.push("fees.max_cost_per_1k must be positive".to_string());
+        }
+        if self.recorder.enabled && self.recorder.markets.is_empty() {
+            problems.push("recorder.markets is empty".to_string());
+        }
+        if self.shutdown.cancel_attempts == 0 {
+            problems.push("shutdown.cancel_attempts must be at least 1".to_string());
+        }
+        if self.recorder.rotate_minutes == 0 {
+            problems.push("recorder.rotate_minutes must be at least 1".to_string());
+        }
+        if let Some(endpoint) = &self.exchange.endpoint {
+            if !["http://", "https://"].iter().any(|scheme| endpoint.rest.starts_with(scheme)) {
+                problems.push(format!("exchange.endpoint.rest {} is not an http(s) URL", endpoint.rest));
+            }
+            // parsed by the websocket manager, which panics on an invalid URL
+            if !["ws://", "wss://"].iter().any(|scheme| endpoint.websocket.starts_with(scheme))
+                || endpoint.websocket.parse::<http::Uri>().is_err()
+            {
+                problems.push(format!("exchange.endpoint.websocket {} is not a ws(s) URL", endpoint.websocket));
+            }
+        }
+        if let Err(err) = metrics::parse_address(&self.metrics.address) {
+            problems.push(err);
+        }
+        let mut names = std::collections::HashSet::new();
+        for account in &self.accounts {
+            if account.name.trim().is_empty() {
+                problems.push("accounts.name is empty".to_string());
+            } else if !names.insert(account.name.as_str()) {
+                problems.push(format!("account {} is defined twice", account.name));
+            }
+        }
+        let mut journals = std::collections::HashSet::new();
+        for account in self.accounts() {
+            if account.journal.enabled && !journals.insert(account.journal.path.clone()) {
+                problems.push(format!(
+                    "journal {} is shared by several accounts",
+                    account.journal.path.display()
+                ));
+            }
+        }
+        problems
+    }
+}
+
+// "journal/trades.jsonl" + "main" -> "journal/trades_main.jsonl"
+fn suffixed_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("trades");
+    let name = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{}_{}.{}", stem, suffix, extension),
+        None => format!("{}_{}", stem, suffix),
+    };
+    path.with_file_name(name)
+}
@@ -0,0 +1,53 @@
+use paradex::structs::{Fill, OrderBook, OrderUpdate, Position};
+use crossbeam::channel::Sender;
+use rust_decimal::Decimal;
+
+use crate::fees::FeeModel;
+use crate::targets::TargetProgress;
+
+// Events sent from back-end to front-end (besides logs)
+#[derive(Clone, Debug)]
+pub enum BackendEvent {
+    // Open Option positions, refreshed each loop
+    Positions(Vec<Position>),
+    // Option fill of the account, sent once per fill id
+    Fill(Fill),
+    // Market sell sent by the bot to close a filled position
+    Liquidation { market: String, size: Decimal },
+    // Open orders of the account, refreshed each loop
+    OpenOrders(Vec<OrderUpdate>),
+    // Snapshot of the market selected in the order book viewer
+    OrderBook(OrderBook),
+    // Progress toward the volume target / spending budget, refreshed each loop
+    TargetProgress(TargetProgress),
+    // Fee rates used for the exit cost estimates, refreshed each loop
+    FeeModel(FeeModel),
+    // Event of an account worker
+    Account { account: String, event: Box<BackendEvent> },
+    // Bot loop ended (shutdown sequence completed, no order left, bot not started or fatal error)
+    Stopped,
+}
+
+// Events of one account worker, tagged with the account name
+#[derive(Clone)]
+pub struct AccountEventSender {
+    account: String,
+    sender: Sender<BackendEvent>,
+}
+
+impl AccountEventSender {
+    pub fn new(account: impl Into<String>, sender: Sender<BackendEvent>) -> Self {
+        Self {
+            account: account.into(),
+            sender,
+        }
+    }
+
+    // The front-end may be gone (headless), events are then dropped
+    pub fn send(&self, event: BackendEvent) {
+        let _ = self.sender.send(BackendEvent::Account {
+            account: self.account.clone(),
+            event: Box::new(event),
+        });
+    }
+}
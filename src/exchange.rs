@@ -0,0 +1,222 @@
+use chrono::{DateTime, Utc};
+use paradex::error::Error;
+use paradex::rest::Client;
+use paradex::structs::{
+    CancelByMarketResponse, Fill, ModifyOrderRequest, OrderRequest, OrderUpdate, OrderUpdates, Positions,
+};
+use paradex::url::URL;
+use paradex::ws::{Channel, Message, WebsocketManager};
+use std::future::Future;
+use std::time::Duration;
+
+use crate::Logger;
+use crate::clock::Clock;
+use crate::orderbook_state::{self, OrderBookState};
+use crate::strategy::BookTop;
+
+// Operations of the venue used by the bot loop: Paradex (REST client + websocket order book),
+// or the simulated exchange of the tests (see `sim`)
+
+pub type ExchangeResult<T> = Result<T, Error>;
+
+// Wait for the first order book snapshot after the subscription
+const SNAPSHOT_TIMEOUT_SEC: u64 = 10;
+
+pub trait Exchange: Send {
+    fn positions(&self) -> impl Future<Output = ExchangeResult<Positions>> + Send;
+
+    fn open_orders(&self) -> impl Future<Output = ExchangeResult<OrderUpdates>> + Send;
+
+    fn fills(
+        &self,
+        market: Option<String>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = ExchangeResult<Vec<Fill>>> + Send;
+
+    fn create_order(&self, request: OrderRequest) -> impl Future<Output = ExchangeResult<OrderUpdate>> + Send;
+
+    fn modify_order(
+        &self,
+        request: ModifyOrderRequest,
+    ) -> impl Future<Output = ExchangeResult<OrderUpdate>> + Send;
+
+    fn cancel_order(&self, order_id: String) -> impl Future<Output = ExchangeResult<()>> + Send;
+
+    fn cancel_all_orders_for_market(
+        &self,
+        market: String,
+    ) -> impl Future<Output = ExchangeResult<CancelByMarketResponse>> + Send;
+
+    // Levels of the order book read by the strategy rules
+    fn book_top(&mut self, market: String) -> impl Future<Output = Result<BookTop, String>> + Send;
+
+    // End of the market data subscriptions
+    fn stop(&mut self) -> impl Future<Output = Result<(), String>> + Send;
+
+    // Market data connections opened again after a failure
+    fn websocket_reconnects(&self) -> u64 {
+        0
+    }
+}
+
+pub struct ParadexExchange {
+    logger: Logger,
+    // Server of the websockets
+    url: URL,
+    client: Client,
+    // Public manager for WS, connected on the first order book request and again after a failure
+    manager: Option<WebsocketManager>,
+    connections: u64,
+    // Times the wait of the snapshots
+    clock: Clock,
+}
+
+impl ParadexExchange {
+    pub fn new(logger: Logger, url: URL, client: Client, clock: Clock) -> Self {
+        Self {
+            logger,
+            url,
+            client,
+            manager: None,
+            connections: 0,
+            clock,
+        }
+    }
+
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    // Drop a failed connection, the next order book request connects again
+    async fn disconnect(&mut self) {
+        if let Some(manager) = self.manager.take()
+            && let Err(err) = manager.stop().await
+        {
+            self.logger.debug(format!("Failed to stop websocket manager: {}", err));
+        }
+    }
+}
+
+impl Exchange for ParadexExchange {
+    fn positions(&self) -> impl Future<Output = ExchangeResult<Positions>> + Send {
+        self.client.positions()
+    }
+
+    fn open_orders(&self) -> impl Future<Output = ExchangeResult<OrderUpdates>> + Send {
+        self.client.open_orders()
+    }
+
+    fn fills(
+        &self,
+        market: Option<String>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = ExchangeResult<Vec<Fill>>> + Send {
+        self.client.fills(market, start, end)
+    }
+
+    fn create_order(&self, request: OrderRequest) -> impl Future<Output = ExchangeResult<OrderUpdate>> + Send {
+        self.client.create_order(request)
+    }
+
+    fn modify_order(
+        &self,
+        request: ModifyOrderRequest,
+    ) -> impl Future<Output = ExchangeResult<OrderUpdate>> + Send {
+        self.client.modify_order(request)
+    }
+
+    fn cancel_order(&self, order_id: String) -> impl Future<Output = ExchangeResult<()>> + Send {
+        self.client.cancel_order(order_id)
+    }
+
+    fn cancel_all_orders_for_market(
+        &self,
+        market: String,
+    ) -> impl Future<Output = ExchangeResult<CancelByMarketResponse>> + Send {
+        self.client.cancel_all_orders_for_market(market)
+    }
+
+    // Subscribe to the order book, wait for the first snapshot then unsubscribe
+    async fn book_top(&mut self, market_symbol: String) -> Result<BookTop, String> {
+        let manager = match &mut self.manager {
+            Some(manager) => manager,
+            None => {
+                self.connections += 1;
+                self.manager
+                    .insert(WebsocketManager::new(self.url, None).await)
+            }
+        };
+        let state = OrderBookState::new();
+        let clones = state.clone_for_callback();
+        // Get the order book using public manager
+        self.logger.debug(format!("The market of the order is: {:?}", market_symbol));
+        let callback_logger = self.logger.clone();
+        let orderbook_id = manager
+            .subscribe(
+                Channel::OrderBook {
+                    market_symbol: market_symbol.clone(),
+                    channel_name: None,
+                    refresh_rate: "50ms".into(),
+                    price_tick: None,
+                },
+                Box::new(move |message| {
+                    callback_logger.debug("Received message!");
+                    // ignore other variants for the moment
+                    if let Message::OrderBook(ob_snapshot) = message {
+                        orderbook_state::extract_data_from_snapshot(ob_snapshot, &clones);
+                    }
+                }),
+            )
+            .await;
+        let orderbook_id = match orderbook_id {
+            Ok(orderbook_id) => orderbook_id,
+            Err(err) => {
+                self.disconnect().await;
+                return Err(format!("Failed to subscribe to order book {}: {}", market_symbol, err));
+            }
+        };
+        // wait for message (the snapshot may never come: dropped message, closed connection)
+        self.logger.debug("Waiting for OrderBook snapshot notification...");
+        let received = tokio::select! {
+            _ = state.notify.notified() => true,
+            _ = self.clock.sleep(Duration::from_secs(SNAPSHOT_TIMEOUT_SEC)) => false,
+        };
+        // then unsubscribe
+        self.logger.debug("Unsubscribing...");
+        if let Err(err) = manager.unsubscribe(orderbook_id).await {
+            self.disconnect().await;
+            return Err(format!("Failed to unsubscribe from order book {}: {}", market_symbol, err));
+        }
+
+        if received {
+            Ok(state.top())
+        } else {
+            Err(format!(
+                "No order book snapshot of {} after {} s",
+                market_symbol, SNAPSHOT_TIMEOUT_SEC
+            ))
+        }
+    }
+
+    async fn stop(&mut self) -> Result<(), String> {
+        match &self.manager {
+            Some(manager) => manager
+                .stop()
+                .await
+                .map_err(|err| format!("Failed to stop websocket manager: {}", err)),
+            None => Ok(()),
+        }
+    }
+
+    fn websocket_reconnects(&self) -> u64 {
+        self.connections.saturating_sub(1)
+    }
+}
+
+// Paradex with a private client (L2 key from the keystore or an env variable)
+pub async fn connect(logger: &Logger, url: URL, l2_key: Option<String>, clock: Clock) -> Result<ParadexExchange, String> {
+    let client = crate::connect_client(url, l2_key).await?;
+    Ok(ParadexExchange::new(logger.clone(), url, client, clock))
+}
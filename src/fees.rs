@@ -0,0 +1,148 @@
+use paradex::structs::Level;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use serde::Deserialize;
+
+use crate::accounting::AccountingTotals;
+
+// Fees of a round trip: our bid is filled as maker, the liquidation is a taker market sell.
+// Rates are fractions of the premium notional (price * size), negative = rebate.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeSource {
+    // Rates of the configuration only
+    #[default]
+    Config,
+    // Rates observed on the account fills (configuration until fills of each kind are known)
+    Fills,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeeConfig {
+    pub source: FeeSource,
+    pub maker_rate: f64,
+    pub taker_rate: f64,
+    // Orders whose estimated round trip cost per $1k of volume is above are cancelled
+    pub max_cost_per_1k: Option<f64>,
+}
+
+impl Default for FeeConfig {
+    fn default() -> Self {
+        Self {
+            source: FeeSource::Config,
+            maker_rate: 0.0,
+            taker_rate: 0.0003,
+            max_cost_per_1k: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FeeModel {
+    pub maker_rate: f64,
+    pub taker_rate: f64,
+}
+
+impl FeeModel {
+    pub fn from_config(config: &FeeConfig) -> Self {
+        Self {
+            maker_rate: config.maker_rate,
+            taker_rate: config.taker_rate,
+        }
+    }
+
+    // Replace each rate by the one observed on the fills, when there are fills of this kind
+    pub fn calibrate(&mut self, totals: &AccountingTotals) {
+        if totals.maker_volume > 0.0 {
+            self.maker_rate = totals.maker_fees / totals.maker_volume;
+        }
+        if totals.taker_volume > 0.0 {
+            self.taker_rate = totals.taker_fees / totals.taker_volume;
+        }
+    }
+
+    pub fn maker_fee(&self, notional: f64) -> f64 {
+        notional * self.maker_rate
+    }
+
+    pub fn taker_fee(&self, notional: f64) -> f64 {
+        notional * self.taker_rate
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExitEstimate {
+    // Average price of the market sell
+    pub exit_price: f64,
+    pub volume: f64,
+    pub spread_loss: f64,
+    pub maker_fee: f64,
+    pub taker_fee: f64,
+}
+
+impl ExitEstimate {
+    pub fn cost(&self) -> f64 {
+        self.spread_loss + self.maker_fee + self.taker_fee
+    }
+
+    pub fn cost_per_1k(&self) -> Option<f64> {
+        if self.volume > 0.0 {
+            Some(self.cost() / self.volume * 1000.0)
+        } else {
+            None
+        }
+    }
+}
+
+// Round trip of our bid (price, size) filled then sold at market into the given bids.
+// Our own size is removed from the bids at `book_price`, the price of our order in the book if any
+// (our order is cancelled before selling). None if the bids cannot absorb the whole size.
+pub fn estimate_exit(
+    bids: &[&Level],
+    book_price: Option<Decimal>,
+    price: Decimal,
+    size: Decimal,
+    fees: &FeeModel,
+) -> Option<ExitEstimate> {
+    let price = price.to_f64()?;
+    let size = size.to_f64()?;
+    if size <= 0.0 {
+        return None;
+    }
+    let book_price = match book_price {
+        Some(book_price) => Some(book_price.to_f64()?),
+        None => None,
+    };
+    let mut sorted_bids: Vec<&Level> = bids.to_vec();
+    sorted_bids.sort_by(|a, b| b.price.total_cmp(&a.price));
+
+    let mut remaining = size;
+    let mut exit_notional = 0.0;
+    for bid in sorted_bids {
+        let available = if book_price.is_some_and(|book_price| (bid.price - book_price).abs() < f64::EPSILON) {
+            (bid.size - size).max(0.0)
+        } else {
+            bid.size
+        };
+        let sold = remaining.min(available);
+        exit_notional += sold * bid.price;
+        remaining -= sold;
+        if remaining <= f64::EPSILON {
+            break;
+        }
+    }
+    if remaining > f64::EPSILON {
+        return None;
+    }
+
+    let entry_notional = price * size;
+    Some(ExitEstimate {
+        exit_price: exit_notional / size,
+        volume: entry_notional + exit_notional,
+        spread_loss: entry_notional - exit_notional,
+        maker_fee: fees.maker_fee(entry_notional),
+        taker_fee: fees.taker_fee(exit_notional),
+    })
+}
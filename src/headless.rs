@@ -0,0 +1,245 @@
+use crossbeam::channel::{Receiver, Sender, select, unbounded};
+use paradex::url::URL;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, ExitCode};
+use std::thread::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use zeroize::Zeroizing;
+
+use crate::clock::Clock;
+use crate::config::{Account, BotConfig, Environment, ExchangeConfig, LoggingConfig};
+use crate::events::BackendEvent;
+use crate::journal::ReasonCode;
+use crate::keystore::{self, AccountKeys, KeystoreConfig};
+use crate::shutdown::shutdown_signal;
+use crate::{LogLevel, LogMessage, Logger, SharedLogLevel};
+
+// Bot without window (daemon, CLI): logs on stdout and in the daily log files.
+// SIGINT / SIGTERM run the shutdown sequence before exiting, fatal errors exit with a nonzero code.
+
+// Logs printed on stdout until `finish`
+pub struct ConsoleLogs {
+    sender: Sender<LogMessage>,
+    level: SharedLogLevel,
+    done_sender: Sender<()>,
+    printer: JoinHandle<()>,
+}
+
+impl ConsoleLogs {
+    pub fn start(level: LogLevel, config: &LoggingConfig) -> Self {
+        let (sender, receiver) = unbounded::<LogMessage>();
+        let level = SharedLogLevel::new(level);
+        // daily log files + logs of dependencies
+        if let Err(err) = crate::logging::init_logging(sender.clone(), level.clone(), config) {
+            eprintln!("{}", err);
+        }
+        let (done_sender, done_receiver) = unbounded::<()>();
+        let printer = std::thread::spawn(move || print_logs(receiver, done_receiver));
+        Self {
+            sender,
+            level,
+            done_sender,
+            printer,
+        }
+    }
+
+    pub fn logger(&self) -> Logger {
+        Logger::with_shared_level(self.sender.clone(), self.level.clone())
+    }
+
+    // Print the remaining logs
+    pub fn finish(self) {
+        drop(self.done_sender);
+        let _ = self.printer.join();
+    }
+}
+
+// Ask a yes / no question on the terminal
+pub fn ask_confirmation(question: &str) -> bool {
+    print!("{} Type 'yes' to continue: ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok() && answer.trim() == "yes"
+}
+
+// Read a line from the terminal without echo (stty on unix)
+pub fn read_secret(prompt: &str) -> Result<Zeroizing<String>, String> {
+    print!("{}", prompt);
+    let _ = std::io::stdout().flush();
+    let echo_off = cfg!(unix)
+        && std::io::stdin().is_terminal()
+        && Command::new("stty").arg("-echo").status().is_ok_and(|status| status.success());
+    let mut line = Zeroizing::new(String::new());
+    let result = std::io::stdin().read_line(&mut line);
+    if echo_off {
+        let _ = Command::new("stty").arg("echo").status();
+        println!();
+    }
+    result.map_err(|err| format!("Cannot read input: {}", err))?;
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    Ok(line)
+}
+
+// Keys of the accounts, the keystore password comes from the password file, the env variable or the terminal
+pub fn unlock_keys(config: &KeystoreConfig) -> Result<AccountKeys, String> {
+    if !keystore::is_locked(config)? {
+        return keystore::unlock_keys(config, None);
+    }
+    let password = if let Some(path) = &config.password_file {
+        keystore::read_password_file(path)?
+    } else if let Ok(password) = std::env::var(keystore::PASSWORD_VAR) {
+        eprintln!(
+            "Warning: keystore password read from {}, prefer keystore.password_file",
+            keystore::PASSWORD_VAR
+        );
+        Zeroizing::new(password)
+    } else if std::io::stdin().is_terminal() {
+        read_secret("Keystore password: ")?
+    } else {
+        return Err(format!(
+            "Keystore {} is locked: set keystore.password_file, {} or run from a terminal",
+            config.path.display(),
+            keystore::PASSWORD_VAR
+        ));
+    };
+    keystore::unlock_keys(config, Some(&password))
+}
+
+// Trading on production must be confirmed (flag or terminal prompt) unless disabled in config
+pub fn confirm_production(config: &ExchangeConfig, confirmed: bool) -> Result<(), String> {
+    if config.environment != Environment::Production || !config.confirm_production || confirmed {
+        return Ok(());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(
+            "Trading on production must be confirmed: pass --yes or set exchange.confirm_production = false"
+                .to_string(),
+        );
+    }
+    if ask_confirmation("Trading on PRODUCTION with real funds.") {
+        Ok(())
+    } else {
+        Err("Aborted.".to_string())
+    }
+}
+
+// Run the bot loop until it ends, a fatal error or a stop signal, on the server `url`.
+// `confirmed`: production trading already confirmed by the operator
+pub async fn run(config: BotConfig, url: URL, log_level: LogLevel, confirmed: bool) -> ExitCode {
+    if let Err(err) = confirm_production(&config.exchange, confirmed) {
+        eprintln!("{}", err);
+        return ExitCode::FAILURE;
+    }
+    let keys = match unlock_keys(&config.keystore) {
+        Ok(keys) => keys,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    let console = ConsoleLogs::start(log_level, &config.logging);
+    let logger = console.logger();
+    logger.info(format!(
+        "Bot Paradex v{} (headless, {})",
+        env!("CARGO_PKG_VERSION"),
+        config.exchange.describe()
+    ));
+    // the bot trades without its metrics when the address is taken
+    if config.metrics.enabled
+        && let Err(err) = crate::metrics::serve(&logger, &config.metrics)
+    {
+        logger.error(err);
+    }
+
+    // back-end events are only displayed by the GUI
+    let (event_sender, _) = unbounded::<BackendEvent>();
+    let shutdown = CancellationToken::new();
+    let recorder = config.recorder.enabled.then(|| {
+        tokio::spawn(crate::recorder::run_recorder(
+            logger.clone(),
+            config.recorder.clone(),
+            url,
+            Clock::System,
+            shutdown.clone(),
+        ))
+    });
+    let mut backend = tokio::spawn(crate::run_accounts(
+        console.sender.clone(),
+        event_sender,
+        console.level.clone(),
+        config,
+        url,
+        keys,
+        shutdown.clone(),
+    ));
+    let result = tokio::select! {
+        result = &mut backend => result,
+        signal = shutdown_signal() => {
+            logger.info(format!("{} received.", signal));
+            shutdown.cancel();
+            tokio::select! {
+                result = &mut backend => result,
+                signal = shutdown_signal() => {
+                    logger.warn(format!("{} received again, not waiting for the shutdown.", signal));
+                    backend.abort();
+                    backend.await
+                }
+            }
+        }
+    };
+    // the recorder stops with the bot
+    if let Some(recorder) = recorder {
+        shutdown.cancel();
+        match recorder.await {
+            Ok(Err(err)) => logger.error(err),
+            Err(err) => logger.error(format!("Recorder crashed: {}", err)),
+            Ok(Ok(())) => {}
+        }
+    }
+    let exit_code = match result {
+        Ok(Ok(())) => ExitCode::SUCCESS,
+        Ok(Err(err)) => {
+            logger.error(err);
+            ExitCode::FAILURE
+        }
+        Err(err) => {
+            logger.error(format!("Back-end crashed: {}", err));
+            ExitCode::FAILURE
+        }
+    };
+
+    drop(logger);
+    console.finish();
+    exit_code
+}
+
+pub async fn cancel_bot_orders(
+    logger: &Logger,
+    url: URL,
+    account: &Account,
+    l2_key: Option<String>,
+    reason: ReasonCode,
+) -> Result<(), String> {
+    let journal = crate::open_journal(logger, &account.journal, &Clock::System, Clock::System.now());
+    let mut exchange = crate::exchange::connect(logger, url, l2_key, Clock::System).await?;
+    crate::cancel_bot_orders(logger, &journal, &mut exchange, reason).await
+}
+
+// The log bridge keeps a sender forever, so the end is signalled by dropping `done`
+fn print_logs(logs: Receiver<LogMessage>, done: Receiver<()>) {
+    loop {
+        select! {
+            recv(logs) -> log => match log {
+                Ok(log) => println!("{} [{}] {}", log.timestamp, log.level, log.message),
+                Err(_) => return,
+            },
+            recv(done) -> _ => {
+                for log in logs.try_iter() {
+                    println!("{} [{}] {}", log.timestamp, log.level, log.message);
+                }
+                return;
+            }
+        }
+    }
+}
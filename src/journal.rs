@@ -0,0 +1,284 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use paradex::structs::{Fill, Side};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, LineWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::clock::Clock;
+
+// Append-only journal of the order actions and fills, one JSON object per line (JSONL)
+
+// Bytes read at once when the end of the journal is read backward
+const TAIL_CHUNK: u64 = 64 * 1024;
+// Delay of the exchange between the creation of a fill and its journal entry, clock skew included
+const FILL_DELAY_MARGIN: TimeDelta = TimeDelta::hours(1);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalAction {
+    Modify,
+    Cancel,
+    // Cancel of every order of a market
+    CancelAll,
+    Fill,
+    // Market sell closing a position (the only orders created by the bot)
+    Liquidation,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasonCode {
+    // Bid moved to first bid / closer to second bid
+    Reprice,
+    // Not enough size right below our bid to absorb our market sell
+    LowLiquidity,
+    // An Option position is open in the market
+    PositionOpen,
+    // Fill reported by the exchange
+    Exchange,
+    // Volume target of the period reached
+    TargetReached,
+    // Spending budget of the period exhausted
+    BudgetExhausted,
+    // Estimated round trip cost per $1k of volume above the maximum
+    TooExpensive,
+    // Bot stopped by a signal
+    Shutdown,
+    // Command of the operator (CLI)
+    Manual,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: JournalAction,
+    pub reason: ReasonCode,
+    pub market: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fill_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub side: Option<Side>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Decimal>,
+    pub success: bool,
+    // Exchange response (or error) of the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response: Option<serde_json::Value>,
+}
+
+impl JournalEntry {
+    pub fn new(clock: &Clock, action: JournalAction, reason: ReasonCode, market: impl Into<String>) -> Self {
+        Self {
+            timestamp: clock.now(),
+            action,
+            reason,
+            market: market.into(),
+            order_id: None,
+            fill_id: None,
+            side: None,
+            price: None,
+            size: None,
+            fee: None,
+            success: true,
+            response: None,
+        }
+    }
+
+    pub fn from_fill(clock: &Clock, fill: &Fill) -> Self {
+        Self {
+            timestamp: DateTime::from_timestamp_millis(fill.created_at as i64)
+                .unwrap_or_else(|| clock.now()),
+            order_id: Some(fill.order_id.clone()),
+            fill_id: Some(fill.id.clone()),
+            side: Some(fill.side),
+            price: Decimal::from_f64(fill.price),
+            size: Decimal::from_f64(fill.size),
+            fee: Decimal::from_f64(fill.fee),
+            response: serde_json::to_value(fill).ok(),
+            ..Self::new(clock, JournalAction::Fill, ReasonCode::Exchange, fill.market.clone())
+        }
+    }
+
+    // Keep the exchange response, serialized when possible
+    pub fn with_result<T: Serialize, E: std::fmt::Display>(mut self, result: &Result<T, E>) -> Self {
+        match result {
+            Ok(response) => {
+                self.success = true;
+                self.response = serde_json::to_value(response).ok();
+            }
+            Err(err) => {
+                self.success = false;
+                self.response = Some(serde_json::Value::String(err.to_string()));
+            }
+        }
+        self
+    }
+}
+
+pub struct Journal {
+    path: PathBuf,
+    writer: Option<Mutex<LineWriter<File>>>,
+    // Fills already journaled, also from previous sessions since the start of the fills query
+    fill_ids: Mutex<HashSet<String>>,
+    // Time of the entries
+    clock: Clock,
+}
+
+impl Journal {
+    // The fills created before `fills_since` are not queried again: only the end of the file is read
+    pub fn open(path: &Path, fills_since: DateTime<Utc>) -> std::io::Result<Journal> {
+        if let Some(directory) = path.parent()
+            && !directory.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(directory)?;
+        }
+        let fill_ids = Self::recent_fill_ids(path, fills_since)?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Journal {
+            path: path.to_path_buf(),
+            writer: Some(Mutex::new(LineWriter::new(file))),
+            fill_ids: Mutex::new(fill_ids),
+            clock: Clock::System,
+        })
+    }
+
+    // Journal writing nothing (disabled in config)
+    pub fn disabled() -> Journal {
+        Journal {
+            path: PathBuf::new(),
+            writer: None,
+            fill_ids: Mutex::new(HashSet::new()),
+            clock: Clock::System,
+        }
+    }
+
+    // Same journal with entries timed by the given clock
+    pub fn with_clock(self, clock: Clock) -> Self {
+        Self { clock, ..self }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    pub fn record(&self, entry: &JournalEntry) {
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        match serde_json::to_string(entry) {
+            Ok(line) => {
+                if let Ok(mut writer) = writer.lock()
+                    && let Err(err) = writeln!(writer, "{}", line)
+                {
+                    log::error!("Cannot write journal {}: {}", self.path.display(), err);
+                }
+            }
+            Err(err) => log::error!("Cannot serialize journal entry: {}", err),
+        }
+    }
+
+    pub fn flush(&self) {
+        if let Some(writer) = &self.writer
+            && let Ok(mut writer) = writer.lock()
+            && let Err(err) = writer.flush()
+        {
+            log::error!("Cannot flush journal {}: {}", self.path.display(), err);
+        }
+    }
+
+    // Record the fill unless already journaled
+    pub fn record_fill(&self, fill: &Fill) {
+        let is_new = self
+            .fill_ids
+            .lock()
+            .map(|mut fill_ids| fill_ids.insert(fill.id.clone()))
+            .unwrap_or(false);
+        if is_new {
+            self.record(&JournalEntry::from_fill(&self.clock, fill));
+        }
+    }
+
+    // Ids of the fills created since `since`, from the last line back to an entry other than a fill
+    // written well before (the entries are appended in time order, a fill some time after its creation)
+    fn recent_fill_ids(path: &Path, since: DateTime<Utc>) -> std::io::Result<HashSet<String>> {
+        let mut fill_ids = HashSet::new();
+        let mut file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(fill_ids),
+            Err(err) => return Err(err),
+        };
+        let mut position = file.metadata()?.len();
+        // start of the first line of the bytes read, completed by the next chunk
+        let mut partial = Vec::new();
+        while position > 0 {
+            let size = TAIL_CHUNK.min(position);
+            position -= size;
+            file.seek(SeekFrom::Start(position))?;
+            let mut chunk = vec![0; size as usize];
+            file.read_exact(&mut chunk)?;
+            chunk.append(&mut partial);
+            let mut lines: Vec<&[u8]> = chunk.split(|byte| *byte == b'\n').collect();
+            if position > 0 {
+                partial = lines.remove(0).to_vec();
+            }
+            for line in lines.into_iter().rev() {
+                // blank or truncated line (crash): skipped like in `read`
+                let Ok(entry) = serde_json::from_slice::<JournalEntry>(line) else {
+                    continue;
+                };
+                if entry.action == JournalAction::Fill {
+                    if let Some(fill_id) = entry.fill_id
+                        && entry.timestamp >= since
+                    {
+                        fill_ids.insert(fill_id);
+                    }
+                } else if entry.timestamp < since - FILL_DELAY_MARGIN {
+                    return Ok(fill_ids);
+                }
+            }
+        }
+        Ok(fill_ids)
+    }
+
+    // Every entry of a journal file, missing file = no entry
+    pub fn read(path: &Path) -> std::io::Result<Vec<JournalEntry>> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut entries = Vec::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                // a truncated last line (crash) must not prevent reading the rest
+                Err(err) => log::warn!(
+                    "Skipping invalid journal line {} of {}: {}",
+                    index + 1,
+                    path.display(),
+                    err
+                ),
+            }
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,318 @@
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::config::Account;
+
+// Encrypted keystore of the L2 private keys, one per account name.
+// A 32 bytes key is derived from the password with scrypt (random salt of the file) and encrypts each
+// L2 key with AES-256-GCM (random nonce per key). The version, the scrypt parameters and the account
+// name are authenticated with the key as associated data.
+
+// 1: AES-256-CTR + HMAC-SHA256, no longer read
+const VERSION: u32 = 2;
+const SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+// Password of the keystore for unattended runs (daemon) when there is no password file, prompted otherwise.
+// Fallback only: the environment of a process can be read by other processes of the user
+pub const PASSWORD_VAR: &str = "BOT_PARADEX_KEYSTORE_PASSWORD";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct KeystoreConfig {
+    pub path: PathBuf,
+    // Read the keys missing from the keystore in the env variables (PARADEX_L2_KEY...)
+    pub env_fallback: bool,
+    // File holding the password for unattended runs, readable by the owner only (mode 0600)
+    pub password_file: Option<PathBuf>,
+}
+
+impl Default for KeystoreConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("keystore.json"),
+            env_fallback: false,
+            password_file: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct KdfParams {
+    log_n: u8,
+    r: u32,
+    p: u32,
+    salt: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct KeyEntry {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    nonce: String,
+    // Encrypted key followed by the GCM tag
+    ciphertext: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Keystore {
+    version: u32,
+    kdf: KdfParams,
+    keys: Vec<KeyEntry>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Keystore {
+    // Missing file = empty keystore, created by `save`
+    pub fn load(path: &Path) -> Result<Keystore, String> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => {
+                let mut keystore: Keystore = serde_json::from_str(&content)
+                    .map_err(|err| format!("Invalid keystore {}: {}", path.display(), err))?;
+                if keystore.version != VERSION {
+                    return Err(format!(
+                        "Unsupported keystore version {} in {}: import the keys again into a new keystore",
+                        keystore.version,
+                        path.display()
+                    ));
+                }
+                keystore.path = path.to_path_buf();
+                Ok(keystore)
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Keystore {
+                version: VERSION,
+                kdf: KdfParams {
+                    log_n: SCRYPT_LOG_N,
+                    r: SCRYPT_R,
+                    p: SCRYPT_P,
+                    salt: hex::encode(random_bytes::<SALT_LEN>()?),
+                },
+                keys: Vec::new(),
+                path: path.to_path_buf(),
+            }),
+            Err(err) => Err(format!("Cannot read keystore {}: {}", path.display(), err)),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn entries(&self) -> &[KeyEntry] {
+        &self.keys
+    }
+
+    fn derive(&self, password: &str) -> Result<Aes256Gcm, String> {
+        let params = scrypt::Params::new(self.kdf.log_n, self.kdf.r, self.kdf.p)
+            .map_err(|err| format!("Invalid keystore parameters: {}", err))?;
+        let salt = hex::decode(&self.kdf.salt).map_err(|err| format!("Invalid keystore salt: {}", err))?;
+        let mut output = Zeroizing::new([0u8; 32]);
+        scrypt::scrypt(password.as_bytes(), &salt, &params, output.as_mut())
+            .map_err(|err| format!("Key derivation failed: {}", err))?;
+        Aes256Gcm::new_from_slice(output.as_ref()).map_err(|err| format!("Key derivation failed: {}", err))
+    }
+
+    // Authenticated with the key of an account: altering the header of the file fails the decryption
+    fn associated_data(&self, name: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&self.version.to_be_bytes());
+        data.push(self.kdf.log_n);
+        data.extend_from_slice(&self.kdf.r.to_be_bytes());
+        data.extend_from_slice(&self.kdf.p.to_be_bytes());
+        data.extend_from_slice(&(self.kdf.salt.len() as u32).to_be_bytes());
+        data.extend_from_slice(self.kdf.salt.as_bytes());
+        data.extend_from_slice(name.as_bytes());
+        data
+    }
+
+    // Every key of the keystore, Err if the password is wrong or the file was altered
+    pub fn unlock(&self, password: &str) -> Result<HashMap<String, String>, String> {
+        let cipher = self.derive(password)?;
+        self.keys
+            .iter()
+            .map(|entry| self.decrypt(&cipher, entry).map(|key| (entry.name.clone(), key)))
+            .collect()
+    }
+
+    // Add or replace the key of an account, the password must be the one of the other keys
+    pub fn insert(&mut self, name: &str, key: &str, password: &str) -> Result<(), String> {
+        let cipher = self.derive(password)?;
+        for entry in &self.keys {
+            self.decrypt(&cipher, entry)?;
+        }
+        let entry = self.encrypt(&cipher, name, key)?;
+        self.keys.retain(|entry| entry.name != name);
+        self.keys.push(entry);
+        self.keys.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(())
+    }
+
+    fn encrypt(&self, cipher: &Aes256Gcm, name: &str, key: &str) -> Result<KeyEntry, String> {
+        let nonce = random_bytes::<NONCE_LEN>()?;
+        let payload = Payload {
+            msg: key.as_bytes(),
+            aad: &self.associated_data(name),
+        };
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| format!("Cannot encrypt the key of {}", name))?;
+        Ok(KeyEntry {
+            name: name.to_string(),
+            created_at: Utc::now(),
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    fn decrypt(&self, cipher: &Aes256Gcm, entry: &KeyEntry) -> Result<String, String> {
+        let invalid = |err: hex::FromHexError| format!("Invalid key {} in keystore: {}", entry.name, err);
+        let nonce = hex::decode(&entry.nonce).map_err(invalid)?;
+        let ciphertext = hex::decode(&entry.ciphertext).map_err(invalid)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(format!("Invalid nonce of key {} in keystore", entry.name));
+        }
+        let payload = Payload {
+            msg: &ciphertext,
+            aad: &self.associated_data(&entry.name),
+        };
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(&nonce), payload)
+            .map_err(|_| "Wrong keystore password (or altered keystore)".to_string())?;
+        String::from_utf8(plaintext).map_err(|err| {
+            err.into_bytes().zeroize();
+            format!("Invalid key {} in keystore", entry.name)
+        })
+    }
+
+    pub fn remove(&mut self, name: &str) -> bool {
+        let count = self.keys.len();
+        self.keys.retain(|entry| entry.name != name);
+        self.keys.len() != count
+    }
+
+    // Write through a temporary file, created readable by the owner only
+    pub fn save(&self) -> Result<(), String> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|err| format!("Cannot serialize keystore: {}", err))?;
+        if let Some(directory) = self.path.parent()
+            && !directory.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(directory)
+                .map_err(|err| format!("Cannot create {}: {}", directory.display(), err))?;
+        }
+        let temporary = self.path.with_extension("tmp");
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+            options.mode(0o600);
+            // left by an interrupted save: the mode only applies to new files
+            if temporary.exists() {
+                std::fs::set_permissions(&temporary, std::fs::Permissions::from_mode(0o600))
+                    .map_err(|err| format!("Cannot write {}: {}", temporary.display(), err))?;
+            }
+        }
+        options
+            .open(&temporary)
+            .and_then(|mut file| file.write_all(content.as_bytes()).and_then(|_| file.sync_all()))
+            .map_err(|err| format!("Cannot write {}: {}", temporary.display(), err))?;
+        std::fs::rename(&temporary, &self.path)
+            .map_err(|err| format!("Cannot write {}: {}", self.path.display(), err))
+    }
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    getrandom::getrandom(&mut bytes).map_err(|err| format!("No random source: {}", err))?;
+    Ok(bytes)
+}
+
+
+// L2 keys of the accounts: unlocked keystore, then env variables if the fallback is enabled
+#[derive(Clone, Default)]
+pub struct AccountKeys {
+    keys: HashMap<String, String>,
+    env_fallback: bool,
+}
+
+impl Drop for AccountKeys {
+    fn drop(&mut self) {
+        self.keys.values_mut().for_each(|key| key.zeroize());
+    }
+}
+
+impl AccountKeys {
+    pub fn get(&self, account: &Account) -> Option<String> {
+        self.keys.get(&account.name).cloned().or_else(|| {
+            if self.env_fallback {
+                std::env::var(&account.key_var).ok()
+            } else {
+                None
+            }
+        })
+    }
+}
+
+// True when a password is needed to start (keys stored in the keystore)
+pub fn is_locked(config: &KeystoreConfig) -> Result<bool, String> {
+    Ok(!Keystore::load(&config.path)?.is_empty())
+}
+
+// Password of the password file without the final line break, Err if other users can read it
+pub fn read_password_file(path: &Path) -> Result<Zeroizing<String>, String> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let metadata =
+            std::fs::metadata(path).map_err(|err| format!("Cannot read password file {}: {}", path.display(), err))?;
+        if metadata.permissions().mode() & 0o077 != 0 {
+            return Err(format!(
+                "Password file {} is readable by other users: chmod 600 it",
+                path.display()
+            ));
+        }
+    }
+    let mut password = Zeroizing::new(
+        std::fs::read_to_string(path)
+            .map_err(|err| format!("Cannot read password file {}: {}", path.display(), err))?,
+    );
+    let len = password.trim_end_matches(['\r', '\n']).len();
+    password.truncate(len);
+    Ok(password)
+}
+
+// Keys of the keystore (password required when it is not empty) with the env fallback
+pub fn unlock_keys(config: &KeystoreConfig, password: Option<&str>) -> Result<AccountKeys, String> {
+    let keystore = Keystore::load(&config.path)?;
+    let keys = if keystore.is_empty() {
+        HashMap::new()
+    } else {
+        let password = password.ok_or("Keystore password required")?;
+        keystore.unlock(password)?
+    };
+    if keys.is_empty() && !config.env_fallback {
+        return Err(format!(
+            "No key in keystore {}: import one with `cli keys import <account>` or set keystore.env_fallback = true",
+            config.path.display()
+        ));
+    }
+    Ok(AccountKeys {
+        keys,
+        env_fallback: config.env_fallback,
+    })
+}
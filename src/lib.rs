@@ -1,24 +1,61 @@
-use paradex::{rest::Client, structs, url::URL, ws};
+use paradex::{rest::Client, structs, url::URL};
 use structs::{
-    ModifyOrderRequest, OrderInstruction, OrderRequest, OrderType, OrderUpdate, OrderUpdates,
-    Position, PositionStatus, Side,
+    ModifyOrderRequest, OrderInstruction, OrderRequest, OrderType, OrderUpdate,
+    OrderUpdates, Position, PositionSide, PositionStatus, Positions, Side,
 };
-use ws::{Channel, Message, WebsocketManager};
 
 use rust_decimal::Decimal;
 use rust_decimal::prelude::FromPrimitive;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
 
 mod orderbook_state;
-use orderbook_state::OrderBookState;
+
+pub mod accounting;
+pub mod backtest;
+pub mod book_viewer;
+pub mod clock;
+pub mod config;
+pub mod events;
+pub mod exchange;
+pub mod fees;
+pub mod headless;
+pub mod journal;
+pub mod keystore;
+pub mod logging;
+pub mod metrics;
+pub mod recorder;
+pub mod shutdown;
+pub mod sim;
+pub mod strategy;
+pub mod sweep;
+pub mod targets;
+use events::{AccountEventSender, BackendEvent};
+use exchange::Exchange;
+use keystore::AccountKeys;
+use journal::{Journal, JournalAction, JournalEntry, ReasonCode};
+use config::{Account, BotConfig, JournalConfig};
+use accounting::Accounting;
+use clock::Clock;
+use fees::{ExitEstimate, FeeModel, FeeSource};
+use strategy::{BookTop, QuoteRules, StrategyParams};
+use targets::{StopReason, TargetProgress};
 
 use crossbeam::channel::Sender;
-use chrono::Local;
+use tokio_util::sync::CancellationToken;
+use chrono::{DateTime, Local, NaiveDate, Utc};
 
 // Log structure
+#[derive(Clone)]
 pub struct Logger {
     sender: Sender<crate::LogMessage>,
-    min_level: LogLevel,
+    min_level: SharedLogLevel,
+    // Prefix of the messages of an account worker
+    account: Option<String>,
+    // Time of the messages
+    clock: Clock,
 }
 
 #[derive(Clone, Copy, PartialEq, PartialOrd, Debug)]
@@ -29,121 +66,169 @@ pub enum LogLevel {
     Error = 3,
 }
 
+impl LogLevel {
+    pub const ALL: [LogLevel; 4] = [LogLevel::Debug, LogLevel::Info, LogLevel::Warn, LogLevel::Error];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LogLevel> {
+        LogLevel::ALL
+            .into_iter()
+            .find(|level| level.as_str().eq_ignore_ascii_case(name))
+    }
+
+    fn from_u8(value: u8) -> LogLevel {
+        match value {
+            0 => LogLevel::Debug,
+            1 => LogLevel::Info,
+            2 => LogLevel::Warn,
+            _ => LogLevel::Error,
+        }
+    }
+}
+
+// Minimum log level shared by every logger and the front-end, so that it can be changed at runtime
+#[derive(Clone, Debug)]
+pub struct SharedLogLevel(Arc<AtomicU8>);
+
+impl SharedLogLevel {
+    pub fn new(level: LogLevel) -> Self {
+        Self(Arc::new(AtomicU8::new(level as u8)))
+    }
+
+    pub fn get(&self) -> LogLevel {
+        LogLevel::from_u8(self.0.load(Ordering::Relaxed))
+    }
+
+    pub fn set(&self, level: LogLevel) {
+        self.0.store(level as u8, Ordering::Relaxed);
+    }
+}
+
 impl Logger {
     pub fn new(sender: Sender<crate::LogMessage>) -> Self {
         Self { 
             sender,
-            min_level: LogLevel::Debug, // default: debug
+            min_level: SharedLogLevel::new(LogLevel::Debug), // default: debug
+            account: None,
+            clock: Clock::System,
         }
     }
 
     pub fn with_level(sender: Sender<crate::LogMessage>, min_level: LogLevel) -> Self {
         Self { 
+            sender,
+            min_level: SharedLogLevel::new(min_level),
+            account: None,
+            clock: Clock::System,
+        }
+    }
+
+    pub fn with_shared_level(sender: Sender<crate::LogMessage>, min_level: SharedLogLevel) -> Self {
+        Self {
             sender,
             min_level,
+            account: None,
+            clock: Clock::System,
+        }
+    }
+
+    // Same logger with messages prefixed by the account name
+    pub fn for_account(&self, account: &str) -> Self {
+        Self {
+            account: Some(account.to_string()),
+            ..self.clone()
+        }
+    }
+
+    // Same logger with messages timed by the given clock
+    pub fn with_clock(&self, clock: Clock) -> Self {
+        Self {
+            clock,
+            ..self.clone()
         }
     }
 
     pub fn set_level(&mut self, level: LogLevel) {
-        self.min_level = level;
+        self.min_level.set(level);
     }
 
     pub fn info(&self, message: impl Into<String>) {
-        if LogLevel::Info >= self.min_level {
-            self.log("INFO", message.into());
+        if LogLevel::Info >= self.min_level.get() {
+            self.log(LogLevel::Info, message.into());
         }
     }
 
     pub fn warn(&self, message: impl Into<String>) {
-        if LogLevel::Warn >= self.min_level {
-            self.log("WARN", message.into());
+        if LogLevel::Warn >= self.min_level.get() {
+            self.log(LogLevel::Warn, message.into());
         }
     }
 
     pub fn error(&self, message: impl Into<String>) {
-        if LogLevel::Error >= self.min_level {
-            self.log("ERROR", message.into());
+        if LogLevel::Error >= self.min_level.get() {
+            self.log(LogLevel::Error, message.into());
         }
     }
 
     pub fn debug(&self, message: impl Into<String>) {
-        if LogLevel::Debug >= self.min_level {
-            self.log("DEBUG", message.into());
+        if LogLevel::Debug >= self.min_level.get() {
+            self.log(LogLevel::Debug, message.into());
         }
     }
 
-    fn log(&self, level: &str, message: String) {
-        let timestamp = Local::now().format("%H:%M:%S%.3f").to_string();
-        let log_msg = crate::LogMessage {
-            timestamp,
-            level: level.to_string(),
-            message: message.clone(),
+    fn log(&self, level: LogLevel, message: String) {
+        let message = match &self.account {
+            Some(account) => format!("[{}] {}", account, message),
+            None => message,
         };
-        
+        let log_msg = crate::LogMessage::at(self.clock.local_now(), level, message);
+
+        // Write to daily file
+        logging::write_to_file(&log_msg);
         // Send to front-end
-        let _ = self.sender.send(log_msg);
+        logging::send(&self.sender, log_msg);
     }
 }
 
 // Struct LogMessage
 #[derive(Clone, Debug)]
 pub struct LogMessage {
+    // Day of the log file
+    pub day: NaiveDate,
     pub timestamp: String,
     pub level: String,
     pub message: String,
 }
 
-const REFRESH_TIME_SEC: u64 = 10;
-const SIZE_MULTIPLIER_BIDDING_MARGIN: i32 = 5;
-const STEP_SIZE: f64 = 0.1;
-const MAX_SPREAD_PRICE: i32 = 5;
+impl LogMessage {
+    pub fn now(level: LogLevel, message: String) -> Self {
+        Self::at(Local::now(), level, message)
+    }
 
-async fn run_orderbook_subscription(
-    logger: &Logger,
-    manager: &mut WebsocketManager,
-    market_symbol: String,
-    state: &OrderBookState,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let clones = state.clone_for_callback();
-    // Get the order book using public manager
-    logger.debug(format!("The market of the order is: {:?}", market_symbol));
-    let logger_sender = logger.sender.clone();
-    let logger_level = logger.min_level;
-    let orderbook_id = manager
-        .subscribe(
-            Channel::OrderBook {
-                market_symbol: market_symbol.clone(),
-                channel_name: None,
-                refresh_rate: "50ms".into(),
-                price_tick: None,
-            },
-            Box::new(move |message| {
-                let logger_local = Logger::with_level(logger_sender.clone(), logger_level);
-                logger_local.debug("Received message!");
-                match message {
-                    Message::OrderBook(ob_snapshot) => {
-                        orderbook_state::extract_data_from_snapshot(&ob_snapshot, &clones);
-                    }
-                    // ignore other variants for the moment
-                    _ => {}
-                }
-            }),
-        )
-        .await
-        .unwrap();
-    // wait for message
-    logger.debug(format!("Waiting for OrderBook snapshot notification..."));
-    state.notify.notified().await;
-    // then unsubscribe
-    logger.debug(format!("Notification received! Unsubscribing..."));
-    manager.unsubscribe(orderbook_id).await.unwrap();
-
-    Ok(())
+    pub fn at(time: DateTime<Local>, level: LogLevel, message: String) -> Self {
+        Self {
+            day: time.date_naive(),
+            timestamp: time.format("%H:%M:%S%.3f").to_string(),
+            level: level.as_str().to_string(),
+            message,
+        }
+    }
 }
 
+pub(crate) const REFRESH_TIME_SEC: u64 = 10;
+
 async fn adjust_order(
     logger: &Logger,
-    client_private: &mut Client,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
     order_id: String,
     order_market: String,
     order_size: Decimal,
@@ -158,144 +243,366 @@ async fn adjust_order(
         order_type: OrderType::LIMIT,
     };
     logger.info(format!("Sending modify order {modify_request:?}"));
-    let result = client_private.modify_order(modify_request).await.unwrap();
-    logger.info(format!("Modify order result {result:?}"));
+    let entry = JournalEntry {
+        order_id: Some(modify_request.id.clone()),
+        side: Some(modify_request.side),
+        price: modify_request.price,
+        size: Some(modify_request.size),
+        ..JournalEntry::new(journal.clock(), JournalAction::Modify, ReasonCode::Reprice, modify_request.market.clone())
+    };
+    let result = exchange.modify_order(modify_request).await;
+    journal.record(&entry.with_result(&result));
+    match result {
+        Ok(result) => logger.info(format!("Modify order result {result:?}")),
+        Err(err) => logger.error(format!("Failed to modify order: {}", err)),
+    }
 }
 
-async fn determine_new_bid_price(
+fn determine_new_bid_price(
     logger: &Logger,
     order: &OrderUpdate,
-    state: &OrderBookState,
-    step_size: Decimal,
+    book: &BookTop,
+    params: &StrategyParams,
 ) -> Option<Decimal> {
-    // Keep old price
-    let current_price = order.price.unwrap_or_default();
-    let mut new_price = current_price;
-
-    if let Some(bid) = state.first_bid.lock().unwrap().as_ref() {
-        logger.debug(format!("First bid written by callback: {:?}", bid));
-        if let Some(decimal_price) = order.price {
-            // compare two prices as decimals
-            if let Some(bid_price_decimal) = Decimal::from_f64(bid.price) {
-                if decimal_price == bid_price_decimal {
-                    logger.debug(format!("We are first bid!"));
-                    // Check if we are + step_size from second bid or not alone at first bid
-                    if let Some(bid_size_decimal) = Decimal::from_f64(bid.size) {
-                        if order.size == bid_size_decimal {
-                            logger.debug(format!(
-                                "We are first bid alone at the top! Checking if need to re-price."
-                            ));
-                            if let Some(sec_bid) = state.second_bid.lock().unwrap().as_ref() {
-                                if let Some(sec_bid_size_decimal) = Decimal::from_f64(sec_bid.price)
-                                {
-                                    if new_price - sec_bid_size_decimal > step_size {
-                                        logger.debug(format!("Need re-adjust!"));
-                                        new_price = sec_bid_size_decimal + step_size;
-                                    }
-                                }
-                            }
-                        } else {
-                            logger.debug(format!("We are not alone at the top. Checking if can go first bid alone."));
-                            if let Some(ask) = state.first_ask.lock().unwrap().as_ref() {
-                                if let Some(ask_price_decimal) = Decimal::from_f64(ask.price) {
-                                    if ask_price_decimal != bid_price_decimal + step_size {
-                                        new_price = bid_price_decimal + step_size;
-                                    }
-                                }
-                            }
-                        }
-                    }
-                } else {
-                    logger.debug(format!("We are NOT first bid!"));
-                    new_price = bid_price_decimal;
-                }
-            } else {
-                logger.error(format!("cannot convert f64 into decimal"));
-            }
-        }
+    logger.debug(format!("Book top written by callback: {:?}", book));
+    let new_price = strategy::determine_new_bid_price(order.price, order.size, book, params);
+    if new_price != order.price {
+        logger.debug(format!("Re-price from {:?} to {:?}", order.price, new_price));
     }
-    Some(new_price)
+    new_price
 }
 
+async fn cancel_order_with_reason(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    order: &OrderUpdate,
+    reason: ReasonCode,
+) {
+    let result = exchange.cancel_order(order.id.clone()).await;
+    logger.info(format!("Cancelling order result {result:?}"));
+    let entry = JournalEntry {
+        order_id: Some(order.id.clone()),
+        side: Some(order.side),
+        price: order.price,
+        size: Some(order.size),
+        ..JournalEntry::new(journal.clock(), JournalAction::Cancel, reason, order.market.clone())
+    };
+    journal.record(&entry.with_result(&result));
+}
+
+// Returns true if the order was cancelled
 async fn check_liquidity_and_cancel_if_low(
     logger: &Logger,
-    client_private: &mut Client,
-    order: OrderUpdate,
-    state: &OrderBookState,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    order: &OrderUpdate,
+    book: &BookTop,
     new_price: Option<Decimal>,
+    params: &StrategyParams,
+) -> bool {
+    // Global size of first 3 bids inside the exit window
+    if let Some(glob_size) = strategy::exit_liquidity(book, new_price, params) {
+        logger.debug(format!("Global used size: {:?}", glob_size));
+
+        if strategy::is_liquidity_low(order.size, glob_size, params) {
+            // cancel order.
+            cancel_order_with_reason(logger, journal, exchange, order, ReasonCode::LowLiquidity)
+                .await;
+            return true;
+        }
+    }
+    false
+}
+
+// Cancel the order if fees + spread loss of its round trip are too high for the volume
+async fn check_exit_cost_and_cancel_if_high(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    order: &OrderUpdate,
+    estimate: Option<ExitEstimate>,
+    max_cost_per_1k: f64,
 ) {
-    // Take the global size of first 3 bids
-    let bid1_opt = state.first_bid.lock().unwrap();
-    let bid2_opt = state.second_bid.lock().unwrap();
-    let bid3_opt = state.third_bid.lock().unwrap();
-    if let (Some(bid1), Some(bid2), Some(bid3)) =
-        (bid1_opt.as_ref(), bid2_opt.as_ref(), bid3_opt.as_ref())
-    {
-        if let Some(price_to_adjust) = new_price {
-            let mut glob_size: f64 = 0.0;
-            // Max spread is MAX_SPREAD_PRICE
-            let max_diff_decimal = Decimal::from_i32(MAX_SPREAD_PRICE).unwrap_or_default();
-            // Bid 1
-            if let Some(bid1_price_decimal) = Decimal::from_f64(bid1.price) {
-                if price_to_adjust - bid1_price_decimal < max_diff_decimal {
-                    glob_size += bid1.size;
-                }
-            }
-            // Bid 2
-            if let Some(bid2_price_decimal) = Decimal::from_f64(bid2.price) {
-                if price_to_adjust - bid2_price_decimal < max_diff_decimal {
-                    glob_size += bid2.size;
+    logger.debug(format!("Exit estimate {estimate:?}"));
+
+    if strategy::is_too_expensive(estimate.as_ref(), max_cost_per_1k) {
+        match estimate.and_then(|estimate| estimate.cost_per_1k()) {
+            Some(cost_per_1k) => logger.info(format!(
+                "Round trip too expensive on {}: ${:.2} per $1k (max ${:.2})",
+                order.market, cost_per_1k, max_cost_per_1k
+            )),
+            None => logger.info(format!("No exit for our size in the bids of {}", order.market)),
+        }
+        cancel_order_with_reason(logger, journal, exchange, order, ReasonCode::TooExpensive)
+            .await;
+    }
+}
+
+// Returns the number of new fills
+async fn publish_new_fills(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &impl Exchange,
+    event_sender: &AccountEventSender,
+    seen_fills: &mut HashMap<String, DateTime<Utc>>,
+    accounting: &mut Accounting,
+    fills_since: &mut DateTime<Utc>,
+) -> usize {
+    // Only fills since the last one received (the first call goes back to the start of the day
+    // or of the target period)
+    let mut new_fills = 0;
+    match exchange.fills(None, Some(*fills_since), None).await {
+        Ok(mut fills) => {
+            // chronological order for buy/sell pairing
+            fills.sort_by_key(|fill| fill.created_at);
+            for fill in fills {
+                let created_at = DateTime::from_timestamp_millis(fill.created_at as i64);
+                if let Some(created_at) = created_at {
+                    *fills_since = (*fills_since).max(created_at);
                 }
-            }
-            // Bid 3
-            if let Some(bid3_price_decimal) = Decimal::from_f64(bid3.price) {
-                if price_to_adjust - bid3_price_decimal < max_diff_decimal {
-                    glob_size += bid3.size;
+                if !fill.market.contains("-PERP") && !seen_fills.contains_key(&fill.id) {
+                    seen_fills.insert(fill.id.clone(), created_at.unwrap_or(*fills_since));
+                    logger.debug(format!("New fill {fill:?}"));
+                    journal.record_fill(&fill);
+                    accounting.add_fill(&fill);
+                    event_sender.send(BackendEvent::Fill(fill));
+                    new_fills += 1;
                 }
             }
-            logger.debug(format!("Global used size: {:?}", glob_size));
+            // older fills are not queried again
+            seen_fills.retain(|_, created_at| *created_at >= *fills_since);
+        }
+        Err(err) => {
+            logger.error(format!("Failed to fetch fills: {}", err));
+        }
+    }
+    new_fills
+}
 
-            if let Some(glob_size_decimal) = Decimal::from_f64(glob_size) {
-                if let Some(decimal_multiplier) = Decimal::from_i32(SIZE_MULTIPLIER_BIDDING_MARGIN)
-                {
-                    if glob_size_decimal < order.size * decimal_multiplier {
-                        // cancel order.
-                        let result = client_private.cancel_order(order.id.clone()).await;
-                        logger.info(format!("Cancelling order result {result:?}"));
-                    }
-                }
+// Target or budget hit: cancel the Option bids so that no new fill happens
+async fn stop_quoting(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    orders: &OrderUpdates,
+    stop_reason: StopReason,
+) {
+    let reason = match stop_reason {
+        StopReason::VolumeReached => {
+            logger.info("Volume target reached, cancelling Option orders.");
+            ReasonCode::TargetReached
+        }
+        StopReason::BudgetExhausted => {
+            logger.warn("Spending budget exhausted, cancelling Option orders.");
+            ReasonCode::BudgetExhausted
+        }
+    };
+    // orders left open are cancelled again at the next loop
+    if let Err(err) = cancel_option_orders(logger, journal, exchange, orders, reason).await {
+        logger.error(err);
+    }
+}
+
+// Cancel every order of the Option markets of the given orders. Err lists the markets whose
+// cancel failed.
+async fn cancel_option_orders(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    orders: &OrderUpdates,
+    reason: ReasonCode,
+) -> Result<(), String> {
+    let mut markets: Vec<String> = orders
+        .results
+        .iter()
+        .filter(|order| !order.market.contains("-PERP"))
+        .map(|order| order.market.clone())
+        .collect();
+    markets.sort();
+    markets.dedup();
+    let mut errors = Vec::new();
+    for market in markets {
+        let result = exchange.cancel_all_orders_for_market(market.clone()).await;
+        logger.info(format!("Cancelling order result {result:?}"));
+        journal.record(
+            &JournalEntry::new(journal.clock(), JournalAction::CancelAll, reason, market.clone()).with_result(&result),
+        );
+        if let Err(err) = result {
+            errors.push(format!("{}: {}", market, err));
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Failed to cancel the orders of {}", errors.join("; ")))
+    }
+}
+
+// Cancel the Option orders managed by the bot (shutdown, operator command)
+pub async fn cancel_bot_orders(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    reason: ReasonCode,
+) -> Result<(), String> {
+    let orders = exchange
+        .open_orders()
+        .await
+        .map_err(|err| format!("Failed to fetch orders: {}", err))?;
+    logger.info(format!("Cancelling bot orders, nbr of open orders: {}", orders.results.len()));
+    cancel_option_orders(logger, journal, exchange, &orders, reason).await
+}
+
+// Open positions of the Option markets
+pub fn open_option_positions(positions: Positions) -> Vec<Position> {
+    positions
+        .results
+        .into_iter()
+        .filter(|position| {
+            position.status == PositionStatus::OPEN && !position.market.contains("-PERP")
+        })
+        .collect()
+}
+
+// Cancel the remaining orders of the position market then sell the position at market
+pub async fn liquidate_position(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    position: &Position,
+    reason: ReasonCode,
+) -> Result<OrderUpdate, paradex::error::Error> {
+    // cancel remaining order in this market
+    let result = exchange
+        .cancel_all_orders_for_market(position.market.clone())
+        .await;
+    logger.info(format!("Cancelling order result {result:?}"));
+    journal.record(
+        &JournalEntry::new(journal.clock(), JournalAction::CancelAll, reason, position.market.clone())
+            .with_result(&result),
+    );
+    // Sell a long position, buy back a short one (size signed by the exchange)
+    let side = match position.side {
+        PositionSide::LONG => Side::SELL,
+        PositionSide::SHORT => Side::BUY,
+    };
+    let size = Decimal::from_f64(position.size.abs())
+        .filter(|size| !size.is_zero())
+        .ok_or_else(|| {
+            paradex::error::Error::TypeConversionError(format!(
+                "Invalid size {} of the {} position",
+                position.size, position.market
+            ))
+        })?;
+    let order_request = OrderRequest {
+        instruction: OrderInstruction::GTC,
+        market: position.market.clone(),
+        price: None,
+        side,
+        size,
+        order_type: OrderType::MARKET,
+        client_id: Some("order_sent_using_rust_api".into()),
+        flags: vec![],
+        recv_window: None,
+        stp: None,
+        trigger_price: None,
+    };
+    logger.info(format!("Sending order {order_request:?}"));
+    let entry = JournalEntry {
+        side: Some(order_request.side),
+        size: Some(order_request.size),
+        ..JournalEntry::new(journal.clock(), JournalAction::Liquidation, reason, order_request.market.clone())
+    };
+    let result = exchange.create_order(order_request).await;
+    let entry = entry.with_result(&result);
+    journal.record(&JournalEntry {
+        order_id: result.as_ref().ok().map(|order| order.id.clone()),
+        ..entry
+    });
+    result
+}
+
+// Liquidate every Option position, returns the number of positions sold
+pub async fn flatten_option_positions(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    reason: ReasonCode,
+) -> Result<usize, String> {
+    let positions = exchange
+        .positions()
+        .await
+        .map_err(|err| format!("Failed to fetch positions: {}", err))?;
+    let mut errors = Vec::new();
+    let mut sold = 0;
+    for position in open_option_positions(positions) {
+        match liquidate_position(logger, journal, exchange, &position, reason).await {
+            Ok(result) => {
+                logger.info(format!("Sell order result {result:?}"));
+                sold += 1;
             }
+            Err(err) => errors.push(format!("{}: {}", position.market, err)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(sold)
+    } else {
+        Err(format!("Failed to sell positions: {}", errors.join(", ")))
+    }
+}
+
+// Client for REST api, private with a L2 key (keystore or env variable)
+pub async fn connect_client(url: URL, l2_key: Option<String>) -> Result<Client, String> {
+    Client::new(url, l2_key)
+        .await
+        .map_err(|err| format!("Cannot create Paradex client for {}: {}", url.rest(), err))
+}
+
+// Journal of order actions and fills (disabled in config or if it cannot be opened), timed by the clock.
+// The fills journaled since `fills_since` are not journaled again.
+pub fn open_journal(logger: &Logger, config: &JournalConfig, clock: &Clock, fills_since: DateTime<Utc>) -> Journal {
+    if !config.enabled {
+        return Journal::disabled().with_clock(clock.clone());
+    }
+    match Journal::open(&config.path, fills_since) {
+        Ok(journal) => {
+            logger.info(format!("Trade journal: {}", journal.path().display()));
+            journal.with_clock(clock.clone())
+        }
+        Err(err) => {
+            logger.error(format!("Cannot open trade journal {}: {}", config.path.display(), err));
+            Journal::disabled().with_clock(clock.clone())
         }
     }
 }
 
 async fn process_option_open_orders(
     logger: &Logger,
-    client_private: &mut Client,
-    manager: &mut WebsocketManager,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
     orders: OrderUpdates,
+    rules: &QuoteRules,
 ) {
     for order in orders.results {
         if !order.market.contains("-PERP") {
-            let state = OrderBookState::new();
-
-            if let Err(e) = run_orderbook_subscription(logger, manager, order.market.clone(), &state).await
-            {
-                logger.debug(format!("Subscription failed for market {}: {}", order.market, e));
-                continue; // go for next order
-            }
-
-            // Process data retrieved with callback
+            let book = match exchange.book_top(order.market.clone()).await {
+                Ok(book) => book,
+                Err(e) => {
+                    logger.debug(format!("Subscription failed for market {}: {}", order.market, e));
+                    continue; // go for next order
+                }
+            };
 
             // 1) Are we first bid with good margin?
-            let step_size = Decimal::from_f64(STEP_SIZE).unwrap_or_default();
-            let new_price = determine_new_bid_price(logger, &order, &state, step_size).await;
+            let new_price = determine_new_bid_price(logger, &order, &book, &rules.params);
 
             // 2) Modify order if necessary
             if new_price != order.price {
                 adjust_order(
                     logger,
-                    client_private,
+                    journal,
+                    exchange,
                     order.id.clone(),
                     order.market.clone(),
                     order.size,
@@ -305,64 +612,185 @@ async fn process_option_open_orders(
             }
 
             // 3) Is there sufficient size below our bid? Cancel order if that's not the case
-            check_liquidity_and_cancel_if_low(logger, client_private, order, &state, new_price).await;
+            let cancelled = check_liquidity_and_cancel_if_low(
+                logger,
+                journal,
+                exchange,
+                &order,
+                &book,
+                new_price,
+                &rules.params,
+            )
+            .await;
+
+            // 4) Are fees + spread loss acceptable? Cancel order if that's not the case
+            if !cancelled && let Some(max_cost_per_1k) = rules.max_cost_per_1k {
+                let estimate =
+                    strategy::estimate_bid_exit(order.price, order.size, &book, new_price, &rules.fee_model);
+                check_exit_cost_and_cancel_if_high(
+                    logger,
+                    journal,
+                    exchange,
+                    &order,
+                    estimate,
+                    max_cost_per_1k,
+                )
+                .await;
+            }
+        }
+    }
+}
+
+// Run one worker per account until they all stop. Err lists the accounts stopped by an error.
+pub async fn run_accounts(
+    log_sender: Sender<LogMessage>,
+    event_sender: Sender<BackendEvent>,
+    log_level: SharedLogLevel,
+    config: BotConfig,
+    url: URL,
+    keys: AccountKeys,
+    shutdown: CancellationToken,
+) -> Result<(), String> {
+    let logger = Logger::with_shared_level(log_sender, log_level);
+    let accounts = config.accounts();
+    let multi_account = !config.accounts.is_empty();
+    let mut workers = tokio::task::JoinSet::new();
+    for account in accounts {
+        let worker_logger = if multi_account {
+            logger.for_account(&account.name)
+        } else {
+            logger.clone()
+        };
+        let worker_events = AccountEventSender::new(account.name.clone(), event_sender.clone());
+        let worker_config = config.clone();
+        let worker_shutdown = shutdown.clone();
+        let l2_key = keys.get(&account);
+        workers.spawn(async move {
+            let name = account.name.clone();
+            let result = run_backend_logic(
+                worker_logger,
+                worker_events,
+                worker_config,
+                url,
+                account,
+                l2_key,
+                worker_shutdown,
+            )
+            .await;
+            (name, result)
+        });
+    }
+
+    let mut errors = Vec::new();
+    while let Some(result) = workers.join_next().await {
+        match result {
+            Ok((_, Ok(()))) => {}
+            Ok((name, Err(err))) => {
+                logger.error(format!("Account {} stopped: {}", name, err));
+                errors.push(format!("{}: {}", name, err));
+            }
+            Err(err) => {
+                logger.error(format!("Account worker crashed: {}", err));
+                errors.push(format!("worker crashed: {}", err));
+            }
         }
     }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
 }
 
-pub async fn run_backend_logic(log_sender: Sender<LogMessage>) {
-    // Log
-    let logger = Logger::with_level(log_sender, LogLevel::Info);
-    
-    // Public manager for WS
-    let mut manager = WebsocketManager::new(URL::Production, None).await;
-    // Private client for REST api
-    let url = URL::Production;
-    // Read key from env variable PARADEX_L2_KEY
-    let l2_private_key_hex_str = std::env::var("PARADEX_L2_KEY").ok();
-    let mut client_private = Client::new(url, l2_private_key_hex_str).await.unwrap();
-
-    loop {
+pub async fn run_backend_logic(
+    logger: Logger,
+    event_sender: AccountEventSender,
+    config: BotConfig,
+    url: URL,
+    account: Account,
+    l2_key: Option<String>,
+    shutdown: CancellationToken,
+) -> Result<(), String> {
+    let l2_key = l2_key.ok_or_else(|| format!("No L2 key for account {}", account.name))?;
+
+    logger.info(format!("Paradex environment: {}", config.exchange.describe()));
+    // Private client for REST api, public manager for WS, counted in the metrics
+    let exchange = exchange::connect(&logger, url, Some(l2_key), Clock::System).await?;
+    let mut exchange = metrics::MeteredExchange::new(exchange, &account.name);
+    run_trading_loop(
+        &logger,
+        &event_sender,
+        &config,
+        &account,
+        &mut exchange,
+        &Clock::System,
+        &shutdown,
+    )
+    .await
+}
+
+// Bot loop of one account on the given exchange until there is no order left or the shutdown,
+// followed by the shutdown sequence. The clock gives the refresh period and the day / target periods.
+pub async fn run_trading_loop(
+    logger: &Logger,
+    event_sender: &AccountEventSender,
+    config: &BotConfig,
+    account: &Account,
+    exchange: &mut impl Exchange,
+    clock: &Clock,
+    shutdown: &CancellationToken,
+) -> Result<(), String> {
+    // Fills queried from the start of the day or of the target period
+    let start_of_day = targets::local_midnight(clock.local_now().date_naive(), clock.now());
+    let mut fills_since = if account.target.is_set() {
+        start_of_day.min(account.target.period.start(clock.local_now()))
+    } else {
+        start_of_day
+    };
+    // Journal of order actions and fills
+    let journal = open_journal(logger, &account.journal, clock, fills_since);
+    // Fills already sent to front-end, by creation time (the older ones are dropped)
+    let mut seen_fills: HashMap<String, DateTime<Utc>> = HashMap::new();
+    // Volume and cost of the fills, for the target / budget
+    let mut accounting = Accounting::new(clock.now());
+    let mut fee_model = FeeModel::from_config(&account.fees);
+
+    let labels = [("account", account.name.as_str())];
+    while !shutdown.is_cancelled() {
+        let loop_start = Instant::now();
         // Any Option open positions? Cancel order of same marke + sell market
-        let positions = client_private.positions().await;
+        // No position seen open (the sells are checked at the next loop, they may fail or fill partially)
+        let mut flat = false;
+        let positions = exchange.positions().await;
         match positions {
             Ok(positions) => {
-                let open_option_positions: Vec<Position> = positions
-                    .results
-                    .clone() // clone and consume values
-                    .into_iter()
-                    .filter(|position| {
-                        position.status == PositionStatus::OPEN
-                            && !position.market.contains("-PERP")
-                    })
-                    .collect();
+                let open_option_positions = open_option_positions(positions);
+                flat = open_option_positions.is_empty();
                 logger.info(format!(
                     "Nbr of Option open positions: {}",
                     open_option_positions.len()));
-                if open_option_positions.len() >= 1 {
-                    for position in open_option_positions {
-                        // cancel remaining order in this market
-                        let result = client_private
-                            .cancel_all_orders_for_market(position.market.clone())
-                            .await;
-                        logger.info(format!("Cancelling order result {result:?}"));
-                        // Sell position
-                        let order_request = OrderRequest {
-                            instruction: OrderInstruction::GTC,
-                            market: position.market.clone(),
-                            price: None,
-                            side: Side::SELL,
-                            size: Decimal::from_f64(position.size).unwrap(),
-                            order_type: OrderType::MARKET,
-                            client_id: Some("order_sent_using_rust_api".into()),
-                            flags: vec![],
-                            recv_window: None,
-                            stp: None,
-                            trigger_price: None,
-                        };
-                        logger.info(format!("Sending order {order_request:?}"));
-                        let result = client_private.create_order(order_request).await.unwrap();
-                        logger.info(format!("Sell order result {result:?}"));
+                event_sender.send(BackendEvent::Positions(open_option_positions.clone()));
+                for position in open_option_positions {
+                    let result = liquidate_position(
+                        logger,
+                        &journal,
+                        exchange,
+                        &position,
+                        ReasonCode::PositionOpen,
+                    )
+                    .await;
+                    // a failed sell is retried at the next loop, the position being still open
+                    match result {
+                        Ok(result) => {
+                            logger.info(format!("Sell order result {result:?}"));
+                            event_sender.send(BackendEvent::Liquidation {
+                                market: result.market,
+                                size: result.size,
+                            });
+                        }
+                        Err(err) => {
+                            logger.error(format!("Failed to sell position {}: {}", position.market, err));
+                        }
                     }
                 }
             }
@@ -371,17 +799,67 @@ pub async fn run_backend_logic(log_sender: Sender<LogMessage>) {
             }
         }
 
+        // New fills for the dashboard
+        let new_fills = publish_new_fills(
+            logger,
+            &journal,
+            exchange,
+            event_sender,
+            &mut seen_fills,
+            &mut accounting,
+            &mut fills_since,
+        )
+        .await;
+        metrics::FILLS.add(&labels, new_fills as f64);
+        let totals = accounting.total();
+        metrics::VOLUME.set(&labels, totals.volume);
+        metrics::COST.set(&labels, totals.net_cost());
+
+        // Fees of maker bid + taker liquidation
+        if account.fees.source == FeeSource::Fills {
+            fee_model.calibrate(&totals);
+        }
+        event_sender.send(BackendEvent::FeeModel(fee_model));
+
+        // Volume target / spending budget of the period
+        let stop_reason = if account.target.is_set() {
+            let progress = TargetProgress::compute(&account.target, &accounting, clock.local_now());
+            logger.debug(format!("Target progress {progress:?}"));
+            let stop_reason = progress.stop_reason();
+            event_sender.send(BackendEvent::TargetProgress(progress));
+            stop_reason
+        } else {
+            None
+        };
+
         // For each open orders:
-        // - go to first bid + STEP_SIZE margin if possible (depends of first ask)
+        // - go to first bid + step_size margin if possible (depends of first ask)
         // - check if the below orders have enough size to absorb massive instant sell
-        let orders = client_private.open_orders().await;
+        let orders = exchange.open_orders().await;
         match orders {
             Ok(orders) => {
                 logger.info(format!("Nbr of open orders: {:?}", orders.results.len()));
-                if orders.results.len() >= 1 {
-                    process_option_open_orders(&logger, &mut client_private, &mut manager, orders).await;
-                } else {
-                    manager.stop().await.unwrap();
+                event_sender.send(BackendEvent::OpenOrders(orders.results.clone()));
+                if let Some(stop_reason) = stop_reason
+                    && !orders.results.is_empty()
+                {
+                    stop_quoting(logger, &journal, exchange, &orders, stop_reason)
+                        .await;
+                } else if !orders.results.is_empty() {
+                    let rules = QuoteRules {
+                        params: config.strategy,
+                        fee_model,
+                        max_cost_per_1k: account.fees.max_cost_per_1k,
+                    };
+                    process_option_open_orders(
+                        logger,
+                        &journal,
+                        exchange,
+                        orders,
+                        &rules,
+                    )
+                    .await;
+                } else if flat {
                     break;
                 }
             }
@@ -389,7 +867,22 @@ pub async fn run_backend_logic(log_sender: Sender<LogMessage>) {
                 logger.error(format!("Failed to fetch orders: {}", err));
             }
         }
+        metrics::LOOPS.inc(&labels);
+        metrics::LOOP_DURATION.set(&labels, loop_start.elapsed().as_secs_f64());
 
-        tokio::time::sleep(Duration::from_secs(REFRESH_TIME_SEC)).await;
+        tokio::select! {
+            _ = shutdown.cancelled() => {}
+            _ = clock.sleep(Duration::from_secs(REFRESH_TIME_SEC)) => {}
+        }
     }
+
+    shutdown::close_backend(
+        logger,
+        &journal,
+        exchange,
+        &config.shutdown,
+        clock,
+        shutdown.is_cancelled(),
+    )
+    .await
 }
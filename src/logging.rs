@@ -0,0 +1,185 @@
+use chrono::NaiveDate;
+use crossbeam::channel::{Sender, TrySendError};
+use std::fs::{File, OpenOptions};
+use std::io::{LineWriter, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use crate::config::LoggingConfig;
+use crate::{LogLevel, LogMessage, SharedLogLevel};
+
+const LOG_FILE_PREFIX: &str = "bot_paradex_";
+const LOG_FILE_EXTENSION: &str = ".log";
+
+// Daily log file shared by every logger of the process
+static LOG_FILE: OnceLock<Mutex<RotatingLogFile>> = OnceLock::new();
+// Logs not sent to a full front-end channel, still written to the daily file
+static DROPPED_LOGS: AtomicU64 = AtomicU64::new(0);
+
+// One file per local day: <directory>/bot_paradex_YYYY-MM-DD.log
+struct RotatingLogFile {
+    directory: PathBuf,
+    retention_days: u32,
+    day: Option<NaiveDate>,
+    writer: Option<LineWriter<File>>,
+}
+
+impl RotatingLogFile {
+    fn write(&mut self, log: &LogMessage) {
+        let today = log.day;
+        if self.day != Some(today) {
+            self.day = Some(today);
+            self.writer = self.open(today);
+            self.purge(today);
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writeln!(writer, "{} [{}] {}", log.timestamp, log.level, log.message);
+        }
+    }
+
+    fn open(&self, day: NaiveDate) -> Option<LineWriter<File>> {
+        if let Err(err) = std::fs::create_dir_all(&self.directory) {
+            eprintln!("Cannot create log directory {}: {}", self.directory.display(), err);
+            return None;
+        }
+        let path = self.directory.join(format!(
+            "{}{}{}",
+            LOG_FILE_PREFIX,
+            day.format("%Y-%m-%d"),
+            LOG_FILE_EXTENSION
+        ));
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => Some(LineWriter::new(file)),
+            Err(err) => {
+                eprintln!("Cannot open log file {}: {}", path.display(), err);
+                None
+            }
+        }
+    }
+
+    // Delete daily files older than the retention
+    fn purge(&self, today: NaiveDate) {
+        let Ok(entries) = std::fs::read_dir(&self.directory) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name();
+            let Some(day) = name
+                .to_str()
+                .and_then(|name| name.strip_prefix(LOG_FILE_PREFIX))
+                .and_then(|name| name.strip_suffix(LOG_FILE_EXTENSION))
+                .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+            else {
+                continue;
+            };
+            if (today - day).num_days() >= self.retention_days as i64 {
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(writer) = self.writer.as_mut() {
+            let _ = writer.flush();
+        }
+    }
+}
+
+// Append to the daily log file (no-op if file logging is not initialized)
+pub(crate) fn write_to_file(log: &LogMessage) {
+    if let Some(file) = LOG_FILE.get()
+        && let Ok(mut file) = file.lock()
+    {
+        file.write(log);
+    }
+}
+
+// Records of the `log` facade (our modules and dependencies) sent to the front-end and the file
+struct LogBridge {
+    sender: Sender<LogMessage>,
+    min_level: SharedLogLevel,
+}
+
+fn to_log_level(level: log::Level) -> Option<LogLevel> {
+    match level {
+        log::Level::Error => Some(LogLevel::Error),
+        log::Level::Warn => Some(LogLevel::Warn),
+        log::Level::Info => Some(LogLevel::Info),
+        log::Level::Debug => Some(LogLevel::Debug),
+        // too verbose (raw requests of paradex crate...)
+        log::Level::Trace => None,
+    }
+}
+
+impl log::Log for LogBridge {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        let Some(level) = to_log_level(metadata.level()) else {
+            return false;
+        };
+        // Other crates (GUI, network...) only for warnings and errors
+        let target = metadata.target();
+        if target.starts_with("backend") || target.starts_with("paradex") {
+            level >= self.min_level.get()
+        } else {
+            level >= LogLevel::Warn
+        }
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let Some(level) = to_log_level(record.level()) else {
+            return;
+        };
+        let log_msg = LogMessage::now(level, format!("{}: {}", record.target(), record.args()));
+        write_to_file(&log_msg);
+        send(&self.sender, log_msg);
+    }
+
+    fn flush(&self) {
+        flush();
+    }
+}
+
+// Send to the front-end without blocking: with a bounded channel full, the log is dropped and counted
+pub(crate) fn send(sender: &Sender<LogMessage>, log: LogMessage) {
+    if let Err(TrySendError::Full(_)) = sender.try_send(log) {
+        DROPPED_LOGS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// Logs dropped since the start of the process
+pub fn dropped_logs() -> u64 {
+    DROPPED_LOGS.load(Ordering::Relaxed)
+}
+
+// Flush the daily log file (shutdown)
+pub fn flush() {
+    if let Some(file) = LOG_FILE.get()
+        && let Ok(mut file) = file.lock()
+    {
+        file.flush();
+    }
+}
+
+// Install the `log` facade bridge and the daily log file, once per process
+pub fn init_logging(
+    sender: Sender<LogMessage>,
+    min_level: SharedLogLevel,
+    config: &LoggingConfig,
+) -> Result<(), String> {
+    if config.file_enabled {
+        let _ = LOG_FILE.set(Mutex::new(RotatingLogFile {
+            directory: config.directory.clone(),
+            retention_days: config.retention_days.max(1),
+            day: None,
+            writer: None,
+        }));
+    }
+    log::set_boxed_logger(Box::new(LogBridge { sender, min_level }))
+        .map_err(|err| format!("Cannot install logger: {}", err))?;
+    log::set_max_level(log::LevelFilter::Debug);
+    Ok(())
+}
@@ -0,0 +1,448 @@
+use chrono::{DateTime, Utc};
+use paradex::error::Error;
+use paradex::structs::{
+    CancelByMarketResponse, Fill, ModifyOrderRequest, OrderRequest, OrderType, OrderUpdate, OrderUpdates,
+    Positions,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::Logger;
+use crate::exchange::{Exchange, ExchangeResult};
+use crate::strategy::BookTop;
+
+// Counters and gauges of the bot workers, served in the Prometheus text format on
+// http://<address>/metrics by the daemon and `cli run`
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsConfig {
+    pub enabled: bool,
+    // Local address of the HTTP endpoint
+    pub address: String,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            address: "127.0.0.1:9898".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        }
+    }
+}
+
+pub struct Metric {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: MetricKind,
+}
+
+pub const ORDERS_MODIFIED: Metric = Metric {
+    name: "bot_paradex_orders_modified_total",
+    help: "Orders modified (repriced)",
+    kind: MetricKind::Counter,
+};
+pub const ORDERS_CANCELLED: Metric = Metric {
+    name: "bot_paradex_orders_cancelled_total",
+    help: "Cancel requests accepted, of one order or of every order of a market",
+    kind: MetricKind::Counter,
+};
+pub const FILLS: Metric = Metric {
+    name: "bot_paradex_fills_total",
+    help: "Option fills received",
+    kind: MetricKind::Counter,
+};
+pub const LIQUIDATIONS: Metric = Metric {
+    name: "bot_paradex_liquidations_total",
+    help: "Market orders closing open positions accepted",
+    kind: MetricKind::Counter,
+};
+pub const LOOPS: Metric = Metric {
+    name: "bot_paradex_loops_total",
+    help: "Passes of the bot loop",
+    kind: MetricKind::Counter,
+};
+pub const LOOP_DURATION: Metric = Metric {
+    name: "bot_paradex_loop_duration_seconds",
+    help: "Duration of the last pass of the bot loop, without the refresh wait",
+    kind: MetricKind::Gauge,
+};
+pub const WEBSOCKET_RECONNECTS: Metric = Metric {
+    name: "bot_paradex_websocket_reconnects_total",
+    help: "Websocket connections opened again after a failure",
+    kind: MetricKind::Counter,
+};
+pub const EXCHANGE_ERRORS: Metric = Metric {
+    name: "bot_paradex_exchange_errors_total",
+    help: "Failed exchange requests by request and kind of error",
+    kind: MetricKind::Counter,
+};
+pub const VOLUME: Metric = Metric {
+    name: "bot_paradex_volume_usd",
+    help: "Volume of the round trips since the start of the run",
+    kind: MetricKind::Gauge,
+};
+pub const COST: Metric = Metric {
+    name: "bot_paradex_cost_usd",
+    help: "Net cost (fees + spread loss) of the round trips since the start of the run",
+    kind: MetricKind::Gauge,
+};
+
+// Every metric, shown from the start of the process
+const METRICS: [&Metric; 10] = [
+    &ORDERS_MODIFIED,
+    &ORDERS_CANCELLED,
+    &FILLS,
+    &LIQUIDATIONS,
+    &LOOPS,
+    &LOOP_DURATION,
+    &WEBSOCKET_RECONNECTS,
+    &EXCHANGE_ERRORS,
+    &VOLUME,
+    &COST,
+];
+
+type Labels = Vec<(String, String)>;
+
+struct Family {
+    help: &'static str,
+    kind: MetricKind,
+    series: BTreeMap<Labels, f64>,
+}
+
+// Values of every worker of the process, by metric name
+static REGISTRY: Mutex<BTreeMap<&'static str, Family>> = Mutex::new(BTreeMap::new());
+
+impl Metric {
+    pub fn inc(&self, labels: &[(&str, &str)]) {
+        self.add(labels, 1.0);
+    }
+
+    pub fn add(&self, labels: &[(&str, &str)], value: f64) {
+        self.update(labels, |current| *current += value);
+    }
+
+    pub fn set(&self, labels: &[(&str, &str)], value: f64) {
+        self.update(labels, |current| *current = value);
+    }
+
+    // 0 until updated
+    pub fn value(&self, labels: &[(&str, &str)]) -> f64 {
+        let registry = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        registry
+            .get(self.name)
+            .and_then(|family| family.series.get(&to_labels(labels)))
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    fn update(&self, labels: &[(&str, &str)], update: impl FnOnce(&mut f64)) {
+        let mut registry = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        update(self.family(&mut registry).series.entry(to_labels(labels)).or_insert(0.0));
+    }
+
+    fn family<'a>(&self, registry: &'a mut BTreeMap<&'static str, Family>) -> &'a mut Family {
+        registry.entry(self.name).or_insert_with(|| Family {
+            help: self.help,
+            kind: self.kind,
+            series: BTreeMap::new(),
+        })
+    }
+}
+
+// HELP and TYPE of every metric, before any value
+pub fn register_all() {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    for metric in METRICS {
+        metric.family(&mut registry);
+    }
+}
+
+// Counters of an account at 0 until they fire, the series of the errors appear with their first error
+pub fn register_account(account: &str) {
+    register_all();
+    let labels = [("account", account)];
+    for metric in [&ORDERS_MODIFIED, &FILLS, &LIQUIDATIONS, &LOOPS, &WEBSOCKET_RECONNECTS] {
+        metric.add(&labels, 0.0);
+    }
+    for scope in ["order", "market"] {
+        ORDERS_CANCELLED.add(&[("account", account), ("scope", scope)], 0.0);
+    }
+}
+
+fn to_labels(labels: &[(&str, &str)]) -> Labels {
+    labels
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+// Prometheus text exposition format
+pub fn render() -> String {
+    let registry = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut text = String::new();
+    for (name, family) in registry.iter() {
+        text.push_str(&format!("# HELP {} {}\n", name, family.help));
+        text.push_str(&format!("# TYPE {} {}\n", name, family.kind.as_str()));
+        for (labels, value) in &family.series {
+            text.push_str(name);
+            if !labels.is_empty() {
+                let labels: Vec<String> = labels
+                    .iter()
+                    .map(|(label, value)| format!("{}=\"{}\"", label, escape(value)))
+                    .collect();
+                text.push_str(&format!("{{{}}}", labels.join(",")));
+            }
+            text.push_str(&format!(" {}\n", format_value(*value)));
+        }
+    }
+    text
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+// Kind of a failed request, few values to keep the series count low
+pub fn error_kind(error: &Error) -> &'static str {
+    match error {
+        Error::HTTPError { status_code } | Error::ParadexError { status_code, .. } => {
+            if status_code.as_u16() == 429 {
+                "rate_limited"
+            } else if status_code.is_server_error() {
+                "server"
+            } else {
+                "rejected"
+            }
+        }
+        Error::RestError(_) | Error::RestEmptyResponse | Error::WebSocketSend(_) => "network",
+        Error::JsonParseError(_) | Error::DeserializationError(_) | Error::TypeConversionError(_) => "parse",
+        Error::StarknetError(_) | Error::TimeError(_) | Error::MissingPrivateKey => "other",
+    }
+}
+
+// Answer GET /metrics until the end of the process
+pub fn serve(logger: &Logger, config: &MetricsConfig) -> Result<(), String> {
+    let address = parse_address(&config.address)?;
+    let listener =
+        TcpListener::bind(address).map_err(|err| format!("Cannot serve the metrics on {}: {}", address, err))?;
+    register_all();
+    logger.info(format!("Metrics on http://{}/metrics", address));
+    let logger = logger.clone();
+    std::thread::Builder::new()
+        .name("metrics".to_string())
+        .spawn(move || {
+            for stream in listener.incoming().flatten() {
+                if let Err(err) = answer(stream) {
+                    logger.debug(format!("Metrics request failed: {}", err));
+                }
+            }
+        })
+        .map_err(|err| format!("Cannot start the metrics server: {}", err))?;
+    Ok(())
+}
+
+// One request of a connection: the metrics on GET /metrics, 404 on other paths, 405 on other methods
+pub fn answer(mut stream: TcpStream) -> std::io::Result<()> {
+    // a client sending nothing must not block the next ones
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // headers ignored
+    let mut header = String::new();
+    loop {
+        header.clear();
+        if reader.read_line(&mut header)? == 0 || header.trim_end().is_empty() {
+            break;
+        }
+    }
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts.next().unwrap_or_default().split('?').next().unwrap_or_default();
+    let (status, body) = match (method, path) {
+        ("GET", "/metrics") => ("200 OK", render()),
+        ("GET", _) => ("404 Not Found", "Not found, see /metrics\n".to_string()),
+        _ => ("405 Method Not Allowed", "Only GET is supported\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// Loopback ip:port only: the endpoint has no authentication
+pub fn parse_address(address: &str) -> Result<SocketAddr, String> {
+    let address: SocketAddr = address
+        .parse()
+        .map_err(|_| format!("metrics.address {} is not an ip:port address", address))?;
+    if !address.ip().is_loopback() {
+        return Err(format!(
+            "metrics.address {} is not a loopback address, the endpoint has no authentication",
+            address
+        ));
+    }
+    Ok(address)
+}
+
+// Exchange counting the order actions and the failed requests of an account
+pub struct MeteredExchange<E> {
+    inner: E,
+    account: String,
+    // Reconnections of the inner exchange already counted
+    reconnects: u64,
+}
+
+impl<E: Exchange> MeteredExchange<E> {
+    pub fn new(inner: E, account: impl Into<String>) -> Self {
+        let account = account.into();
+        register_account(&account);
+        let reconnects = inner.websocket_reconnects();
+        Self {
+            inner,
+            account,
+            reconnects,
+        }
+    }
+}
+
+// Count the error of a request of the account, then the success with `on_success`
+async fn metered<T>(
+    account: String,
+    request: &'static str,
+    future: impl Future<Output = ExchangeResult<T>> + Send,
+    on_success: impl FnOnce(&str) + Send,
+) -> ExchangeResult<T> {
+    let result = future.await;
+    match &result {
+        Ok(_) => on_success(&account),
+        Err(err) => EXCHANGE_ERRORS.inc(&[
+            ("account", &account),
+            ("request", request),
+            ("kind", error_kind(err)),
+        ]),
+    }
+    result
+}
+
+impl<E: Exchange> Exchange for MeteredExchange<E> {
+    fn positions(&self) -> impl Future<Output = ExchangeResult<Positions>> + Send {
+        metered(self.account.clone(), "positions", self.inner.positions(), |_| {})
+    }
+
+    fn open_orders(&self) -> impl Future<Output = ExchangeResult<OrderUpdates>> + Send {
+        metered(self.account.clone(), "open_orders", self.inner.open_orders(), |_| {})
+    }
+
+    fn fills(
+        &self,
+        market: Option<String>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> impl Future<Output = ExchangeResult<Vec<Fill>>> + Send {
+        metered(self.account.clone(), "fills", self.inner.fills(market, start, end), |_| {})
+    }
+
+    fn create_order(&self, request: OrderRequest) -> impl Future<Output = ExchangeResult<OrderUpdate>> + Send {
+        // the bot only sends market orders to close the positions
+        let liquidation = request.order_type == OrderType::MARKET;
+        metered(
+            self.account.clone(),
+            "create_order",
+            self.inner.create_order(request),
+            move |account| {
+                if liquidation {
+                    LIQUIDATIONS.inc(&[("account", account)]);
+                }
+            },
+        )
+    }
+
+    fn modify_order(
+        &self,
+        request: ModifyOrderRequest,
+    ) -> impl Future<Output = ExchangeResult<OrderUpdate>> + Send {
+        metered(
+            self.account.clone(),
+            "modify_order",
+            self.inner.modify_order(request),
+            |account| ORDERS_MODIFIED.inc(&[("account", account)]),
+        )
+    }
+
+    fn cancel_order(&self, order_id: String) -> impl Future<Output = ExchangeResult<()>> + Send {
+        metered(
+            self.account.clone(),
+            "cancel_order",
+            self.inner.cancel_order(order_id),
+            |account| ORDERS_CANCELLED.inc(&[("account", account), ("scope", "order")]),
+        )
+    }
+
+    fn cancel_all_orders_for_market(
+        &self,
+        market: String,
+    ) -> impl Future<Output = ExchangeResult<CancelByMarketResponse>> + Send {
+        metered(
+            self.account.clone(),
+            "cancel_all_orders_for_market",
+            self.inner.cancel_all_orders_for_market(market),
+            |account| ORDERS_CANCELLED.inc(&[("account", account), ("scope", "market")]),
+        )
+    }
+
+    async fn book_top(&mut self, market: String) -> Result<BookTop, String> {
+        let result = self.inner.book_top(market).await;
+        if result.is_err() {
+            EXCHANGE_ERRORS.inc(&[("account", &self.account), ("request", "book_top"), ("kind", "order_book")]);
+        }
+        let reconnects = self.inner.websocket_reconnects();
+        if reconnects > self.reconnects {
+            WEBSOCKET_RECONNECTS.add(&[("account", &self.account)], (reconnects - self.reconnects) as f64);
+            self.reconnects = reconnects;
+        }
+        result
+    }
+
+    fn stop(&mut self) -> impl Future<Output = Result<(), String>> + Send {
+        self.inner.stop()
+    }
+
+    fn websocket_reconnects(&self) -> u64 {
+        self.inner.websocket_reconnects()
+    }
+}
@@ -3,6 +3,8 @@ use paradex::structs::Level;
 use std::sync::{Arc, Mutex};
 use tokio::sync::Notify;
 
+use crate::strategy::BookTop;
+
 pub struct OrderBookState {
     // Bids
     pub first_bid: Arc<Mutex<Option<Level>>>,
@@ -25,6 +27,16 @@ impl OrderBookState {
         }
     }
 
+    // Copy of the levels for the strategy rules
+    pub fn top(&self) -> BookTop {
+        BookTop {
+            first_bid: self.first_bid.lock().unwrap().clone(),
+            second_bid: self.second_bid.lock().unwrap().clone(),
+            third_bid: self.third_bid.lock().unwrap().clone(),
+            first_ask: self.first_ask.lock().unwrap().clone(),
+        }
+    }
+
     // Utilities for cloning references for callback
     pub fn clone_for_callback(&self) -> OrderBookStateCallbackClones {
         OrderBookStateCallbackClones {
@@ -52,25 +64,20 @@ pub fn extract_data_from_snapshot(
 ) {
     debug!("Snaphot OrderBook received. Extracting data...");
 
-    let inserts = &ob_snapshot.inserts;
-
+    let top = BookTop::from_snapshot(ob_snapshot);
     // Bids
-    if inserts.len() >= 1 {
-        *clones.first_bid.lock().unwrap() = Some(inserts[0].clone());
+    if top.first_bid.is_some() {
+        *clones.first_bid.lock().unwrap() = top.first_bid;
     }
-    if inserts.len() >= 2 {
-        *clones.second_bid.lock().unwrap() = Some(inserts[1].clone());
+    if top.second_bid.is_some() {
+        *clones.second_bid.lock().unwrap() = top.second_bid;
     }
-    if inserts.len() >= 3 {
-        *clones.third_bid.lock().unwrap() = Some(inserts[2].clone());
+    if top.third_bid.is_some() {
+        *clones.third_bid.lock().unwrap() = top.third_bid;
     }
-
     // First ask
-    if let Some(first_ask_level) = inserts
-        .iter()
-        .find(|level| level.side == paradex::structs::Side::SELL)
-    {
-        *clones.first_ask.lock().unwrap() = Some(first_ask_level.clone());
+    if top.first_ask.is_some() {
+        *clones.first_ask.lock().unwrap() = top.first_ask;
     }
 
     // Notification to unsubscribe
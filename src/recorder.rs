@@ -0,0 +1,380 @@
+use chrono::{DateTime, Utc};
+use crossbeam::channel::{Receiver, RecvTimeoutError, unbounded};
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use paradex::structs::{BBO, OrderBook, Trade};
+use paradex::url::URL;
+use paradex::ws::{Channel, Message, WebsocketManager};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use crate::Logger;
+use crate::clock::Clock;
+
+// Market data recorder: order book snapshots / deltas, trades and BBO of selected markets written to
+// gzip compressed JSONL files (one record per line), rotated periodically. Each finished file is
+// appended to `index.jsonl` in the same directory with its time range and record count per market.
+
+const INDEX_FILE: &str = "index.jsonl";
+const FILE_EXTENSION: &str = "jsonl.gz";
+// Delay between two checks of the shutdown by the writer thread
+const WRITER_POLL_MS: u64 = 500;
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecorderConfig {
+    // Record alongside the bot (daemon, GUI), the CLI `record` command records in any case
+    pub enabled: bool,
+    pub directory: PathBuf,
+    pub markets: Vec<String>,
+    // Channels recorded
+    pub order_book: bool,
+    pub deltas: bool,
+    pub trades: bool,
+    pub bbo: bool,
+    // A new file is started every `rotate_minutes`
+    pub rotate_minutes: u32,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: PathBuf::from("recordings"),
+            markets: Vec::new(),
+            order_book: true,
+            deltas: false,
+            trades: true,
+            bbo: true,
+            rotate_minutes: 60,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "data", rename_all = "snake_case")]
+pub enum MarketData {
+    // Snapshot of the 15 first levels of each side
+    OrderBook(OrderBook),
+    OrderBookDeltas(OrderBook),
+    Trade(Trade),
+    Bbo(BBO),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Record {
+    pub received_at: DateTime<Utc>,
+    pub market: String,
+    #[serde(flatten)]
+    pub data: MarketData,
+}
+
+// Finished recording file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IndexEntry {
+    // Name in the recording directory
+    pub file: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub records: u64,
+    // Record count per market
+    pub markets: BTreeMap<String, u64>,
+}
+
+impl IndexEntry {
+    fn new(file: String, start: DateTime<Utc>) -> Self {
+        Self {
+            file,
+            start,
+            end: start,
+            records: 0,
+            markets: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, record: &Record) {
+        self.end = self.end.max(record.received_at);
+        self.records += 1;
+        *self.markets.entry(record.market.clone()).or_default() += 1;
+    }
+}
+
+struct RecordingFile {
+    encoder: GzEncoder<BufWriter<File>>,
+    entry: IndexEntry,
+}
+
+pub struct RecordingWriter {
+    directory: PathBuf,
+    rotation: chrono::Duration,
+    current: Option<RecordingFile>,
+}
+
+impl RecordingWriter {
+    // Files left unindexed by a crash are indexed with the records which can still be read
+    pub fn open(directory: &Path, rotate_minutes: u32) -> std::io::Result<RecordingWriter> {
+        std::fs::create_dir_all(directory)?;
+        recover_index(directory)?;
+        Ok(RecordingWriter {
+            directory: directory.to_path_buf(),
+            rotation: chrono::Duration::minutes(rotate_minutes.max(1) as i64),
+            current: None,
+        })
+    }
+
+    pub fn write(&mut self, record: &Record) -> std::io::Result<()> {
+        if let Some(file) = &self.current
+            && record.received_at >= file.entry.start + self.rotation
+        {
+            self.finish()?;
+        }
+        let file = match &mut self.current {
+            Some(file) => file,
+            None => self.current.insert(self.create(record.received_at)?),
+        };
+        serde_json::to_writer(&mut file.encoder, record)?;
+        file.encoder.write_all(b"\n")?;
+        file.entry.add(record);
+        Ok(())
+    }
+
+    // Close the current file and index it
+    pub fn finish(&mut self) -> std::io::Result<()> {
+        let Some(file) = self.current.take() else {
+            return Ok(());
+        };
+        file.encoder.finish()?.flush()?;
+        append_index(&self.directory, &file.entry)
+    }
+
+    fn create(&self, start: DateTime<Utc>) -> std::io::Result<RecordingFile> {
+        let mut name = format!("market_data_{}.{}", start.format("%Y%m%dT%H%M%S"), FILE_EXTENSION);
+        let mut counter = 1;
+        while self.directory.join(&name).exists() {
+            name = format!("market_data_{}_{}.{}", start.format("%Y%m%dT%H%M%S"), counter, FILE_EXTENSION);
+            counter += 1;
+        }
+        let file = OpenOptions::new().write(true).create_new(true).open(self.directory.join(&name))?;
+        Ok(RecordingFile {
+            encoder: GzEncoder::new(BufWriter::new(file), Compression::default()),
+            entry: IndexEntry::new(name, start),
+        })
+    }
+}
+
+impl Drop for RecordingWriter {
+    fn drop(&mut self) {
+        let _ = self.finish();
+    }
+}
+
+fn append_index(directory: &Path, entry: &IndexEntry) -> std::io::Result<()> {
+    let mut index = OpenOptions::new().create(true).append(true).open(directory.join(INDEX_FILE))?;
+    serde_json::to_writer(&mut index, entry)?;
+    index.write_all(b"\n")
+}
+
+fn recover_index(directory: &Path) -> std::io::Result<()> {
+    let indexed: HashSet<String> = read_index(directory)?.into_iter().map(|entry| entry.file).collect();
+    let mut unindexed: Vec<String> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .filter(|name| name.ends_with(FILE_EXTENSION) && !indexed.contains(name))
+        .collect();
+    unindexed.sort();
+    for name in unindexed {
+        let records = read_file(&directory.join(&name))?;
+        let Some(first) = records.first() else {
+            continue;
+        };
+        let mut entry = IndexEntry::new(name, first.received_at);
+        for record in &records {
+            entry.add(record);
+        }
+        append_index(directory, &entry)?;
+    }
+    Ok(())
+}
+
+// Index of a recording directory (empty when nothing was recorded)
+pub fn read_index(directory: &Path) -> std::io::Result<Vec<IndexEntry>> {
+    let file = match File::open(directory.join(INDEX_FILE)) {
+        Ok(file) => file,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(serde_json::from_str(&line)?);
+    }
+    Ok(entries)
+}
+
+// Records of a file, up to the first unreadable line of a file truncated by a crash
+pub fn read_file(path: &Path) -> std::io::Result<Vec<Record>> {
+    let reader = BufReader::new(GzDecoder::new(File::open(path)?));
+    let mut records = Vec::new();
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(_) => break,
+        }
+    }
+    Ok(records)
+}
+
+// Records of a market (every market with None) received in [from, to), in reception order
+pub fn read_records(
+    directory: &Path,
+    market: Option<&str>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> std::io::Result<Vec<Record>> {
+    let mut records = Vec::new();
+    for entry in read_index(directory)? {
+        if entry.end < from || entry.start >= to {
+            continue;
+        }
+        if let Some(market) = market
+            && !entry.markets.contains_key(market)
+        {
+            continue;
+        }
+        records.extend(read_file(&directory.join(&entry.file))?.into_iter().filter(|record| {
+            record.received_at >= from
+                && record.received_at < to
+                && market.is_none_or(|market| record.market == market)
+        }));
+    }
+    records.sort_by_key(|record| record.received_at);
+    Ok(records)
+}
+
+fn channels(config: &RecorderConfig, market_symbol: &str) -> Vec<Channel> {
+    let market_symbol = market_symbol.to_string();
+    let mut channels = Vec::new();
+    if config.order_book {
+        channels.push(Channel::OrderBook {
+            market_symbol: market_symbol.clone(),
+            channel_name: None,
+            refresh_rate: "100ms".into(),
+            price_tick: None,
+        });
+    }
+    if config.deltas {
+        channels.push(Channel::OrderBookDeltas {
+            market_symbol: market_symbol.clone(),
+        });
+    }
+    if config.trades {
+        channels.push(Channel::Trades {
+            market_symbol: market_symbol.clone(),
+        });
+    }
+    if config.bbo {
+        channels.push(Channel::BBO { market_symbol });
+    }
+    channels
+}
+
+fn to_record(market: &str, message: &Message, clock: &Clock) -> Option<Record> {
+    let data = match message {
+        Message::OrderBook(order_book) => MarketData::OrderBook(order_book.clone()),
+        Message::OrderBookDeltas(order_book) => MarketData::OrderBookDeltas(order_book.clone()),
+        Message::Trades(trade) => MarketData::Trade(trade.clone()),
+        Message::BBO(bbo) => MarketData::Bbo(bbo.clone()),
+        _ => return None,
+    };
+    Some(Record {
+        received_at: clock.now(),
+        market: market.to_string(),
+        data,
+    })
+}
+
+// Compression and writes off the websocket callbacks, until the shutdown
+fn write_records(
+    logger: Logger,
+    receiver: Receiver<Record>,
+    mut writer: RecordingWriter,
+    shutdown: CancellationToken,
+) {
+    let mut write = |record: Record| {
+        if let Err(err) = writer.write(&record) {
+            logger.error(format!("Failed to record market data: {}", err));
+        }
+    };
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(WRITER_POLL_MS)) {
+            Ok(record) => write(record),
+            Err(RecvTimeoutError::Timeout) if shutdown.is_cancelled() => break,
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+    receiver.try_iter().for_each(&mut write);
+    if let Err(err) = writer.finish() {
+        logger.error(format!("Failed to finish market data recording: {}", err));
+    }
+}
+
+// Record the market data of `config.markets` until the shutdown, received at the time of the clock
+pub async fn run_recorder(
+    logger: Logger,
+    config: RecorderConfig,
+    url: URL,
+    clock: Clock,
+    shutdown: CancellationToken,
+) -> Result<(), String> {
+    if config.markets.is_empty() {
+        return Err("No market to record: set recorder.markets".to_string());
+    }
+    let writer = RecordingWriter::open(&config.directory, config.rotate_minutes)
+        .map_err(|err| format!("Cannot open recording directory {}: {}", config.directory.display(), err))?;
+    let (sender, receiver) = unbounded::<Record>();
+    let writer_logger = logger.clone();
+    let writer_shutdown = shutdown.clone();
+    let writer_thread =
+        std::thread::spawn(move || write_records(writer_logger, receiver, writer, writer_shutdown));
+
+    let manager = WebsocketManager::new(url, None).await;
+    for market in &config.markets {
+        for channel in channels(&config, market) {
+            let sender = sender.clone();
+            let symbol = market.clone();
+            let clock = clock.clone();
+            let callback = Box::new(move |message: &Message| {
+                if let Some(record) = to_record(&symbol, message, &clock) {
+                    let _ = sender.send(record);
+                }
+            });
+            if let Err(err) = manager.subscribe(channel, callback).await {
+                logger.error(format!("Failed to subscribe recorder to {}: {}", market, err));
+            }
+        }
+    }
+    drop(sender);
+    logger.info(format!(
+        "Recording {} into {}",
+        config.markets.join(", "),
+        config.directory.display()
+    ));
+
+    shutdown.cancelled().await;
+    let _ = manager.stop().await;
+    let _ = tokio::task::spawn_blocking(move || writer_thread.join()).await;
+    logger.info("Market data recording stopped.");
+    Ok(())
+}
@@ -0,0 +1,129 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::clock::Clock;
+use crate::exchange::Exchange;
+use crate::journal::{Journal, ReasonCode};
+use crate::Logger;
+
+// Shutdown sequence of the back-end, triggered by the window close or a signal through the
+// cancellation token given to `run_backend_logic`
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownConfig {
+    // Cancel the bot Option orders when the shutdown is requested
+    pub cancel_orders: bool,
+    // Cancel tries before giving up (exchange errors, rate limit)
+    pub cancel_attempts: u32,
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self {
+            cancel_orders: true,
+            cancel_attempts: 3,
+        }
+    }
+}
+
+// Wait between two cancel tries
+const CANCEL_RETRY_DELAY_SEC: u64 = 2;
+
+// End of the trading loop: cancel orders (if requested), unsubscribe websocket channels and flush.
+// Err if the orders could not be cancelled.
+pub(crate) async fn close_backend(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    config: &ShutdownConfig,
+    clock: &Clock,
+    requested: bool,
+) -> Result<(), String> {
+    let mut result = Ok(());
+    if requested {
+        logger.info("Shutdown requested, stopping the bot.");
+        if config.cancel_orders {
+            result = cancel_with_retries(logger, journal, exchange, config.cancel_attempts, clock).await;
+        }
+    }
+    if let Err(err) = exchange.stop().await {
+        logger.error(err);
+    }
+    journal.flush();
+    logger.info("Back-end stopped.");
+    crate::logging::flush();
+    result
+}
+
+// Orders and markets are fetched again at each try, only the ones left are cancelled. An acknowledged
+// cancel only counts once the orders are gone from the open orders.
+async fn cancel_with_retries(
+    logger: &Logger,
+    journal: &Journal,
+    exchange: &mut impl Exchange,
+    attempts: u32,
+    clock: &Clock,
+) -> Result<(), String> {
+    let attempts = attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        let result = match crate::cancel_bot_orders(logger, journal, exchange, ReasonCode::Shutdown).await {
+            Ok(()) => check_no_bot_order(exchange).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => return Ok(()),
+            Err(err) if attempt >= attempts => {
+                return Err(format!("Cannot cancel bot orders after {} attempts: {}", attempts, err));
+            }
+            Err(err) => {
+                logger.warn(format!(
+                    "Cancel attempt {}/{} failed, retrying in {} s: {}",
+                    attempt, attempts, CANCEL_RETRY_DELAY_SEC, err
+                ));
+                clock.sleep(Duration::from_secs(CANCEL_RETRY_DELAY_SEC)).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+// Err while Option orders are still open
+async fn check_no_bot_order(exchange: &mut impl Exchange) -> Result<(), String> {
+    let orders = exchange
+        .open_orders()
+        .await
+        .map_err(|err| format!("Failed to fetch orders after the cancel: {}", err))?;
+    let left = orders.results.iter().filter(|order| !order.market.contains("-PERP")).count();
+    if left > 0 {
+        return Err(format!("{} bot orders still open after the cancel", left));
+    }
+    Ok(())
+}
+
+// Name of the first stop signal received
+pub async fn shutdown_signal() -> &'static str {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => "SIGINT",
+                    _ = sigterm.recv() => "SIGTERM",
+                }
+            }
+            Err(err) => {
+                log::warn!("Cannot listen to SIGTERM: {}", err);
+                let _ = tokio::signal::ctrl_c().await;
+                "SIGINT"
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+        "Ctrl-C"
+    }
+}
@@ -0,0 +1,820 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use paradex::error::Error;
+use paradex::structs::{
+    CancelByMarketResponse, Fill, FillLiquidity, FillType, Level, ModifyOrderRequest, OrderBook,
+    OrderBookUpdateType, OrderInstruction, OrderRequest, OrderStatus, OrderType, OrderUpdate,
+    OrderUpdates, Position, PositionSide, PositionStatus, Positions, Side,
+};
+use rust_decimal::Decimal;
+use http::StatusCode;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::clock::Clock;
+use crate::exchange::{Exchange, ExchangeResult};
+use crate::fees::FeeModel;
+use crate::strategy::BookTop;
+
+// In-process venue for the integration tests: Option order books with price-time priority matching
+// of the bot orders against scripted counterparties. The bot account sees its orders, fills and
+// positions as on Paradex, and the same updates are kept as events for the assertions.
+// Faults (latency, errors, lost messages, disconnects, partial fills) can be injected in the requests
+// of the bot.
+
+// Owner of an order of the simulated books
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trader {
+    Bot,
+    Counterparty,
+}
+
+// Updates of the bot account, as sent on the private websocket channels
+#[derive(Clone, Debug)]
+pub enum SimEvent {
+    Order(OrderUpdate),
+    Fill(Fill),
+    Position(Position),
+}
+
+// Misbehaviour of the venue toward the bot, drawn from a seeded generator. Rates are per request
+// (per snapshot / update for the messages), between 0 and 1. Default: no fault.
+#[derive(Clone, Debug)]
+pub struct SimFaults {
+    // Time taken by each request
+    pub latency: Duration,
+    // Requests answered by a 503 error
+    pub server_error_rate: f64,
+    // Share of the 503 on order requests processed anyway, the response being lost
+    pub lost_response_share: f64,
+    // Requests answered by a 429 error
+    pub rate_limit_rate: f64,
+    // Requests closing the connection: every request fails during `disconnect_duration`
+    pub disconnect_rate: f64,
+    pub disconnect_duration: Duration,
+    // Order book snapshots and account updates never received
+    pub dropped_message_rate: f64,
+    // Order book snapshots replaced by the previous one of the market
+    pub stale_snapshot_rate: f64,
+    // Bot orders taking liquidity filled for half of their size at most
+    pub partial_fill_rate: f64,
+    // Cancels acknowledged without effect, the orders staying open
+    pub ignored_cancel_rate: f64,
+    pub seed: u64,
+}
+
+impl Default for SimFaults {
+    fn default() -> Self {
+        Self {
+            latency: Duration::ZERO,
+            server_error_rate: 0.0,
+            lost_response_share: 0.0,
+            rate_limit_rate: 0.0,
+            disconnect_rate: 0.0,
+            disconnect_duration: Duration::from_secs(30),
+            dropped_message_rate: 0.0,
+            stale_snapshot_rate: 0.0,
+            partial_fill_rate: 0.0,
+            ignored_cancel_rate: 0.0,
+            seed: 1,
+        }
+    }
+}
+
+fn disconnected() -> Error {
+    Error::RestError("connection closed by the simulated exchange".to_string())
+}
+
+#[derive(Clone, Debug)]
+struct SimOrder {
+    trader: Trader,
+    id: String,
+    market: String,
+    side: Side,
+    order_type: OrderType,
+    instruction: OrderInstruction,
+    price: Option<Decimal>,
+    size: Decimal,
+    remaining: Decimal,
+    // Sum of price * size of the fills, for the average fill price
+    filled_notional: Decimal,
+    status: OrderStatus,
+    cancel_reason: String,
+    created_at: u64,
+    last_updated_at: u64,
+    // Time priority in the book, renewed when the order is modified
+    priority: u64,
+}
+
+impl SimOrder {
+    fn is_resting(&self) -> bool {
+        self.status == OrderStatus::OPEN
+    }
+
+    fn update(&self) -> OrderUpdate {
+        let filled = self.size - self.remaining;
+        let avg_fill_price = if filled.is_zero() {
+            0.0
+        } else {
+            (self.filled_notional / filled).to_f64().unwrap_or_default()
+        };
+        OrderUpdate {
+            account: match self.trader {
+                Trader::Bot => "bot".to_string(),
+                Trader::Counterparty => "counterparty".to_string(),
+            },
+            cancel_reason: self.cancel_reason.clone(),
+            client_id: String::new(),
+            created_at: self.created_at,
+            id: self.id.clone(),
+            instruction: self.instruction.clone(),
+            last_updated_at: self.last_updated_at,
+            market: self.market.clone(),
+            price: self.price,
+            remaining_size: self.remaining,
+            side: self.side,
+            size: self.size,
+            status: self.status.clone(),
+            timestamp: self.last_updated_at,
+            order_type: self.order_type,
+            seq_no: self.priority,
+            avg_fill_price,
+            received_at: self.created_at,
+            published_at: self.last_updated_at,
+            flags: vec![],
+            trigger_price: None,
+        }
+    }
+
+    // True if the taker order can trade with this resting order
+    fn crosses(&self, taker: &SimOrder) -> bool {
+        self.market == taker.market
+            && self.side != taker.side
+            && self.is_resting()
+            && match (taker.price, self.price) {
+                (None, _) => true,
+                (Some(taker_price), Some(price)) => match taker.side {
+                    Side::BUY => price <= taker_price,
+                    Side::SELL => price >= taker_price,
+                },
+                (Some(_), None) => false,
+            }
+    }
+}
+
+// Position of the bot in one market, size < 0 for a short
+#[derive(Clone, Debug, Default)]
+struct SimPosition {
+    size: Decimal,
+    average_entry_price: Decimal,
+    realized_pnl: Decimal,
+    last_fill_id: String,
+    last_updated_at: u64,
+    seq_no: u64,
+}
+
+impl SimPosition {
+    // Realized pnl of the fill
+    fn apply(&mut self, side: Side, price: Decimal, size: Decimal) -> Decimal {
+        let signed = match side {
+            Side::BUY => size,
+            Side::SELL => -size,
+        };
+        let mut realized = Decimal::ZERO;
+        if self.size.is_zero() || self.size.is_sign_positive() == signed.is_sign_positive() {
+            let total = self.size + signed;
+            self.average_entry_price =
+                (self.average_entry_price * self.size.abs() + price * size) / total.abs();
+        } else {
+            let closed = size.min(self.size.abs());
+            realized = match side {
+                Side::SELL => (price - self.average_entry_price) * closed,
+                Side::BUY => (self.average_entry_price - price) * closed,
+            };
+            // position flipped: the rest is opened at the fill price
+            if size > closed {
+                self.average_entry_price = price;
+            }
+        }
+        self.size += signed;
+        if self.size.is_zero() {
+            self.average_entry_price = Decimal::ZERO;
+        }
+        self.realized_pnl += realized;
+        realized
+    }
+
+    fn position(&self, market: &str) -> Position {
+        let price = self.average_entry_price.to_f64().unwrap_or_default();
+        let size = self.size.to_f64().unwrap_or_default();
+        Position {
+            average_entry_price: price,
+            average_entry_price_usd: price,
+            cached_funding_index: 0.0,
+            cost: price * size,
+            cost_usd: price * size,
+            id: format!("position-{}", market),
+            last_fill_id: self.last_fill_id.clone(),
+            last_updated_at: self.last_updated_at,
+            leverage: String::new(),
+            liquidation_price: 0.0,
+            market: market.to_string(),
+            seq_no: self.seq_no,
+            side: if self.size.is_sign_negative() {
+                PositionSide::SHORT
+            } else {
+                PositionSide::LONG
+            },
+            size,
+            status: if self.size.is_zero() {
+                PositionStatus::CLOSED
+            } else {
+                PositionStatus::OPEN
+            },
+            unrealized_funding_pnl: 0.0,
+            unrealized_pnl: 0.0,
+        }
+    }
+}
+
+struct SimState {
+    // Time of the orders and fills
+    clock: Clock,
+    fee_model: FeeModel,
+    orders: BTreeMap<String, SimOrder>,
+    positions: BTreeMap<String, SimPosition>,
+    // Fills of the bot account
+    fills: Vec<Fill>,
+    events: Vec<SimEvent>,
+    seq_no: u64,
+    stopped: bool,
+    faults: SimFaults,
+    rng: StdRng,
+    // End of the current disconnect
+    disconnected_until: Option<DateTime<Utc>>,
+    // Last snapshot served per market, for the stale ones
+    snapshots: BTreeMap<String, OrderBook>,
+}
+
+impl SimState {
+    fn next_seq(&mut self) -> u64 {
+        self.seq_no += 1;
+        self.seq_no
+    }
+
+    fn now(&self) -> u64 {
+        self.clock.now().timestamp_millis() as u64
+    }
+
+    // True with the probability `rate`
+    fn draw(&mut self, rate: f64) -> bool {
+        rate > 0.0 && self.rng.gen_bool(rate.min(1.0))
+    }
+
+    // Fault of a request after its latency: Err if rejected, Some(error) if the response of an order
+    // request is lost after its processing
+    fn request_fault(&mut self, order_request: bool) -> ExchangeResult<Option<Error>> {
+        let now = self.clock.now();
+        if self.disconnected_until.is_some_and(|until| now < until) {
+            return Err(disconnected());
+        }
+        if self.draw(self.faults.disconnect_rate) {
+            let duration = TimeDelta::from_std(self.faults.disconnect_duration).unwrap_or_default();
+            self.disconnected_until = Some(now + duration);
+            return Err(disconnected());
+        }
+        if self.draw(self.faults.rate_limit_rate) {
+            return Err(Error::HTTPError {
+                status_code: StatusCode::TOO_MANY_REQUESTS,
+            });
+        }
+        if self.draw(self.faults.server_error_rate) {
+            let error = Error::HTTPError {
+                status_code: StatusCode::SERVICE_UNAVAILABLE,
+            };
+            if order_request && self.draw(self.faults.lost_response_share) {
+                return Ok(Some(error));
+            }
+            return Err(error);
+        }
+        Ok(None)
+    }
+
+    // Update of the bot account, unless dropped
+    fn emit(&mut self, event: SimEvent) {
+        if !self.draw(self.faults.dropped_message_rate) {
+            self.events.push(event);
+        }
+    }
+
+    fn emit_order(&mut self, id: &str) {
+        if let Some(order) = self.orders.get(id)
+            && order.trader == Trader::Bot
+        {
+            let update = order.update();
+            self.emit(SimEvent::Order(update));
+        }
+    }
+
+    fn bot_order(&self, id: &str) -> ExchangeResult<&SimOrder> {
+        self.orders
+            .get(id)
+            .filter(|order| order.trader == Trader::Bot)
+            .ok_or_else(|| Error::RestError(format!("ORDER_ID_NOT_FOUND: {}", id)))
+    }
+
+    fn cancel(&mut self, id: &str, reason: &str) {
+        let now = self.now();
+        if let Some(order) = self.orders.get_mut(id)
+            && order.is_resting()
+        {
+            order.status = OrderStatus::CLOSED;
+            order.cancel_reason = reason.to_string();
+            order.last_updated_at = now;
+            self.emit_order(id);
+        }
+    }
+
+    // Best resting order against the taker: best price first, then the oldest
+    fn best_maker(&self, taker: &SimOrder) -> Option<String> {
+        self.orders
+            .values()
+            .filter(|maker| maker.crosses(taker))
+            .min_by(|a, b| {
+                let by_price = match taker.side {
+                    Side::BUY => a.price.cmp(&b.price),
+                    Side::SELL => b.price.cmp(&a.price),
+                };
+                by_price.then(a.priority.cmp(&b.priority))
+            })
+            .map(|maker| maker.id.clone())
+    }
+
+    // Add the order to the book: match it then rest the remaining size (limit GTC) or cancel it
+    fn submit(&mut self, mut order: SimOrder) -> OrderUpdate {
+        let id = order.id.clone();
+        if order.instruction == OrderInstruction::POST_ONLY && self.best_maker(&order).is_some() {
+            order.status = OrderStatus::CLOSED;
+            order.cancel_reason = "POST_ONLY_WOULD_CROSS".to_string();
+            self.orders.insert(id.clone(), order);
+            self.emit_order(&id);
+            return self.orders[&id].update();
+        }
+        self.orders.insert(id.clone(), order);
+        self.match_order(&id);
+
+        let order = self.orders.get_mut(&id).expect("submitted order");
+        if !order.remaining.is_zero()
+            && (order.order_type == OrderType::MARKET || order.instruction == OrderInstruction::IOC)
+        {
+            order.status = OrderStatus::CLOSED;
+            order.cancel_reason = "IOC".to_string();
+        }
+        self.emit_order(&id);
+        self.orders[&id].update()
+    }
+
+    // Match the taker with the book. A bot taker may be filled for half of its size at most (fault),
+    // the rest being cancelled or resting like the size without match.
+    fn match_order(&mut self, taker_id: &str) {
+        let taker = &self.orders[taker_id];
+        let mut to_fill = taker.remaining;
+        if taker.trader == Trader::Bot && self.draw(self.faults.partial_fill_rate) {
+            to_fill /= Decimal::TWO;
+        }
+        loop {
+            let taker = &self.orders[taker_id];
+            if to_fill.is_zero() {
+                break;
+            }
+            let Some(maker_id) = self.best_maker(taker) else {
+                break;
+            };
+            // self-trade prevention: the resting order of the same account expires
+            if taker.trader == Trader::Bot && self.orders[&maker_id].trader == Trader::Bot {
+                self.cancel(&maker_id, "SELF_TRADE");
+                continue;
+            }
+            let maker = &self.orders[&maker_id];
+            let price = maker.price.unwrap_or_default();
+            let size = to_fill.min(maker.remaining);
+            to_fill -= size;
+            self.trade(&maker_id, price, size, FillLiquidity::MAKER);
+            self.trade(taker_id, price, size, FillLiquidity::TAKER);
+        }
+    }
+
+    // One side of a trade
+    fn trade(&mut self, id: &str, price: Decimal, size: Decimal, liquidity: FillLiquidity) {
+        let now = self.now();
+        let seq_no = self.next_seq();
+        let order = self.orders.get_mut(id).expect("traded order");
+        order.remaining -= size;
+        order.filled_notional += price * size;
+        order.last_updated_at = now;
+        if order.remaining.is_zero() {
+            order.status = OrderStatus::CLOSED;
+        } else if order.order_type == OrderType::LIMIT {
+            order.status = OrderStatus::OPEN;
+        }
+        if order.trader != Trader::Bot {
+            return;
+        }
+        let (market, side, order_id, remaining) =
+            (order.market.clone(), order.side, order.id.clone(), order.remaining);
+        if liquidity == FillLiquidity::MAKER {
+            self.emit_order(id);
+        }
+
+        let notional = (price * size).to_f64().unwrap_or_default();
+        let fee = match liquidity {
+            FillLiquidity::MAKER => self.fee_model.maker_fee(notional),
+            FillLiquidity::TAKER => self.fee_model.taker_fee(notional),
+        };
+        let fill_id = format!("fill-{}", seq_no);
+        let position = self.positions.entry(market.clone()).or_default();
+        let realized_pnl = position.apply(side, price, size);
+        position.last_fill_id = fill_id.clone();
+        position.last_updated_at = now;
+        position.seq_no = seq_no;
+        let position = position.position(&market);
+
+        let fill = Fill {
+            client_id: String::new(),
+            created_at: now,
+            fee,
+            fee_currency: "USDC".to_string(),
+            id: fill_id,
+            liquidity,
+            market,
+            order_id,
+            price: price.to_f64().unwrap_or_default(),
+            side,
+            size: size.to_f64().unwrap_or_default(),
+            remaining_size: remaining.to_f64().unwrap_or_default(),
+            fill_type: FillType::FILL,
+            realized_pnl: realized_pnl.to_f64().unwrap_or_default(),
+        };
+        self.fills.push(fill.clone());
+        self.emit(SimEvent::Fill(fill));
+        self.emit(SimEvent::Position(position));
+    }
+
+    fn new_order(&mut self, trader: Trader, request: OrderRequest) -> SimOrder {
+        let now = self.now();
+        let priority = self.next_seq();
+        SimOrder {
+            trader,
+            id: format!("sim-{}", priority),
+            market: request.market,
+            side: request.side,
+            order_type: request.order_type,
+            instruction: request.instruction,
+            // the price of a market order is ignored
+            price: request.price.filter(|_| request.order_type == OrderType::LIMIT),
+            size: request.size,
+            remaining: request.size,
+            filled_notional: Decimal::ZERO,
+            status: OrderStatus::OPEN,
+            cancel_reason: String::new(),
+            created_at: now,
+            last_updated_at: now,
+            priority,
+        }
+    }
+
+    // Levels of one side, aggregated by price from the best one
+    fn levels(&self, market: &str, side: Side) -> Vec<Level> {
+        let mut levels: BTreeMap<Decimal, Decimal> = BTreeMap::new();
+        for order in self.orders.values() {
+            if order.market == market
+                && order.side == side
+                && order.is_resting()
+                && let Some(price) = order.price
+            {
+                *levels.entry(price).or_default() += order.remaining;
+            }
+        }
+        let levels = levels.into_iter().map(|(price, size)| Level {
+            side,
+            price: price.to_f64().unwrap_or_default(),
+            size: size.to_f64().unwrap_or_default(),
+        });
+        match side {
+            Side::BUY => levels.rev().collect(),
+            Side::SELL => levels.collect(),
+        }
+    }
+
+    fn snapshot(&mut self, market: &str) -> OrderBook {
+        let mut inserts = self.levels(market, Side::BUY);
+        inserts.extend(self.levels(market, Side::SELL));
+        OrderBook {
+            seq_no: self.next_seq(),
+            market: market.to_string(),
+            last_updated_at: self.now(),
+            update_type: OrderBookUpdateType::Snapshot,
+            deletes: vec![],
+            inserts,
+            updates: vec![],
+        }
+    }
+}
+
+fn decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default()
+}
+
+// GTC order of a counterparty or of the bot outside of the bot loop
+fn order_request(market: &str, side: Side, order_type: OrderType, price: Option<Decimal>, size: f64) -> OrderRequest {
+    OrderRequest {
+        instruction: OrderInstruction::GTC,
+        market: market.to_string(),
+        price,
+        side,
+        size: decimal(size),
+        order_type,
+        client_id: None,
+        flags: vec![],
+        recv_window: None,
+        stp: None,
+        trigger_price: None,
+    }
+}
+
+// Handle on the simulated venue, the clones share the same books
+#[derive(Clone)]
+pub struct SimExchange {
+    state: Arc<Mutex<SimState>>,
+}
+
+impl Default for SimExchange {
+    fn default() -> Self {
+        Self::new(FeeModel::from_config(&Default::default()), Clock::System)
+    }
+}
+
+impl SimExchange {
+    // Fees of the bot fills from the given rates, timestamps from the clock
+    pub fn new(fee_model: FeeModel, clock: Clock) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SimState {
+                clock,
+                fee_model,
+                orders: BTreeMap::new(),
+                positions: BTreeMap::new(),
+                fills: Vec::new(),
+                events: Vec::new(),
+                seq_no: 0,
+                stopped: false,
+                faults: SimFaults::default(),
+                rng: StdRng::seed_from_u64(SimFaults::default().seed),
+                disconnected_until: None,
+                snapshots: BTreeMap::new(),
+            })),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    // Limit GTC order, matched against the book then resting. Returns the order id.
+    pub fn place_limit(&self, trader: Trader, market: &str, side: Side, price: f64, size: f64) -> String {
+        let request = order_request(market, side, OrderType::LIMIT, Some(decimal(price)), size);
+        let mut state = self.state();
+        let order = state.new_order(trader, request);
+        state.submit(order).id
+    }
+
+    // Market order, the size not matched is cancelled. Returns the order id.
+    pub fn place_market(&self, trader: Trader, market: &str, side: Side, size: f64) -> String {
+        let request = order_request(market, side, OrderType::MARKET, None, size);
+        let mut state = self.state();
+        let order = state.new_order(trader, request);
+        state.submit(order).id
+    }
+
+    // Cancel a resting order of any trader
+    pub fn cancel(&self, order_id: &str) {
+        self.state().cancel(order_id, "USER_CANCELED");
+    }
+
+    pub fn order(&self, order_id: &str) -> Option<OrderUpdate> {
+        self.state().orders.get(order_id).map(SimOrder::update)
+    }
+
+    pub fn position(&self, market: &str) -> Option<Position> {
+        self.state()
+            .positions
+            .get(market)
+            .map(|position| position.position(market))
+    }
+
+    // Fills of the bot account, oldest first
+    pub fn bot_fills(&self) -> Vec<Fill> {
+        self.state().fills.clone()
+    }
+
+    // Updates of the bot account since the last call
+    pub fn take_events(&self) -> Vec<SimEvent> {
+        std::mem::take(&mut self.state().events)
+    }
+
+    // Snapshot as sent on the order book channel: bids from the best one, then asks
+    pub fn snapshot(&self, market: &str) -> OrderBook {
+        self.state().snapshot(market)
+    }
+
+    // Faults of the next requests, the random draws starting again from the seed
+    pub fn set_faults(&self, faults: SimFaults) {
+        let mut state = self.state();
+        state.rng = StdRng::seed_from_u64(faults.seed);
+        state.faults = faults;
+    }
+
+    // Every request of the bot fails during the given time of the clock
+    pub fn disconnect(&self, duration: Duration) {
+        let mut state = self.state();
+        let until = state.clock.now() + TimeDelta::from_std(duration).unwrap_or_default();
+        state.disconnected_until = Some(until);
+    }
+
+    // Latency then fault of a request of the bot, see `SimState::request_fault`
+    async fn request(&self, order_request: bool) -> ExchangeResult<Option<Error>> {
+        let (clock, latency) = {
+            let state = self.state();
+            (state.clock.clone(), state.faults.latency)
+        };
+        if !latency.is_zero() {
+            clock.sleep(latency).await;
+        }
+        self.state().request_fault(order_request)
+    }
+
+    // True once the bot stopped its market data subscriptions
+    pub fn is_stopped(&self) -> bool {
+        self.state().stopped
+    }
+}
+
+// Response of an order request processed by the exchange, unless lost
+fn respond<T>(lost: Option<Error>, response: T) -> ExchangeResult<T> {
+    match lost {
+        Some(error) => Err(error),
+        None => Ok(response),
+    }
+}
+
+impl Exchange for SimExchange {
+    async fn positions(&self) -> ExchangeResult<Positions> {
+        self.request(false).await?;
+        let state = self.state();
+        Ok(Positions {
+            results: state
+                .positions
+                .iter()
+                .map(|(market, position)| position.position(market))
+                .collect(),
+        })
+    }
+
+    async fn open_orders(&self) -> ExchangeResult<OrderUpdates> {
+        self.request(false).await?;
+        let state = self.state();
+        Ok(OrderUpdates {
+            results: state
+                .orders
+                .values()
+                .filter(|order| order.trader == Trader::Bot && order.is_resting())
+                .map(SimOrder::update)
+                .collect(),
+        })
+    }
+
+    async fn fills(
+        &self,
+        market: Option<String>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> ExchangeResult<Vec<Fill>> {
+        self.request(false).await?;
+        let start = start.map(|start| start.timestamp_millis() as u64);
+        let end = end.map(|end| end.timestamp_millis() as u64);
+        Ok(self
+            .state()
+            .fills
+            .iter()
+            .filter(|fill| market.as_ref().is_none_or(|market| &fill.market == market))
+            .filter(|fill| start.is_none_or(|start| fill.created_at >= start))
+            .filter(|fill| end.is_none_or(|end| fill.created_at <= end))
+            .cloned()
+            .collect())
+    }
+
+    async fn create_order(&self, request: OrderRequest) -> ExchangeResult<OrderUpdate> {
+        let lost = self.request(true).await?;
+        if request.order_type != OrderType::MARKET && request.order_type != OrderType::LIMIT {
+            return Err(Error::RestError(format!(
+                "Order type {:?} not supported by the simulation",
+                request.order_type
+            )));
+        }
+        if request.order_type == OrderType::LIMIT && request.price.is_none() {
+            return Err(Error::RestError("Limit order without price".to_string()));
+        }
+        let mut state = self.state();
+        let order = state.new_order(Trader::Bot, request);
+        respond(lost, state.submit(order))
+    }
+
+    // New price / size with a new time priority, matched if the new price crosses
+    async fn modify_order(&self, request: ModifyOrderRequest) -> ExchangeResult<OrderUpdate> {
+        let lost = self.request(true).await?;
+        let mut state = self.state();
+        let order = state.bot_order(&request.id)?;
+        if !order.is_resting() || order.market != request.market || order.side != request.side {
+            return Err(Error::RestError(format!("ORDER_IS_NOT_MODIFIABLE: {}", request.id)));
+        }
+        let filled = order.size - order.remaining;
+        if request.size <= filled || request.price.is_none() {
+            return Err(Error::RestError(format!("INVALID_ORDER_SIZE: {}", request.id)));
+        }
+        let now = state.now();
+        let priority = state.next_seq();
+        let order = state.orders.get_mut(&request.id).expect("modified order");
+        order.price = request.price;
+        order.remaining = request.size - filled;
+        order.size = request.size;
+        order.last_updated_at = now;
+        order.priority = priority;
+        state.match_order(&request.id);
+        state.emit_order(&request.id);
+        respond(lost, state.orders[&request.id].update())
+    }
+
+    async fn cancel_order(&self, order_id: String) -> ExchangeResult<()> {
+        let lost = self.request(true).await?;
+        let mut state = self.state();
+        if !state.bot_order(&order_id)?.is_resting() {
+            return Err(Error::RestError(format!("ORDER_IS_CLOSED: {}", order_id)));
+        }
+        let ignored_rate = state.faults.ignored_cancel_rate;
+        if !state.draw(ignored_rate) {
+            state.cancel(&order_id, "USER_CANCELED");
+        }
+        respond(lost, ())
+    }
+
+    async fn cancel_all_orders_for_market(&self, market: String) -> ExchangeResult<CancelByMarketResponse> {
+        let lost = self.request(true).await?;
+        let mut state = self.state();
+        let ids: Vec<String> = state
+            .orders
+            .values()
+            .filter(|order| order.trader == Trader::Bot && order.market == market && order.is_resting())
+            .map(|order| order.id.clone())
+            .collect();
+        let ignored_rate = state.faults.ignored_cancel_rate;
+        if !state.draw(ignored_rate) {
+            for id in &ids {
+                state.cancel(id, "USER_CANCELED");
+            }
+        }
+        respond(
+            lost,
+            CancelByMarketResponse {
+                market,
+                message: format!("{} orders cancelled", ids.len()),
+            },
+        )
+    }
+
+    // Snapshot of the order book channel, which may be lost or the previous one of the market
+    async fn book_top(&mut self, market: String) -> Result<BookTop, String> {
+        self.request(false).await.map_err(|err| err.to_string())?;
+        let mut state = self.state();
+        let dropped_rate = state.faults.dropped_message_rate;
+        if state.draw(dropped_rate) {
+            return Err(format!("No order book snapshot of {} received", market));
+        }
+        let stale_rate = state.faults.stale_snapshot_rate;
+        if state.draw(stale_rate)
+            && let Some(previous) = state.snapshots.get(&market)
+        {
+            return Ok(BookTop::from_snapshot(previous));
+        }
+        let snapshot = state.snapshot(&market);
+        let book = BookTop::from_snapshot(&snapshot);
+        state.snapshots.insert(market, snapshot);
+        Ok(book)
+    }
+
+    async fn stop(&mut self) -> Result<(), String> {
+        self.state().stopped = true;
+        Ok(())
+    }
+}
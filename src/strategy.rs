@@ -0,0 +1,172 @@
+use paradex::structs::{Level, OrderBook, Side};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde::Deserialize;
+
+use crate::fees::{self, ExitEstimate, FeeModel};
+
+// Rules applied to each open bid, without side effect so that the bot and the backtester share them
+
+pub const SIZE_MULTIPLIER_BIDDING_MARGIN: i32 = 5;
+pub const STEP_SIZE: f64 = 0.1;
+pub const MAX_SPREAD_PRICE: i32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StrategyParams {
+    // Margin kept above the second bid / added to go first bid alone
+    pub step_size: f64,
+    // Bids less than this below our price can absorb our market sell
+    pub max_spread_price: f64,
+    // Size required in the exit window, in multiples of our size
+    pub size_multiplier: f64,
+}
+
+impl Default for StrategyParams {
+    fn default() -> Self {
+        Self {
+            step_size: STEP_SIZE,
+            max_spread_price: MAX_SPREAD_PRICE as f64,
+            size_multiplier: SIZE_MULTIPLIER_BIDDING_MARGIN as f64,
+        }
+    }
+}
+
+impl StrategyParams {
+    pub fn step_size(&self) -> Decimal {
+        Decimal::from_f64(self.step_size).unwrap_or_default()
+    }
+
+    pub fn max_spread_price(&self) -> Decimal {
+        Decimal::from_f64(self.max_spread_price).unwrap_or_default()
+    }
+
+    pub fn size_multiplier(&self) -> Decimal {
+        Decimal::from_f64(self.size_multiplier).unwrap_or_default()
+    }
+}
+
+// Rules of one account: strategy parameters and limit of the round trip cost
+#[derive(Clone, Copy, Debug)]
+pub struct QuoteRules {
+    pub params: StrategyParams,
+    pub fee_model: FeeModel,
+    pub max_cost_per_1k: Option<f64>,
+}
+
+// Levels of the order book read by the rules
+#[derive(Clone, Debug, Default)]
+pub struct BookTop {
+    pub first_bid: Option<Level>,
+    pub second_bid: Option<Level>,
+    pub third_bid: Option<Level>,
+    pub first_ask: Option<Level>,
+}
+
+impl BookTop {
+    // The 3 first levels of the snapshot are the bids, the first sell level is the ask
+    pub fn from_snapshot(snapshot: &OrderBook) -> Self {
+        let inserts = &snapshot.inserts;
+        Self {
+            first_bid: inserts.first().cloned(),
+            second_bid: inserts.get(1).cloned(),
+            third_bid: inserts.get(2).cloned(),
+            first_ask: inserts.iter().find(|level| level.side == Side::SELL).cloned(),
+        }
+    }
+
+    pub fn bids(&self) -> Vec<&Level> {
+        [self.first_bid.as_ref(), self.second_bid.as_ref(), self.third_bid.as_ref()]
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+}
+
+// Price of our bid (price, size) after the repricing rules:
+// - not first bid: join the first bid
+// - first bid alone: come back to second bid + step if further
+// - first bid with others: go first bid alone (+ step) unless it would touch the first ask
+pub fn determine_new_bid_price(
+    price: Option<Decimal>,
+    size: Decimal,
+    book: &BookTop,
+    params: &StrategyParams,
+) -> Option<Decimal> {
+    let step_size = params.step_size();
+    // Keep old price
+    let mut new_price = price.unwrap_or_default();
+
+    if let Some(bid) = &book.first_bid
+        && let Some(decimal_price) = price
+        && let Some(bid_price_decimal) = Decimal::from_f64(bid.price)
+    {
+        if decimal_price == bid_price_decimal {
+            if let Some(bid_size_decimal) = Decimal::from_f64(bid.size) {
+                if size == bid_size_decimal {
+                    if let Some(sec_bid) = &book.second_bid
+                        && let Some(sec_bid_price_decimal) = Decimal::from_f64(sec_bid.price)
+                        && new_price - sec_bid_price_decimal > step_size
+                    {
+                        new_price = sec_bid_price_decimal + step_size;
+                    }
+                } else if let Some(ask) = &book.first_ask
+                    && let Some(ask_price_decimal) = Decimal::from_f64(ask.price)
+                    && ask_price_decimal > bid_price_decimal + step_size
+                {
+                    new_price = bid_price_decimal + step_size;
+                }
+            }
+        } else {
+            new_price = bid_price_decimal;
+        }
+    }
+    Some(new_price)
+}
+
+// Size of the given bids close enough (< max_spread_price) below our price to absorb our market sell
+pub fn exit_window_liquidity(bids: &[&Level], price: Decimal, params: &StrategyParams) -> f64 {
+    let max_diff_decimal = params.max_spread_price();
+    bids.iter()
+        .filter(|bid| {
+            Decimal::from_f64(bid.price)
+                .is_some_and(|bid_price_decimal| price - bid_price_decimal < max_diff_decimal)
+        })
+        .map(|bid| bid.size)
+        .sum()
+}
+
+// Exit liquidity of the 3 first bids for our new price, None if the book is incomplete
+pub fn exit_liquidity(book: &BookTop, new_price: Option<Decimal>, params: &StrategyParams) -> Option<f64> {
+    match (&book.first_bid, &book.second_bid, &book.third_bid, new_price) {
+        (Some(bid1), Some(bid2), Some(bid3), Some(price)) => {
+            Some(exit_window_liquidity(&[bid1, bid2, bid3], price, params))
+        }
+        _ => None,
+    }
+}
+
+// True if the exit liquidity is below size * size_multiplier: the bid must be cancelled
+pub fn is_liquidity_low(size: Decimal, liquidity: f64, params: &StrategyParams) -> bool {
+    Decimal::from_f64(liquidity).is_some_and(|liquidity| liquidity < size * params.size_multiplier())
+}
+
+// Round trip of our bid at the new price sold into the 3 first bids, our order being in the book at
+// `price` (before the reprice)
+pub fn estimate_bid_exit(
+    price: Option<Decimal>,
+    size: Decimal,
+    book: &BookTop,
+    new_price: Option<Decimal>,
+    fee_model: &FeeModel,
+) -> Option<ExitEstimate> {
+    new_price.and_then(|new_price| fees::estimate_exit(&book.bids(), price, new_price, size, fee_model))
+}
+
+// True if the round trip costs more than the maximum per $1k of volume, or if there is no exit
+// (bids too thin for our size)
+pub fn is_too_expensive(estimate: Option<&ExitEstimate>, max_cost_per_1k: f64) -> bool {
+    estimate
+        .and_then(|estimate| estimate.cost_per_1k())
+        .is_none_or(|cost_per_1k| cost_per_1k > max_cost_per_1k)
+}
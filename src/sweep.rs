@@ -0,0 +1,339 @@
+use chrono::NaiveDate;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::backtest::{self, BacktestConfig, BacktestStats};
+use crate::recorder::Record;
+use crate::strategy::{QuoteRules, StrategyParams};
+
+// Search of the strategy parameters over backtests: every candidate is replayed on each recorded day
+// of the training days (in parallel), ranked by cost per $1k of volume, and the best ones are replayed
+// on the most recent days, left out of the ranking, to check that the result holds out of sample.
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchMethod {
+    // Every combination of the ranges
+    #[default]
+    Grid,
+    // `samples` combinations drawn in the ranges
+    Random,
+}
+
+// Values from min to max (included) by step
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParamRange {
+    pub min: f64,
+    pub max: f64,
+    pub step: f64,
+}
+
+impl ParamRange {
+    fn fixed(value: f64) -> Self {
+        Self {
+            min: value,
+            max: value,
+            step: value,
+        }
+    }
+
+    fn count(&self) -> usize {
+        if self.step <= 0.0 || self.max <= self.min {
+            return 1;
+        }
+        ((self.max - self.min) / self.step + 1e-9).floor() as usize + 1
+    }
+
+    // Rounded so that 0.1 + 0.2 gives the price 0.3 of the exchange
+    fn value(&self, index: usize) -> f64 {
+        let value = self.min + self.step.max(0.0) * index as f64;
+        (value * 1e6).round() / 1e6
+    }
+
+    pub fn values(&self) -> Vec<f64> {
+        (0..self.count()).map(|index| self.value(index)).collect()
+    }
+
+    fn sample(&self, rng: &mut StdRng) -> f64 {
+        self.value(rng.gen_range(0..self.count()))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SweepConfig {
+    pub method: SearchMethod,
+    pub step_size: ParamRange,
+    pub max_spread_price: ParamRange,
+    pub size_multiplier: ParamRange,
+    // Random search
+    pub samples: usize,
+    pub seed: u64,
+    // Share of the recorded days (the most recent) kept for the validation
+    pub validation_share: f64,
+    // Candidates validated and shown in the report
+    pub top: usize,
+    // 0 = one per CPU
+    pub threads: usize,
+}
+
+impl Default for SweepConfig {
+    fn default() -> Self {
+        let params = StrategyParams::default();
+        Self {
+            method: SearchMethod::Grid,
+            step_size: ParamRange::fixed(params.step_size),
+            max_spread_price: ParamRange {
+                min: 1.0,
+                max: 10.0,
+                step: 1.0,
+            },
+            size_multiplier: ParamRange {
+                min: 1.0,
+                max: 10.0,
+                step: 1.0,
+            },
+            samples: 50,
+            seed: 1,
+            validation_share: 0.3,
+            top: 10,
+            threads: 0,
+        }
+    }
+}
+
+// Parameters evaluated by the search
+pub fn candidates(config: &SweepConfig) -> Vec<StrategyParams> {
+    match config.method {
+        SearchMethod::Grid => {
+            let mut candidates = Vec::new();
+            for step_size in config.step_size.values() {
+                for max_spread_price in config.max_spread_price.values() {
+                    for size_multiplier in config.size_multiplier.values() {
+                        candidates.push(StrategyParams {
+                            step_size,
+                            max_spread_price,
+                            size_multiplier,
+                        });
+                    }
+                }
+            }
+            candidates
+        }
+        SearchMethod::Random => {
+            let mut rng = StdRng::seed_from_u64(config.seed);
+            let mut candidates: Vec<StrategyParams> = Vec::new();
+            // duplicates are drawn again, a bounded number of times for small ranges
+            for _ in 0..config.samples * 10 {
+                if candidates.len() >= config.samples {
+                    break;
+                }
+                let params = StrategyParams {
+                    step_size: config.step_size.sample(&mut rng),
+                    max_spread_price: config.max_spread_price.sample(&mut rng),
+                    size_multiplier: config.size_multiplier.sample(&mut rng),
+                };
+                if !candidates.contains(&params) {
+                    candidates.push(params);
+                }
+            }
+            candidates
+        }
+    }
+}
+
+// Recorded data cut by market and day, the last days kept for the validation
+pub struct Dataset {
+    pub training_days: Vec<NaiveDate>,
+    pub validation_days: Vec<NaiveDate>,
+    // Records of one market during one day, replayed separately
+    pub training: Vec<Vec<Record>>,
+    pub validation: Vec<Vec<Record>>,
+}
+
+impl Dataset {
+    pub fn split(records: Vec<Record>, validation_share: f64) -> Result<Dataset, String> {
+        let mut replays: BTreeMap<(NaiveDate, String), Vec<Record>> = BTreeMap::new();
+        for record in records {
+            replays
+                .entry((record.received_at.date_naive(), record.market.clone()))
+                .or_default()
+                .push(record);
+        }
+        let mut days: Vec<NaiveDate> = replays.keys().map(|(day, _)| *day).collect();
+        days.dedup();
+        if days.len() < 2 {
+            return Err(format!(
+                "{} recorded day(s), at least 2 are needed for the out-of-sample validation",
+                days.len()
+            ));
+        }
+        let validation_count =
+            ((days.len() as f64 * validation_share).ceil() as usize).clamp(1, days.len() - 1);
+        let validation_days = days.split_off(days.len() - validation_count);
+
+        let mut dataset = Dataset {
+            training_days: days,
+            validation_days,
+            training: Vec::new(),
+            validation: Vec::new(),
+        };
+        for ((day, _), mut records) in replays {
+            records.sort_by_key(|record| record.received_at);
+            if dataset.validation_days.contains(&day) {
+                dataset.validation.push(records);
+            } else {
+                dataset.training.push(records);
+            }
+        }
+        Ok(dataset)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SweepResult {
+    pub params: StrategyParams,
+    pub training: BacktestStats,
+    // Best candidates only
+    pub validation: Option<BacktestStats>,
+}
+
+// Totals of the candidate over the replays
+pub fn evaluate(
+    params: StrategyParams,
+    rules: &QuoteRules,
+    backtest: &BacktestConfig,
+    replays: &[Vec<Record>],
+) -> BacktestStats {
+    let rules = QuoteRules { params, ..*rules };
+    let mut total = BacktestStats::default();
+    for records in replays {
+        total.merge(&backtest::run_backtest(records, &rules, backtest));
+    }
+    total
+}
+
+// Lowest cost per $1k first, candidates without volume last
+fn ranking_key(stats: &BacktestStats) -> f64 {
+    stats.cost_per_1k().unwrap_or(f64::INFINITY)
+}
+
+// Evaluate every candidate on the training days with `threads` workers
+fn evaluate_all(
+    candidates: &[StrategyParams],
+    rules: &QuoteRules,
+    backtest: &BacktestConfig,
+    replays: &[Vec<Record>],
+    threads: usize,
+) -> Vec<SweepResult> {
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(candidates.len()));
+    std::thread::scope(|scope| {
+        for _ in 0..threads.max(1) {
+            scope.spawn(|| {
+                loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(params) = candidates.get(index) else {
+                        break;
+                    };
+                    let training = evaluate(*params, rules, backtest, replays);
+                    results.lock().unwrap().push(SweepResult {
+                        params: *params,
+                        training,
+                        validation: None,
+                    });
+                }
+            });
+        }
+    });
+    results.into_inner().unwrap()
+}
+
+// Ranked results, the `top` first ones with their validation
+pub fn run_sweep(
+    config: &SweepConfig,
+    dataset: &Dataset,
+    rules: &QuoteRules,
+    backtest: &BacktestConfig,
+) -> Vec<SweepResult> {
+    let threads = match config.threads {
+        0 => std::thread::available_parallelism().map_or(1, |threads| threads.get()),
+        threads => threads,
+    };
+    let candidates = candidates(config);
+    let mut results = evaluate_all(&candidates, rules, backtest, &dataset.training, threads);
+    // ties by parameters: same ranking whatever the order the workers finished in
+    results.sort_by(|a, b| {
+        ranking_key(&a.training)
+            .total_cmp(&ranking_key(&b.training))
+            .then(b.training.volume().total_cmp(&a.training.volume()))
+            .then(a.params.step_size.total_cmp(&b.params.step_size))
+            .then(a.params.max_spread_price.total_cmp(&b.params.max_spread_price))
+            .then(a.params.size_multiplier.total_cmp(&b.params.size_multiplier))
+    });
+
+    let top = config.top.min(results.len());
+    let validated = evaluate_all(
+        &results[..top].iter().map(|result| result.params).collect::<Vec<_>>(),
+        rules,
+        backtest,
+        &dataset.validation,
+        threads,
+    );
+    for result in results.iter_mut().take(top) {
+        result.validation = validated
+            .iter()
+            .find(|validated| validated.params == result.params)
+            .map(|validated| validated.training.clone());
+    }
+    results
+}
+
+fn format_cost(stats: &BacktestStats) -> String {
+    stats.cost_per_1k().map_or("-".to_string(), |cost| format!("{:.2}", cost))
+}
+
+fn report_row(table: &mut String, rank: &str, result: &SweepResult) {
+    let _ = write!(
+        table,
+        "{:>4} {:>6} {:>7} {:>6} | {:>12.2} {:>9} {:>6} |",
+        rank,
+        result.params.step_size,
+        result.params.max_spread_price,
+        result.params.size_multiplier,
+        result.training.volume(),
+        format_cost(&result.training),
+        result.training.fills
+    );
+    let _ = match &result.validation {
+        Some(validation) => writeln!(
+            table,
+            " {:>12.2} {:>9} {:>6}",
+            validation.volume(),
+            format_cost(validation),
+            validation.fills
+        ),
+        None => writeln!(table, " {:>12} {:>9} {:>6}", "-", "-", "-"),
+    };
+}
+
+// Table of the `top` first results, after the parameters in use ("cur")
+pub fn report(results: &[SweepResult], top: usize, current: &SweepResult) -> String {
+    let mut table = String::new();
+    let _ = writeln!(
+        table,
+        "{:>4} {:>6} {:>7} {:>6} | {:>12} {:>9} {:>6} | {:>12} {:>9} {:>6}",
+        "rank", "step", "spread", "mult", "train vol", "cost/1k", "fills", "valid vol", "cost/1k", "fills"
+    );
+    report_row(&mut table, "cur", current);
+    for (rank, result) in results.iter().take(top).enumerate() {
+        report_row(&mut table, &(rank + 1).to_string(), result);
+    }
+    table
+}
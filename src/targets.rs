@@ -0,0 +1,103 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::accounting::Accounting;
+
+// Volume target and spending budget of a period: the bot stops quoting when one is hit
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetPeriod {
+    Day,
+    #[default]
+    Week,
+    Month,
+}
+
+impl TargetPeriod {
+    // Start of the current period (local midnight, weeks start on Monday)
+    pub fn start(&self, now: DateTime<Local>) -> DateTime<Utc> {
+        let today = now.date_naive();
+        let first_day = match self {
+            TargetPeriod::Day => today,
+            TargetPeriod::Week => {
+                today - Duration::days(today.weekday().num_days_from_monday() as i64)
+            }
+            TargetPeriod::Month => today.with_day(1).unwrap_or(today),
+        };
+        local_midnight(first_day, now.with_timezone(&Utc))
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TargetPeriod::Day => "day",
+            TargetPeriod::Week => "week",
+            TargetPeriod::Month => "month",
+        }
+    }
+}
+
+// `now` of the caller's clock when midnight does not exist locally (DST change at midnight)
+pub fn local_midnight(day: NaiveDate, now: DateTime<Utc>) -> DateTime<Utc> {
+    day.and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|midnight| midnight.with_timezone(&Utc))
+        .unwrap_or(now)
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TargetConfig {
+    pub period: TargetPeriod,
+    // Notional volume to reach during the period (no target if missing)
+    pub volume: Option<f64>,
+    // Maximum net cost of the period (no budget if missing)
+    pub max_cost: Option<f64>,
+}
+
+impl TargetConfig {
+    pub fn is_set(&self) -> bool {
+        self.volume.is_some() || self.max_cost.is_some()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    VolumeReached,
+    BudgetExhausted,
+}
+
+#[derive(Clone, Debug)]
+pub struct TargetProgress {
+    pub period: TargetPeriod,
+    pub period_start: DateTime<Utc>,
+    pub volume: f64,
+    pub target_volume: Option<f64>,
+    pub cost: f64,
+    pub max_cost: Option<f64>,
+}
+
+impl TargetProgress {
+    pub fn compute(config: &TargetConfig, accounting: &Accounting, now: DateTime<Local>) -> Self {
+        let period_start = config.period.start(now);
+        let totals = accounting.since(period_start);
+        Self {
+            period: config.period,
+            period_start,
+            volume: totals.volume,
+            target_volume: config.volume,
+            cost: totals.net_cost(),
+            max_cost: config.max_cost,
+        }
+    }
+
+    pub fn stop_reason(&self) -> Option<StopReason> {
+        if self.max_cost.is_some_and(|max_cost| self.cost >= max_cost) {
+            Some(StopReason::BudgetExhausted)
+        } else if self.target_volume.is_some_and(|target| self.volume >= target) {
+            Some(StopReason::VolumeReached)
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use backend::accounting::{Accounting, AccountingTotals};
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use paradex::structs::{Fill, FillLiquidity, FillType, Side};
+
+// FIFO pairing of the buy and sell fills into round trips, and the totals by market / day
+
+fn fill(id: &str, market: &str, side: Side, price: f64, size: f64, fee: f64, at: DateTime<Utc>) -> Fill {
+    Fill {
+        client_id: String::new(),
+        created_at: at.timestamp_millis() as u64,
+        fee,
+        fee_currency: "USDC".to_string(),
+        id: id.to_string(),
+        liquidity: match side {
+            Side::BUY => FillLiquidity::MAKER,
+            Side::SELL => FillLiquidity::TAKER,
+        },
+        market: market.to_string(),
+        order_id: format!("order-{}", id),
+        price,
+        side,
+        size,
+        remaining_size: 0.0,
+        fill_type: FillType::FILL,
+        realized_pnl: 0.0,
+    }
+}
+
+fn local(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Local
+        .with_ymd_and_hms(2026, 6, day, hour, minute, 0)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+}
+
+const BTC_CALL: &str = "BTC-USD-100000-C";
+const BTC_PUT: &str = "BTC-USD-90000-P";
+const ETH_CALL: &str = "ETH-USD-4000-C";
+
+#[test]
+fn sell_smaller_than_the_buy_pairs_part_of_it() {
+    let mut accounting = Accounting::new(local(15, 0, 0));
+    // maker rebate on the bid, taker fee on the liquidation
+    accounting.add_fill(&fill("1", BTC_CALL, Side::BUY, 10.0, 2.0, -0.02, local(15, 10, 0)));
+    accounting.add_fill(&fill("2", BTC_CALL, Side::SELL, 9.0, 1.5, 0.03, local(15, 10, 1)));
+
+    let round_trips = accounting.round_trips();
+    assert_eq!(round_trips.len(), 1);
+    let round_trip = &round_trips[0];
+    assert_close(round_trip.size, 1.5);
+    assert_close(round_trip.buy_fee, -0.015);
+    assert_close(round_trip.sell_fee, 0.03);
+    assert_close(round_trip.volume(), 28.5);
+    assert_close(round_trip.spread_loss(), 1.5);
+    assert_close(round_trip.net_cost(), 1.515);
+    assert_close(accounting.open_size(BTC_CALL), 0.5);
+
+    // the rest of the buy, then a sell of a position opened before the accounting
+    accounting.add_fill(&fill("3", BTC_CALL, Side::SELL, 8.0, 1.0, 0.02, local(15, 10, 2)));
+    assert_eq!(accounting.round_trips().len(), 2);
+    assert_close(accounting.round_trips()[1].size, 0.5);
+    assert_close(accounting.round_trips()[1].sell_fee, 0.01);
+    assert_close(accounting.open_size(BTC_CALL), 0.0);
+}
+
+#[test]
+fn sell_is_paired_with_the_oldest_buys_first() {
+    let mut accounting = Accounting::new(local(15, 0, 0));
+    accounting.add_fill(&fill("1", BTC_CALL, Side::BUY, 10.0, 1.0, 0.0, local(15, 10, 0)));
+    accounting.add_fill(&fill("2", BTC_CALL, Side::BUY, 12.0, 1.0, 0.0, local(15, 10, 1)));
+    accounting.add_fill(&fill("3", BTC_CALL, Side::SELL, 11.0, 1.5, 0.0, local(15, 10, 2)));
+
+    let pairs: Vec<(f64, f64)> = accounting
+        .round_trips()
+        .iter()
+        .map(|round_trip| (round_trip.buy_price, round_trip.size))
+        .collect();
+    assert_eq!(pairs, vec![(10.0, 1.0), (12.0, 0.5)]);
+    assert_eq!(accounting.round_trips()[0].opened_at, local(15, 10, 0));
+    assert_close(accounting.open_size(BTC_CALL), 0.5);
+    // spread loss -1 on the first pair, +0.5 on the second
+    assert_close(accounting.total().spread_loss, -0.5);
+}
+
+#[test]
+fn duplicate_fills_are_ignored() {
+    let mut accounting = Accounting::new(local(15, 0, 0));
+    let buy = fill("1", BTC_CALL, Side::BUY, 10.0, 1.0, 0.0, local(15, 10, 0));
+    accounting.add_fill(&buy);
+    accounting.add_fill(&buy);
+    assert_close(accounting.open_size(BTC_CALL), 1.0);
+}
+
+#[test]
+fn markets_are_paired_and_grouped_separately() {
+    let mut accounting = Accounting::new(local(15, 0, 0));
+    accounting.add_fill(&fill("1", BTC_CALL, Side::BUY, 10.0, 1.0, 0.0, local(15, 10, 0)));
+    accounting.add_fill(&fill("2", ETH_CALL, Side::BUY, 5.0, 2.0, 0.0, local(15, 10, 0)));
+    accounting.add_fill(&fill("3", BTC_PUT, Side::BUY, 20.0, 1.0, 0.0, local(15, 10, 0)));
+    // no buy of this market: not paired with the other markets
+    accounting.add_fill(&fill("4", "SOL-USD-200-C", Side::SELL, 1.0, 1.0, 0.0, local(15, 10, 1)));
+    accounting.add_fill(&fill("5", ETH_CALL, Side::SELL, 4.0, 2.0, 0.0, local(15, 10, 1)));
+    accounting.add_fill(&fill("6", BTC_CALL, Side::SELL, 9.0, 1.0, 0.0, local(15, 10, 2)));
+    accounting.add_fill(&fill("7", BTC_PUT, Side::SELL, 19.0, 1.0, 0.0, local(15, 10, 2)));
+
+    let by_market = accounting.by_market();
+    assert_eq!(by_market.keys().collect::<Vec<_>>(), vec![BTC_CALL, BTC_PUT, ETH_CALL]);
+    assert_close(by_market[ETH_CALL].volume, 18.0);
+    assert_close(by_market[ETH_CALL].spread_loss, 2.0);
+    assert_close(by_market[BTC_CALL].spread_loss, 1.0);
+
+    let by_underlying = accounting.by_underlying();
+    assert_eq!(by_underlying.len(), 2);
+    assert_eq!(by_underlying["BTC"].round_trips, 2);
+    assert_close(by_underlying["BTC"].volume, 19.0 + 39.0);
+    assert_eq!(accounting.total().round_trips, 3);
+}
+
+#[test]
+fn round_trips_belong_to_the_local_day_of_their_sell() {
+    let mut accounting = Accounting::new(local(16, 0, 0));
+    accounting.add_fill(&fill("1", BTC_CALL, Side::BUY, 10.0, 2.0, 0.0, local(15, 23, 58)));
+    accounting.add_fill(&fill("2", BTC_CALL, Side::SELL, 9.0, 1.0, 0.0, local(15, 23, 59)));
+    accounting.add_fill(&fill("3", BTC_CALL, Side::SELL, 8.0, 1.0, 0.0, local(16, 0, 1)));
+
+    let day_15 = NaiveDate::from_ymd_opt(2026, 6, 15).unwrap();
+    let day_16 = NaiveDate::from_ymd_opt(2026, 6, 16).unwrap();
+    let by_day = accounting.by_day();
+    assert_eq!(by_day.keys().copied().collect::<Vec<_>>(), vec![day_15, day_16]);
+    assert_close(accounting.day(day_15).spread_loss, 1.0);
+    assert_close(accounting.day(day_16).spread_loss, 2.0);
+    // the second round trip opened the day before
+    assert_eq!(accounting.round_trips()[1].opened_at, local(15, 23, 58));
+    // session started at midnight
+    assert_eq!(accounting.session().round_trips, 1);
+    assert_eq!(accounting.since(local(15, 23, 59)).round_trips, 2);
+}
+
+#[test]
+fn merged_totals_add_every_field() {
+    let mut first = Accounting::new(local(15, 0, 0));
+    first.add_fill(&fill("1", BTC_CALL, Side::BUY, 10.0, 1.0, -0.01, local(15, 10, 0)));
+    first.add_fill(&fill("2", BTC_CALL, Side::SELL, 9.0, 1.0, 0.02, local(15, 10, 1)));
+    let mut second = Accounting::new(local(15, 0, 0));
+    second.add_fill(&fill("1", ETH_CALL, Side::BUY, 5.0, 2.0, -0.01, local(15, 10, 0)));
+    second.add_fill(&fill("2", ETH_CALL, Side::SELL, 4.5, 2.0, 0.03, local(15, 10, 1)));
+
+    let mut merged = first.total();
+    merged.merge(&second.total());
+    assert_eq!(merged.round_trips, 2);
+    assert_close(merged.volume, 19.0 + 19.0);
+    assert_close(merged.fees, 0.01 + 0.02);
+    assert_close(merged.spread_loss, 1.0 + 1.0);
+    assert_close(merged.maker_volume, 10.0 + 10.0);
+    assert_close(merged.maker_fees, -0.02);
+    assert_close(merged.taker_volume, 9.0 + 9.0);
+    assert_close(merged.taker_fees, 0.05);
+    assert_close(merged.cost_per_1k().unwrap(), 2.03 / 38.0 * 1000.0);
+
+    let mut empty = AccountingTotals::default();
+    empty.merge(&AccountingTotals::default());
+    assert_eq!(empty.cost_per_1k(), None);
+}
@@ -0,0 +1,228 @@
+use backend::backtest::{self, BacktestConfig, BacktestStats};
+use backend::fees::FeeModel;
+use backend::recorder::{MarketData, Record};
+use backend::strategy::{QuoteRules, StrategyParams};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use paradex::structs::{BBO, Level, OrderBook, OrderBookUpdateType, Side, Trade, TradeType};
+
+// Fills simulated from hand-built recordings: queue of our level, crossing asks, liquidations
+
+const MARKET: &str = "BTC-USD-100000-C";
+
+fn at(seconds: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 6, 15, 10, 0, 0).unwrap() + Duration::seconds(seconds)
+}
+
+fn record(seconds: i64, data: MarketData) -> Record {
+    Record {
+        received_at: at(seconds),
+        market: MARKET.to_string(),
+        data,
+    }
+}
+
+// Bids (price, size) from the best one, then the ask
+fn snapshot(seconds: i64, bids: &[(f64, f64)], ask: f64) -> Record {
+    let mut inserts: Vec<Level> = bids
+        .iter()
+        .map(|&(price, size)| Level {
+            side: Side::BUY,
+            price,
+            size,
+        })
+        .collect();
+    inserts.push(Level {
+        side: Side::SELL,
+        price: ask,
+        size: 5.0,
+    });
+    let book = OrderBook {
+        seq_no: seconds as u64,
+        market: MARKET.to_string(),
+        last_updated_at: at(seconds).timestamp_millis() as u64,
+        update_type: OrderBookUpdateType::Snapshot,
+        deletes: Vec::new(),
+        inserts,
+        updates: Vec::new(),
+    };
+    record(seconds, MarketData::OrderBook(book))
+}
+
+fn trade(seconds: i64, side: Side, price: f64, size: f64) -> Record {
+    record(
+        seconds,
+        MarketData::Trade(Trade {
+            created_at: at(seconds).timestamp_millis() as u64,
+            id: seconds.to_string(),
+            market: MARKET.to_string(),
+            price,
+            side,
+            size,
+            trade_type: TradeType::FILL,
+        }),
+    )
+}
+
+fn bbo(seconds: i64, bid: f64, ask: f64) -> Record {
+    record(
+        seconds,
+        MarketData::Bbo(BBO {
+            bid,
+            bid_size: 1.0,
+            ask,
+            ask_size: 1.0,
+            market: MARKET.to_string(),
+            last_updated_at: at(seconds).timestamp_millis() as u64,
+        }),
+    )
+}
+
+const BOOK: &[(f64, f64)] = &[(10.0, 5.0), (9.9, 20.0), (9.8, 30.0)];
+
+fn run(records: &[Record]) -> BacktestStats {
+    let rules = QuoteRules {
+        params: StrategyParams::default(),
+        fee_model: FeeModel {
+            maker_rate: -0.0001,
+            taker_rate: 0.001,
+        },
+        max_cost_per_1k: None,
+    };
+    let config = BacktestConfig {
+        order_size: 1.0,
+        refresh_seconds: 10,
+        liquidation_delay_seconds: 10,
+    };
+    backtest::run_backtest(records, &rules, &config)
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+}
+
+#[test]
+fn sell_trades_fill_the_queue_ahead_first() {
+    let stats = run(&[
+        // bid placed at the first bid, behind 5
+        snapshot(0, BOOK, 11.0),
+        trade(1, Side::SELL, 10.0, 3.0),
+        // buys and sells above our price do not fill
+        trade(2, Side::BUY, 10.0, 10.0),
+        trade(2, Side::SELL, 10.1, 10.0),
+        // 2 ahead, 0.5 for us
+        trade(3, Side::SELL, 10.0, 2.5),
+        trade(4, Side::SELL, 10.0, 1.0),
+    ]);
+    assert_eq!(stats.orders_placed, 1);
+    assert_eq!(stats.trades, 5);
+    assert_eq!(stats.fills, 2);
+    assert_close(stats.filled_size, 1.0);
+    assert_close(stats.average_fill_wait().unwrap(), 3.5);
+    assert_close(stats.entry_volume, 10.0);
+    assert_close(stats.maker_fees, -0.001);
+    // not liquidated before the end: sold into the last book
+    assert_eq!(stats.liquidations, 0);
+    assert_close(stats.open_position, 1.0);
+    assert_close(stats.exit_volume, 10.0);
+}
+
+#[test]
+fn cancels_ahead_move_us_up_in_the_queue() {
+    let stats = run(&[
+        snapshot(0, BOOK, 11.0),
+        // 3 of the 5 ahead cancelled
+        snapshot(1, &[(10.0, 2.0), (9.9, 20.0), (9.8, 30.0)], 11.0),
+        // new bids at our price are behind us
+        snapshot(2, &[(10.0, 6.0), (9.9, 20.0), (9.8, 30.0)], 11.0),
+        trade(3, Side::SELL, 10.0, 2.5),
+    ]);
+    assert_eq!(stats.snapshots, 3);
+    assert_eq!(stats.fills, 1);
+    assert_close(stats.filled_size, 0.5);
+}
+
+#[test]
+fn trade_below_our_price_fills_the_whole_bid() {
+    let stats = run(&[snapshot(0, BOOK, 11.0), trade(1, Side::SELL, 9.9, 0.1)]);
+    assert_eq!(stats.fills, 1);
+    assert_close(stats.filled_size, 1.0);
+}
+
+#[test]
+fn crossing_ask_fills_the_whole_bid() {
+    let stats = run(&[snapshot(0, BOOK, 11.0), bbo(1, 10.0, 10.5), bbo(2, 10.0, 10.0)]);
+    assert_eq!(stats.fills, 1);
+    assert_close(stats.filled_size, 1.0);
+
+    let stats = run(&[snapshot(0, BOOK, 11.0), snapshot(1, BOOK, 9.95)]);
+    assert_eq!(stats.fills, 1);
+    assert_close(stats.filled_size, 1.0);
+}
+
+#[test]
+fn position_is_sold_into_the_bids_after_the_delay() {
+    let stats = run(&[
+        snapshot(0, BOOK, 11.0),
+        trade(1, Side::SELL, 9.9, 0.1),
+        // seen by the bot 10 s after the fill
+        snapshot(5, BOOK, 11.0),
+        snapshot(11, &[(9.9, 0.4), (9.8, 0.3), (9.7, 5.0)], 11.0),
+    ]);
+    assert_eq!(stats.liquidations, 1);
+    assert_eq!(stats.incomplete_liquidations, 0);
+    assert_close(stats.open_position, 0.0);
+    let exit = 0.4 * 9.9 + 0.3 * 9.8 + 0.3 * 9.7;
+    assert_close(stats.exit_volume, exit);
+    assert_close(stats.taker_fees, exit * 0.001);
+    assert_close(stats.spread_loss(), 10.0 - exit);
+    let cost = 10.0 - exit - 0.001 + exit * 0.001;
+    assert_close(stats.cost_per_1k().unwrap(), cost / (10.0 + exit) * 1000.0);
+    // next bid placed once the position is sold
+    assert_eq!(stats.orders_placed, 2);
+}
+
+#[test]
+fn thin_bids_leave_part_of_the_position() {
+    let stats = run(&[
+        snapshot(0, BOOK, 11.0),
+        trade(1, Side::SELL, 9.9, 0.1),
+        snapshot(11, &[(9.9, 0.4)], 11.0),
+        snapshot(12, &[(9.9, 0.4)], 11.0),
+    ]);
+    assert_eq!(stats.liquidations, 2);
+    assert_eq!(stats.incomplete_liquidations, 2);
+    // 0.2 left, valued into the last book
+    assert_close(stats.open_position, 0.2);
+    assert_close(stats.exit_volume, 0.8 * 9.9 + 0.2 * 9.9);
+    assert_eq!(stats.orders_placed, 1);
+}
+
+#[test]
+fn partial_fill_liquidation_cancels_the_rest_of_the_bid() {
+    let stats = run(&[
+        snapshot(0, BOOK, 11.0),
+        trade(1, Side::SELL, 10.0, 5.4),
+        snapshot(11, BOOK, 11.0),
+    ]);
+    assert_close(stats.filled_size, 0.4);
+    assert_eq!(stats.cancels_liquidation, 1);
+    assert_eq!(stats.liquidations, 1);
+    assert_close(stats.exit_volume, 0.4 * 10.0);
+}
+
+#[test]
+fn cycles_see_our_bid_in_the_book() {
+    let stats = run(&[
+        snapshot(0, BOOK, 11.0),
+        // first bid shared with 5: go first bid alone at 10.1
+        snapshot(10, BOOK, 11.0),
+        trade(11, Side::SELL, 10.1, 0.3),
+        // first bid alone one step above the second bid: kept, the recording does not hold our bid
+        snapshot(20, BOOK, 11.0),
+    ]);
+    assert_eq!(stats.cycles, 3);
+    assert_eq!(stats.reprices, 1);
+    // nobody ahead at the new price
+    assert_eq!(stats.fills, 1);
+    assert_close(stats.entry_volume, 0.3 * 10.1);
+}
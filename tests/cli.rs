@@ -0,0 +1,24 @@
+use std::process::Command;
+
+// Command line of the cli binary, before any request to the exchange
+
+#[test]
+fn run_refuses_an_unknown_account() {
+    let directory = std::env::temp_dir().join(format!("bot_paradex_cli_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let config = "[exchange]\nenvironment = \"testnet\"\n\n[logging]\nfile_enabled = false\n\n\
+                  [[accounts]]\nname = \"main\"\n\n[[accounts]]\nname = \"alt\"\n";
+    std::fs::write(directory.join("bot_paradex.toml"), config).unwrap();
+
+    // without the filter, run started every account
+    let output = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .current_dir(&directory)
+        .args(["--account", "other", "run"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Unknown account other"), "{}", stderr);
+    std::fs::remove_dir_all(&directory).unwrap();
+}
@@ -0,0 +1,64 @@
+use backend::clock::{Clock, ManualClock};
+use chrono::{TimeDelta, TimeZone, Utc};
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+// Sleeps on the manual clock
+
+// Poll the future once, true if it completed
+async fn poll_once(future: &mut Pin<&mut impl Future<Output = ()>>) -> bool {
+    tokio::select! {
+        biased;
+        _ = future.as_mut() => true,
+        _ = tokio::task::yield_now() => false,
+    }
+}
+
+#[tokio::test]
+async fn sleep_ends_at_its_deadline() {
+    let manual = ManualClock::new(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap());
+    let clock = Clock::Manual(manual.clone());
+    let sleep = clock.sleep(Duration::from_secs(10));
+    tokio::pin!(sleep);
+
+    assert!(!poll_once(&mut sleep).await);
+    assert_eq!(manual.pending_sleeps(), 1);
+    manual.advance(Duration::from_secs(9));
+    assert!(!poll_once(&mut sleep).await);
+    manual.advance(Duration::from_secs(1));
+    assert!(poll_once(&mut sleep).await);
+    assert_eq!(manual.pending_sleeps(), 0);
+}
+
+#[tokio::test]
+async fn dropped_sleep_is_no_longer_pending() {
+    let manual = ManualClock::new(Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap());
+    let clock = Clock::Manual(manual.clone());
+    {
+        let sleep = clock.sleep(Duration::from_secs(10));
+        tokio::pin!(sleep);
+        assert!(!poll_once(&mut sleep).await);
+        assert_eq!(manual.pending_sleeps(), 1);
+    }
+    assert_eq!(manual.pending_sleeps(), 0);
+}
+
+#[tokio::test]
+async fn advance_to_next_sleep_stops_at_the_first_deadline() {
+    let start = Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap();
+    let manual = ManualClock::new(start);
+    let clock = Clock::Manual(manual.clone());
+    let long = clock.sleep(Duration::from_secs(30));
+    let short = clock.sleep(Duration::from_secs(10));
+    tokio::pin!(long, short);
+    assert!(!poll_once(&mut long).await);
+    assert!(!poll_once(&mut short).await);
+
+    assert_eq!(manual.advance_to_next_sleep().await, start + TimeDelta::seconds(10));
+    assert!(poll_once(&mut short).await);
+    assert!(!poll_once(&mut long).await);
+    assert_eq!(manual.advance_to_next_sleep().await, start + TimeDelta::seconds(30));
+    assert!(poll_once(&mut long).await);
+    assert_eq!(clock.now(), start + TimeDelta::seconds(30));
+}
@@ -0,0 +1,191 @@
+use backend::config::{BotConfig, DEFAULT_ACCOUNT, Environment};
+use backend::targets::TargetPeriod;
+use std::path::PathBuf;
+
+// Accounts resolved from the configuration file and the checks of `BotConfig::problems`
+
+fn parse(toml: &str) -> BotConfig {
+    toml::from_str(toml).unwrap()
+}
+
+#[test]
+fn default_account_uses_the_global_sections() {
+    let config = parse(
+        r#"
+        [journal]
+        path = "journal/bot.jsonl"
+        [target]
+        volume = 1000.0
+        "#,
+    );
+    let accounts = config.accounts();
+    assert_eq!(accounts.len(), 1);
+    let account = &accounts[0];
+    assert_eq!(account.name, DEFAULT_ACCOUNT);
+    assert_eq!(account.key_var, Environment::Production.key_var());
+    // no suffix for the single account
+    assert_eq!(account.journal.path, PathBuf::from("journal/bot.jsonl"));
+    assert_eq!(account.target.volume, Some(1000.0));
+    assert!(config.problems().is_empty(), "{:?}", config.problems());
+}
+
+#[test]
+fn accounts_override_the_global_sections() {
+    let config = parse(
+        r#"
+        [exchange]
+        environment = "testnet"
+        [target]
+        period = "week"
+        max_cost = 100.0
+        [fees]
+        taker_rate = 0.0005
+
+        [[accounts]]
+        name = "main"
+        key_var = "KEY_MAIN"
+
+        [[accounts]]
+        name = "alt"
+        [accounts.target]
+        period = "day"
+        max_cost = 20.0
+        [accounts.fees]
+        max_cost_per_1k = 1.2
+        [accounts.journal]
+        path = "alt/journal.jsonl"
+        "#,
+    );
+    let accounts = config.accounts();
+    let names: Vec<&str> = accounts.iter().map(|account| account.name.as_str()).collect();
+    assert_eq!(names, vec!["main", "alt"]);
+
+    let (main, alt) = (&accounts[0], &accounts[1]);
+    assert_eq!(main.key_var, "KEY_MAIN");
+    assert_eq!(main.target.period, TargetPeriod::Week);
+    assert_eq!(main.target.max_cost, Some(100.0));
+    assert_eq!(main.fees.taker_rate, 0.0005);
+
+    // environment variable of the testnet by default
+    assert_eq!(alt.key_var, Environment::Testnet.key_var());
+    assert_eq!(alt.target.period, TargetPeriod::Day);
+    assert_eq!(alt.target.max_cost, Some(20.0));
+    // a section replaces the global one as a whole
+    assert_eq!(alt.fees.max_cost_per_1k, Some(1.2));
+    assert_eq!(alt.fees.taker_rate, 0.0003);
+    assert_eq!(alt.journal.path, PathBuf::from("alt/journal.jsonl"));
+    assert!(config.problems().is_empty(), "{:?}", config.problems());
+}
+
+#[test]
+fn account_journals_are_suffixed_with_the_account_name() {
+    let config = parse(
+        r#"
+        [journal]
+        enabled = false
+        path = "journal/trades.jsonl"
+        [[accounts]]
+        name = "main"
+        [[accounts]]
+        name = "alt"
+        "#,
+    );
+    let accounts = config.accounts();
+    assert_eq!(accounts[0].journal.path, PathBuf::from("journal/trades_main.jsonl"));
+    assert_eq!(accounts[1].journal.path, PathBuf::from("journal/trades_alt.jsonl"));
+    // the global switch applies to the default journals
+    assert!(accounts.iter().all(|account| !account.journal.enabled));
+
+    let config = parse(
+        r#"
+        [journal]
+        path = "trades"
+        [[accounts]]
+        name = "main"
+        "#,
+    );
+    assert_eq!(config.accounts()[0].journal.path, PathBuf::from("trades_main"));
+}
+
+#[test]
+fn keep_account_leaves_the_other_accounts_out() {
+    let mut config = parse(
+        r#"
+        [[accounts]]
+        name = "main"
+        [[accounts]]
+        name = "alt"
+        "#,
+    );
+    assert!(config.keep_account("other").is_err());
+    assert_eq!(config.accounts().len(), 2);
+    config.keep_account("alt").unwrap();
+    let accounts = config.accounts();
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].name, "alt");
+    assert_eq!(accounts[0].journal.path, PathBuf::from("journal/trades_alt.jsonl"));
+
+    let mut config = parse("");
+    config.keep_account(DEFAULT_ACCOUNT).unwrap();
+    assert_eq!(config.accounts()[0].name, DEFAULT_ACCOUNT);
+    assert!(config.keep_account("main").is_err());
+}
+
+#[test]
+fn duplicate_account_names_are_reported() {
+    let config = parse(
+        r#"
+        [[accounts]]
+        name = "main"
+        [[accounts]]
+        name = "main"
+        [[accounts]]
+        name = " "
+        "#,
+    );
+    let problems = config.problems();
+    assert!(problems.contains(&"account main is defined twice".to_string()), "{:?}", problems);
+    assert!(problems.contains(&"accounts.name is empty".to_string()), "{:?}", problems);
+}
+
+#[test]
+fn shared_journals_are_reported() {
+    let config = parse(
+        r#"
+        [[accounts]]
+        name = "main"
+        [accounts.journal]
+        path = "journal/shared.jsonl"
+        [[accounts]]
+        name = "alt"
+        [accounts.journal]
+        path = "journal/shared.jsonl"
+        "#,
+    );
+    assert_eq!(
+        config.problems(),
+        vec!["journal journal/shared.jsonl is shared by several accounts".to_string()]
+    );
+
+    // disabled journals are not written
+    let config = parse(
+        r#"
+        [[accounts]]
+        name = "main"
+        [accounts.journal]
+        enabled = false
+        path = "journal/shared.jsonl"
+        [[accounts]]
+        name = "alt"
+        [accounts.journal]
+        path = "journal/shared.jsonl"
+        "#,
+    );
+    assert!(config.problems().is_empty(), "{:?}", config.problems());
+}
+
+#[test]
+fn unknown_settings_are_rejected() {
+    assert!(toml::from_str::<BotConfig>("[[accounts]]\nname = \"main\"\nkey = \"secret\"\n").is_err());
+    assert!(toml::from_str::<BotConfig>("[strategy]\nstep = 0.1\n").is_err());
+}
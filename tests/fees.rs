@@ -0,0 +1,123 @@
+use backend::accounting::AccountingTotals;
+use backend::fees::{self, FeeModel};
+use backend::strategy;
+use paradex::structs::{Level, Side};
+use rust_decimal::Decimal;
+
+// Round trip estimate of a bid sold at market into the bids, and the fee rates seen on the fills
+
+fn bid(price: f64, size: f64) -> Level {
+    Level {
+        side: Side::BUY,
+        price,
+        size,
+    }
+}
+
+fn decimal(value: &str) -> Decimal {
+    value.parse().unwrap()
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "{} != {}", actual, expected);
+}
+
+const FEES: FeeModel = FeeModel {
+    maker_rate: -0.0001,
+    taker_rate: 0.001,
+};
+
+#[test]
+fn exit_sells_down_the_levels() {
+    let levels = [bid(9.8, 10.0), bid(10.0, 1.0), bid(9.9, 1.0)];
+    let bids: Vec<&Level> = levels.iter().collect();
+    let estimate = fees::estimate_exit(&bids, None, decimal("10.1"), decimal("3"), &FEES).unwrap();
+
+    // 1 at 10, 1 at 9.9, 1 at 9.8
+    assert_close(estimate.exit_price, 9.9);
+    assert_close(estimate.volume, 30.3 + 29.7);
+    assert_close(estimate.spread_loss, 0.6);
+    assert_close(estimate.maker_fee, -0.00303);
+    assert_close(estimate.taker_fee, 0.0297);
+    assert_close(estimate.cost(), 0.6 - 0.00303 + 0.0297);
+    assert_close(estimate.cost_per_1k().unwrap(), estimate.cost() / 60.0 * 1000.0);
+}
+
+#[test]
+fn own_size_is_not_exit_liquidity() {
+    // our bid of 1 shares the first level with 2
+    let levels = [bid(10.0, 3.0), bid(9.0, 5.0)];
+    let bids: Vec<&Level> = levels.iter().collect();
+    let estimate = fees::estimate_exit(&bids, Some(decimal("10")), decimal("10"), decimal("1"), &FEES).unwrap();
+    assert_close(estimate.exit_price, 10.0);
+    assert_close(estimate.spread_loss, 0.0);
+}
+
+#[test]
+fn own_size_is_removed_at_its_book_price_after_a_reprice() {
+    // our bid of 1 alone at 10, moved to 10.1: the level at 10 is ours and gone once we are filled
+    let levels = [bid(10.0, 1.0), bid(9.9, 5.0)];
+    let bids: Vec<&Level> = levels.iter().collect();
+    let estimate = fees::estimate_exit(&bids, Some(decimal("10")), decimal("10.1"), decimal("1"), &FEES).unwrap();
+    assert_close(estimate.exit_price, 9.9);
+    assert_close(estimate.spread_loss, 0.2);
+}
+
+#[test]
+fn no_exit_when_the_bids_are_too_thin() {
+    let levels = [bid(10.0, 3.0), bid(9.9, 1.0)];
+    let bids: Vec<&Level> = levels.iter().collect();
+    // 3 at 10 are ours
+    assert_eq!(fees::estimate_exit(&bids, Some(decimal("10")), decimal("10"), decimal("3"), &FEES), None);
+    assert_eq!(fees::estimate_exit(&bids, None, decimal("10"), decimal("5"), &FEES), None);
+    assert_eq!(fees::estimate_exit(&bids, None, decimal("10"), decimal("0"), &FEES), None);
+}
+
+#[test]
+fn missing_exit_is_too_expensive() {
+    let levels = [bid(10.0, 1.0), bid(9.9, 1.0)];
+    let bids: Vec<&Level> = levels.iter().collect();
+    let estimate = fees::estimate_exit(&bids, None, decimal("10"), decimal("1"), &FEES);
+    // cost: -0.001 maker rebate + 0.01 taker fee on $20 = $0.45 per $1k
+    assert!(!strategy::is_too_expensive(estimate.as_ref(), 0.5));
+    assert!(strategy::is_too_expensive(estimate.as_ref(), 0.4));
+    assert!(strategy::is_too_expensive(None, 0.5));
+}
+
+#[test]
+fn calibration_uses_the_rates_of_the_fills() {
+    let mut model = FeeModel {
+        maker_rate: 0.0,
+        taker_rate: 0.0003,
+    };
+    let totals = AccountingTotals {
+        maker_volume: 1000.0,
+        maker_fees: -0.2,
+        taker_volume: 500.0,
+        taker_fees: 0.25,
+        ..AccountingTotals::default()
+    };
+    model.calibrate(&totals);
+    assert_close(model.maker_rate, -0.0002);
+    assert_close(model.taker_rate, 0.0005);
+    assert_close(model.maker_fee(100.0), -0.02);
+    assert_close(model.taker_fee(100.0), 0.05);
+}
+
+#[test]
+fn calibration_keeps_the_rates_without_fills_of_the_kind() {
+    let mut model = FeeModel {
+        maker_rate: 0.0,
+        taker_rate: 0.0003,
+    };
+    model.calibrate(&AccountingTotals {
+        maker_volume: 1000.0,
+        maker_fees: 0.1,
+        ..AccountingTotals::default()
+    });
+    assert_close(model.maker_rate, 0.0001);
+    assert_close(model.taker_rate, 0.0003);
+
+    model.calibrate(&AccountingTotals::default());
+    assert_close(model.maker_rate, 0.0001);
+}
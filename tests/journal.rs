@@ -0,0 +1,108 @@
+use backend::clock::{Clock, ManualClock};
+use backend::journal::{Journal, JournalAction, JournalEntry, ReasonCode};
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use paradex::structs::{Fill, FillLiquidity, FillType, Side};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Fill ids loaded at the opening of a journal: only the end of the file inside the fills window
+
+const MARKET: &str = "BTC-USD-100000-C";
+
+fn fill(id: &str, at: DateTime<Utc>) -> Fill {
+    Fill {
+        client_id: String::new(),
+        created_at: at.timestamp_millis() as u64,
+        fee: 0.01,
+        fee_currency: "USDC".to_string(),
+        id: id.to_string(),
+        liquidity: FillLiquidity::MAKER,
+        market: MARKET.to_string(),
+        order_id: format!("order-{}", id),
+        price: 10.0,
+        side: Side::BUY,
+        size: 1.0,
+        remaining_size: 0.0,
+        fill_type: FillType::FILL,
+        realized_pnl: 0.0,
+    }
+}
+
+// Fresh journal path per test, removed first in case of a previous failed run
+fn journal_path(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("bot_paradex_journal_{}_{}.jsonl", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+fn fill_count(path: &Path, id: &str) -> usize {
+    Journal::read(path)
+        .unwrap()
+        .iter()
+        .filter(|entry| entry.fill_id.as_deref() == Some(id))
+        .count()
+}
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 3, 2, 0, 0, 0).unwrap()
+}
+
+#[test]
+fn fills_of_the_window_are_not_journaled_again() {
+    let path = journal_path("window");
+    let journal = Journal::open(&path, start()).unwrap();
+    journal.record_fill(&fill("old", start() - TimeDelta::hours(2)));
+    journal.record_fill(&fill("new", start() + TimeDelta::hours(1)));
+    drop(journal);
+
+    let journal = Journal::open(&path, start()).unwrap();
+    journal.record_fill(&fill("new", start() + TimeDelta::hours(1)));
+    // before the window: not queried again, so not loaded
+    journal.record_fill(&fill("old", start() - TimeDelta::hours(2)));
+    drop(journal);
+    assert_eq!(fill_count(&path, "new"), 1);
+    assert_eq!(fill_count(&path, "old"), 2);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn reading_stops_at_an_entry_written_before_the_window() {
+    let path = journal_path("stop");
+    let clock = ManualClock::new(start() - TimeDelta::days(1));
+    let journal = Journal::open(&path, start()).unwrap().with_clock(Clock::Manual(clock.clone()));
+    // cannot happen (a fill journaled before its creation): shows that the lines above are not read
+    journal.record_fill(&fill("above", start() + TimeDelta::hours(1)));
+    journal.record(&JournalEntry::new(journal.clock(), JournalAction::Modify, ReasonCode::Reprice, MARKET));
+    clock.advance(Duration::from_secs(26 * 3600));
+    journal.record_fill(&fill("below", start() + TimeDelta::hours(1)));
+    drop(journal);
+
+    let journal = Journal::open(&path, start()).unwrap();
+    journal.record_fill(&fill("below", start() + TimeDelta::hours(1)));
+    journal.record_fill(&fill("above", start() + TimeDelta::hours(1)));
+    drop(journal);
+    assert_eq!(fill_count(&path, "below"), 1);
+    assert_eq!(fill_count(&path, "above"), 2);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn lines_across_read_chunks_are_loaded() {
+    let path = journal_path("chunks");
+    let journal = Journal::open(&path, start()).unwrap();
+    // several chunks of the backward read, a truncated last line (crash)
+    for index in 0..500 {
+        journal.record_fill(&fill(&format!("fill-{}", index), start() + TimeDelta::seconds(index)));
+    }
+    drop(journal);
+    assert!(std::fs::metadata(&path).unwrap().len() > 3 * 64 * 1024);
+    std::fs::write(&path, std::fs::read_to_string(&path).unwrap() + "{\"timestamp\":").unwrap();
+
+    let journal = Journal::open(&path, start()).unwrap();
+    for index in 0..500 {
+        journal.record_fill(&fill(&format!("fill-{}", index), start() + TimeDelta::seconds(index)));
+    }
+    drop(journal);
+    assert_eq!(Journal::read(&path).unwrap().len(), 500);
+    let _ = std::fs::remove_file(&path);
+}
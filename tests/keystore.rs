@@ -0,0 +1,170 @@
+use backend::keystore::{self, Keystore, KeystoreConfig};
+use serde_json::Value;
+use std::path::PathBuf;
+
+// Encryption of the L2 keys: round trip, wrong password and altered files rejected
+
+const PASSWORD: &str = "correct horse";
+
+// Fresh directory per test, removed first in case of a previous failed run
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("bot_paradex_keystore_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    directory
+}
+
+fn saved_keystore(name: &str) -> PathBuf {
+    let path = directory(name).join("keystore.json");
+    let mut keystore = Keystore::load(&path).unwrap();
+    keystore.insert("main", "0x1234", PASSWORD).unwrap();
+    keystore.insert("alt", "0xabcd", PASSWORD).unwrap();
+    keystore.save().unwrap();
+    path
+}
+
+// Rewrite the saved JSON through `alter`
+fn alter(path: &PathBuf, alter: impl FnOnce(&mut Value)) {
+    let mut json: Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+    alter(&mut json);
+    std::fs::write(path, serde_json::to_string(&json).unwrap()).unwrap();
+}
+
+fn flip_hex(value: &mut Value) {
+    let mut text = value.as_str().unwrap().to_string();
+    let last = if text.ends_with('0') { "1" } else { "0" };
+    text.replace_range(text.len() - 1.., last);
+    *value = Value::String(text);
+}
+
+#[test]
+fn inserted_keys_are_unlocked_after_a_reload() {
+    let path = saved_keystore("round_trip");
+    let keystore = Keystore::load(&path).unwrap();
+    let names: Vec<&str> = keystore.entries().iter().map(|entry| entry.name.as_str()).collect();
+    assert_eq!(names, vec!["alt", "main"]);
+
+    let keys = keystore.unlock(PASSWORD).unwrap();
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys["main"], "0x1234");
+    assert_eq!(keys["alt"], "0xabcd");
+    // the key is not stored in clear
+    assert!(!std::fs::read_to_string(&path).unwrap().contains("abcd"));
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
+
+#[test]
+fn replaced_key_keeps_a_single_entry() {
+    let path = saved_keystore("replace");
+    let mut keystore = Keystore::load(&path).unwrap();
+    keystore.insert("main", "0x5678", PASSWORD).unwrap();
+    assert_eq!(keystore.entries().len(), 2);
+    assert_eq!(keystore.unlock(PASSWORD).unwrap()["main"], "0x5678");
+}
+
+#[test]
+fn wrong_password_is_rejected() {
+    let path = saved_keystore("wrong_password");
+    let mut keystore = Keystore::load(&path).unwrap();
+    assert!(keystore.unlock("wrong").is_err());
+    // a key cannot be added with another password
+    assert!(keystore.insert("other", "0x99", "wrong").is_err());
+    assert_eq!(keystore.entries().len(), 2);
+}
+
+#[test]
+fn altered_entries_are_rejected() {
+    for field in ["ciphertext", "nonce"] {
+        let path = saved_keystore(&format!("altered_{}", field));
+        alter(&path, |json| flip_hex(&mut json["keys"][0][field]));
+        let keystore = Keystore::load(&path).unwrap();
+        assert!(keystore.unlock(PASSWORD).is_err(), "altered {} accepted", field);
+    }
+
+    // key of an account moved to another one
+    let path = saved_keystore("altered_name");
+    alter(&path, |json| json["keys"][0]["name"] = Value::String("other".to_string()));
+    assert!(Keystore::load(&path).unwrap().unlock(PASSWORD).is_err());
+}
+
+#[test]
+fn altered_kdf_parameters_are_rejected() {
+    let alterations = [
+        ("log_n", Value::from(14)),
+        ("r", Value::from(4)),
+        ("salt", Value::from("00112233445566778899aabbccddeeff")),
+    ];
+    for (field, value) in alterations {
+        let path = saved_keystore(&format!("kdf_{}", field));
+        alter(&path, |json| json["kdf"][field] = value);
+        let keystore = Keystore::load(&path).unwrap();
+        assert!(keystore.unlock(PASSWORD).is_err(), "altered kdf {} accepted", field);
+    }
+}
+
+#[test]
+fn removed_key_is_gone_after_a_reload() {
+    let path = saved_keystore("remove");
+    let mut keystore = Keystore::load(&path).unwrap();
+    assert!(keystore.remove("alt"));
+    assert!(!keystore.remove("alt"));
+    keystore.save().unwrap();
+
+    let keystore = Keystore::load(&path).unwrap();
+    let keys = keystore.unlock(PASSWORD).unwrap();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys["main"], "0x1234");
+}
+
+#[test]
+fn unknown_version_is_rejected() {
+    let path = saved_keystore("version");
+    alter(&path, |json| json["version"] = Value::from(3));
+    let err = Keystore::load(&path).unwrap_err();
+    assert!(err.contains("Unsupported keystore version 3"), "{}", err);
+    // CTR + HMAC keystores of version 1
+    alter(&path, |json| json["version"] = Value::from(1));
+    let err = Keystore::load(&path).unwrap_err();
+    assert!(err.contains("import the keys again"), "{}", err);
+}
+
+#[test]
+fn locked_keystore_needs_a_password() {
+    let path = saved_keystore("locked");
+    let config = KeystoreConfig {
+        path,
+        ..KeystoreConfig::default()
+    };
+    assert!(keystore::is_locked(&config).unwrap());
+    assert!(keystore::unlock_keys(&config, None).is_err());
+    assert!(keystore::unlock_keys(&config, Some(PASSWORD)).is_ok());
+
+    // nothing to unlock without keys nor env fallback
+    let config = KeystoreConfig {
+        path: directory("empty").join("keystore.json"),
+        ..KeystoreConfig::default()
+    };
+    assert!(!keystore::is_locked(&config).unwrap());
+    assert!(keystore::unlock_keys(&config, None).is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn password_file_must_be_private() {
+    use std::os::unix::fs::PermissionsExt;
+    let directory = directory("password_file");
+    std::fs::create_dir_all(&directory).unwrap();
+    let path = directory.join("keystore.pass");
+    std::fs::write(&path, format!("{}\n", PASSWORD)).unwrap();
+
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+    assert!(keystore::read_password_file(&path).is_err());
+
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+    assert_eq!(keystore::read_password_file(&path).unwrap().as_str(), PASSWORD);
+}
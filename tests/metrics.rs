@@ -0,0 +1,139 @@
+use backend::Logger;
+use backend::config::BotConfig;
+use backend::metrics::{self, EXCHANGE_ERRORS, FILLS, LOOP_DURATION, MetricsConfig, ORDERS_CANCELLED};
+use crossbeam::channel::unbounded;
+use http::StatusCode;
+use paradex::error::Error;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+// Prometheus text of the registry, kinds of the failed requests and the HTTP endpoint.
+// The registry is shared by the tests of the file: each test uses its own account label.
+
+#[test]
+fn every_metric_is_listed_from_the_start() {
+    metrics::register_account("registered");
+    let text = metrics::render();
+    assert!(text.contains("# HELP bot_paradex_fills_total Option fills received\n"), "{}", text);
+    assert!(text.contains("# TYPE bot_paradex_fills_total counter\n"), "{}", text);
+    assert!(text.contains("# TYPE bot_paradex_loop_duration_seconds gauge\n"), "{}", text);
+    // family without series until the first error
+    assert!(text.contains("# TYPE bot_paradex_exchange_errors_total counter\n"), "{}", text);
+    assert!(text.contains("bot_paradex_fills_total{account=\"registered\"} 0\n"), "{}", text);
+    assert!(text.contains("bot_paradex_orders_cancelled_total{account=\"registered\",scope=\"market\"} 0\n"));
+
+    // registered again by another worker: values kept
+    FILLS.add(&[("account", "registered")], 2.0);
+    metrics::register_account("registered");
+    assert_eq!(FILLS.value(&[("account", "registered")]), 2.0);
+}
+
+#[test]
+fn series_are_rendered_with_their_labels() {
+    let labels = [("account", "labels"), ("scope", "order")];
+    ORDERS_CANCELLED.inc(&labels);
+    ORDERS_CANCELLED.inc(&labels);
+    assert_eq!(ORDERS_CANCELLED.value(&labels), 2.0);
+    EXCHANGE_ERRORS.inc(&[("account", "labels"), ("request", "positions"), ("kind", "server")]);
+
+    let text = metrics::render();
+    assert!(text.contains("bot_paradex_orders_cancelled_total{account=\"labels\",scope=\"order\"} 2\n"));
+    assert!(text.contains(
+        "bot_paradex_exchange_errors_total{account=\"labels\",request=\"positions\",kind=\"server\"} 1\n"
+    ));
+}
+
+#[test]
+fn label_values_are_escaped() {
+    FILLS.inc(&[("account", "a\"b\\c\nd")]);
+    let text = metrics::render();
+    assert!(text.contains("bot_paradex_fills_total{account=\"a\\\"b\\\\c\\nd\"} 1\n"), "{}", text);
+}
+
+#[test]
+fn special_values_use_the_prometheus_spelling() {
+    LOOP_DURATION.set(&[("account", "infinite")], f64::INFINITY);
+    LOOP_DURATION.set(&[("account", "negative")], f64::NEG_INFINITY);
+    LOOP_DURATION.set(&[("account", "nan")], f64::NAN);
+    LOOP_DURATION.set(&[("account", "decimal")], 0.25);
+    let text = metrics::render();
+    assert!(text.contains("bot_paradex_loop_duration_seconds{account=\"infinite\"} +Inf\n"));
+    assert!(text.contains("bot_paradex_loop_duration_seconds{account=\"negative\"} -Inf\n"));
+    assert!(text.contains("bot_paradex_loop_duration_seconds{account=\"nan\"} NaN\n"));
+    assert!(text.contains("bot_paradex_loop_duration_seconds{account=\"decimal\"} 0.25\n"));
+}
+
+#[test]
+fn errors_are_grouped_by_kind() {
+    let http = |status: u16| Error::HTTPError {
+        status_code: StatusCode::from_u16(status).unwrap(),
+    };
+    assert_eq!(metrics::error_kind(&http(429)), "rate_limited");
+    assert_eq!(metrics::error_kind(&http(503)), "server");
+    assert_eq!(metrics::error_kind(&http(400)), "rejected");
+    let paradex = Error::ParadexError {
+        status_code: StatusCode::INTERNAL_SERVER_ERROR,
+        error: None,
+        message: String::new(),
+    };
+    assert_eq!(metrics::error_kind(&paradex), "server");
+    assert_eq!(metrics::error_kind(&Error::RestError("timeout".to_string())), "network");
+    assert_eq!(metrics::error_kind(&Error::RestEmptyResponse), "network");
+    assert_eq!(metrics::error_kind(&Error::DeserializationError(String::new())), "parse");
+    assert_eq!(metrics::error_kind(&Error::MissingPrivateKey), "other");
+}
+
+// Response of the endpoint to one raw request
+fn request(raw: &str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let server = std::thread::spawn(move || metrics::answer(listener.accept().unwrap().0));
+    let mut stream = TcpStream::connect(address).unwrap();
+    stream.write_all(raw.as_bytes()).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    server.join().unwrap().unwrap();
+    response
+}
+
+#[test]
+fn endpoint_answers_get_metrics_only() {
+    metrics::register_account("endpoint");
+    let response = request("GET /metrics HTTP/1.1\r\nHost: localhost\r\nAccept: */*\r\n\r\n");
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+    assert!(response.contains("Content-Type: text/plain; version=0.0.4"));
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    assert!(head.contains(&format!("Content-Length: {}\r\n", body.len())), "{}", head);
+    assert!(body.contains("bot_paradex_loops_total{account=\"endpoint\"} 0\n"));
+
+    // query string ignored
+    assert!(request("GET /metrics?x=1 HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(request("GET / HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    assert!(request("POST /metrics HTTP/1.1\r\n\r\n").starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+}
+
+#[test]
+fn only_loopback_addresses_are_accepted() {
+    assert!(metrics::parse_address("127.0.0.1:9898").is_ok());
+    assert!(metrics::parse_address("[::1]:9898").is_ok());
+    assert!(metrics::parse_address("0.0.0.0:9898").is_err());
+    assert!(metrics::parse_address("192.168.1.10:9898").is_err());
+    assert!(metrics::parse_address("localhost:9898").is_err());
+
+    let config: BotConfig = toml::from_str("[metrics]\nenabled = true\naddress = \"0.0.0.0:9898\"\n").unwrap();
+    let problems = config.problems();
+    assert_eq!(problems.len(), 1, "{:?}", problems);
+    assert!(problems[0].contains("not a loopback address"), "{:?}", problems);
+}
+
+#[test]
+fn serve_refuses_other_addresses() {
+    let (log_sender, _) = unbounded();
+    let logger = Logger::new(log_sender);
+    let config = MetricsConfig {
+        enabled: true,
+        address: "0.0.0.0:0".to_string(),
+    };
+    let err = metrics::serve(&logger, &config).unwrap_err();
+    assert!(err.contains("not a loopback address"), "{}", err);
+}
@@ -0,0 +1,505 @@
+use backend::Logger;
+use backend::clock::{Clock, ManualClock};
+use backend::config::{BotConfig, Endpoint, Environment, ExchangeConfig};
+use backend::exchange::{self, Exchange};
+use backend::recorder::{self, MarketData, RecorderConfig};
+use chrono::{TimeZone, Utc};
+use crossbeam::channel::unbounded;
+use futures_util::{SinkExt, StreamExt};
+use paradex::structs::{
+    Level, ModifyOrderRequest, OrderBook, OrderBookUpdateType, OrderInstruction, OrderStatus, OrderType,
+    OrderUpdate, OrderUpdates, Side,
+};
+use rust_decimal::Decimal;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tokio_util::sync::CancellationToken;
+
+// The Paradex client (REST + websocket) and the daemon against a local mock of the Paradex server:
+// system config, auth, positions, fills, orders and the order book channel
+
+const MARKET: &str = "BTC-USD-100000-C";
+const JWT: &str = "mock-jwt";
+// Any private key signs: the mock does not check the signatures
+const L2_KEY: &str = "0x4a3f1e2d5c6b7a8912345678abcdef0123456789abcdef0123456789abcdef0";
+
+// Orders and books of the mock, requests received
+#[derive(Default)]
+struct MockState {
+    // "METHOD target" in reception order
+    requests: Vec<String>,
+    orders: Vec<OrderUpdate>,
+    books: HashMap<String, OrderBook>,
+    // Channels subscribed on the websocket
+    subscriptions: Vec<String>,
+}
+
+impl MockState {
+    // Status and JSON body of a REST request
+    fn answer(&mut self, method: &str, target: &str, headers: &HashMap<String, String>, body: &[u8]) -> (u16, String) {
+        self.requests.push(format!("{} {}", method, target));
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        match (method, path) {
+            ("GET", "/v1/system/config") => return (200, system_config().to_string()),
+            ("POST", "/v1/auth") => {
+                return if headers.contains_key("paradex-starknet-signature") {
+                    (200, json!({ "jwt_token": JWT }).to_string())
+                } else {
+                    error(401, "missing signature")
+                };
+            }
+            _ => {}
+        }
+        if headers.get("authorization") != Some(&format!("Bearer {}", JWT)) {
+            return error(401, "missing jwt");
+        }
+        match (method, path) {
+            ("GET", "/v1/positions") => (200, json!({ "results": [] }).to_string()),
+            ("GET", "/v1/fills") => (200, json!({ "next": null, "prev": null, "results": [] }).to_string()),
+            ("GET", "/v1/orders") => {
+                let orders = OrderUpdates {
+                    results: self.orders.clone(),
+                };
+                (200, serde_json::to_string(&orders).unwrap())
+            }
+            ("DELETE", "/v1/orders/") => {
+                let market = query.strip_prefix("market=").unwrap_or_default().to_string();
+                self.orders.retain(|order| order.market != market);
+                (200, json!({ "market": market, "message": "cancelled" }).to_string())
+            }
+            ("PUT", _) if let Some(id) = path.strip_prefix("/v1/orders/") => {
+                let request: Value = serde_json::from_slice(body).unwrap();
+                match self.orders.iter_mut().find(|order| order.id == id) {
+                    Some(order) => {
+                        order.price = request["price"].as_str().and_then(|price| Decimal::from_str(price).ok());
+                        (200, serde_json::to_string(order).unwrap())
+                    }
+                    None => error(404, "order not found"),
+                }
+            }
+            ("DELETE", _) if let Some(id) = path.strip_prefix("/v1/orders/") => {
+                if self.orders.iter().any(|order| order.id == id) {
+                    self.orders.retain(|order| order.id != id);
+                    (204, String::new())
+                } else {
+                    error(404, "order not found")
+                }
+            }
+            _ => error(404, "not found"),
+        }
+    }
+
+    fn count(&self, prefix: &str) -> usize {
+        self.requests.iter().filter(|request| request.starts_with(prefix)).count()
+    }
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    (status, json!({ "error": "MOCK_ERROR", "message": message }).to_string())
+}
+
+// Hashes of the account contracts and chain id, from which the client derives the account address
+fn system_config() -> Value {
+    json!({
+        "block_explorer_url": "",
+        "bridged_tokens": [],
+        "environment": "mock",
+        "l1_chain_id": "11155111",
+        "l1_core_contract_address": "0x0",
+        "l1_operator_address": "0x0",
+        "liquidation_fee": "0.2",
+        "oracle_address": "0x0",
+        "paraclear_account_hash": "0x41cb0280ebadaa75f996d8d92c6f265f6d040bb3ba442e5f86a554f1765244e",
+        "paraclear_account_proxy_hash": "0x3530cc4759d78042f1b543bf797f5f3d647cde0388c33734cf91b7f7b9314a9",
+        "paraclear_address": "0x0",
+        "paraclear_decimals": 8,
+        "partial_liquidation_buffer": "0.2",
+        "partial_liquidation_share_increment": "0.05",
+        "starknet_chain_id": "PRIVATE_SN_POTC_SEPOLIA",
+        "starknet_fullnode_rpc_url": "",
+        "starknet_gateway_url": "",
+        "universal_deployer_address": "0x0"
+    })
+}
+
+fn bid(id: &str, price: &str, size: &str) -> OrderUpdate {
+    OrderUpdate {
+        account: "0x1".to_string(),
+        cancel_reason: String::new(),
+        client_id: String::new(),
+        created_at: 1_781_517_600_000,
+        id: id.to_string(),
+        instruction: OrderInstruction::POST_ONLY,
+        last_updated_at: 1_781_517_600_000,
+        market: MARKET.to_string(),
+        price: Some(Decimal::from_str(price).unwrap()),
+        remaining_size: Decimal::from_str(size).unwrap(),
+        side: Side::BUY,
+        size: Decimal::from_str(size).unwrap(),
+        status: OrderStatus::OPEN,
+        timestamp: 1_781_517_600_000,
+        order_type: OrderType::LIMIT,
+        seq_no: 1,
+        avg_fill_price: 0.0,
+        received_at: 1_781_517_600_000,
+        published_at: 1_781_517_600_000,
+        flags: vec![],
+        trigger_price: None,
+    }
+}
+
+// Our bid of 1 shares the first bid with 4 others, plenty of bids below
+fn book() -> OrderBook {
+    let level = |side, price, size| Level { side, price, size };
+    OrderBook {
+        seq_no: 1,
+        market: MARKET.to_string(),
+        last_updated_at: 1_781_517_600_000,
+        update_type: OrderBookUpdateType::Snapshot,
+        deletes: vec![],
+        inserts: vec![
+            level(Side::BUY, 10.0, 5.0),
+            level(Side::BUY, 9.9, 20.0),
+            level(Side::BUY, 9.8, 30.0),
+            level(Side::SELL, 11.0, 5.0),
+        ],
+        updates: vec![],
+    }
+}
+
+struct MockServer {
+    state: Arc<Mutex<MockState>>,
+    endpoint: Endpoint,
+}
+
+impl MockServer {
+    // REST and websocket servers on two local ports, one open bid on MARKET
+    async fn start() -> Self {
+        let mut state = MockState::default();
+        state.orders.push(bid("order-1", "10.0", "1"));
+        state.books.insert(MARKET.to_string(), book());
+        let state = Arc::new(Mutex::new(state));
+        let rest = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let websocket = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = Endpoint {
+            rest: format!("http://{}", rest.local_addr().unwrap()),
+            websocket: format!("ws://{}/v1", websocket.local_addr().unwrap()),
+        };
+        tokio::spawn(serve_rest(rest, state.clone()));
+        tokio::spawn(serve_websocket(websocket, state.clone()));
+        Self { state, endpoint }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap()
+    }
+
+    // Wait until the condition holds on the state, false after the timeout
+    async fn wait_for(&self, timeout: Duration, condition: impl Fn(&MockState) -> bool) -> bool {
+        let start = std::time::Instant::now();
+        while start.elapsed() < timeout {
+            if condition(&self.state()) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        false
+    }
+}
+
+async fn serve_rest(listener: TcpListener, state: Arc<Mutex<MockState>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(rest_connection(stream, state.clone()));
+    }
+}
+
+// HTTP/1.1 requests of a kept-alive connection, bodies sized by Content-Length
+async fn rest_connection(stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let mut reader = BufReader::new(stream);
+    loop {
+        let mut request_line = String::new();
+        if reader.read_line(&mut request_line).await.unwrap_or(0) == 0 {
+            return;
+        }
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+        let mut headers = HashMap::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap_or(0) == 0 {
+                return;
+            }
+            let line = line.trim_end();
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+            }
+        }
+        let length = headers.get("content-length").and_then(|length| length.parse().ok()).unwrap_or(0);
+        let mut body = vec![0; length];
+        if reader.read_exact(&mut body).await.is_err() {
+            return;
+        }
+        let (status, body) = state.lock().unwrap().answer(method, target, &headers, &body);
+        let reason = match status {
+            200 => "OK",
+            204 => "No Content",
+            401 => "Unauthorized",
+            _ => "Not Found",
+        };
+        let response = format!(
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            status,
+            reason,
+            body.len(),
+            body
+        );
+        if reader.get_mut().write_all(response.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn serve_websocket(listener: TcpListener, state: Arc<Mutex<MockState>>) {
+    while let Ok((stream, _)) = listener.accept().await {
+        tokio::spawn(websocket_connection(stream, state.clone()));
+    }
+}
+
+// JSON-RPC subscribe / unsubscribe, then the snapshot of the order book channels
+async fn websocket_connection(stream: TcpStream, state: Arc<Mutex<MockState>>) {
+    let Ok(mut socket) = tokio_tungstenite::accept_async(stream).await else {
+        return;
+    };
+    // pings answered by tungstenite
+    while let Some(Ok(message)) = socket.next().await {
+        let WsMessage::Text(text) = message else {
+            continue;
+        };
+        let request: Value = serde_json::from_str(text.as_str()).unwrap();
+        let channel = request["params"]["channel"].as_str().unwrap_or_default().to_string();
+        let response = json!({ "jsonrpc": "2.0", "id": request["id"], "result": { "channel": channel } });
+        if socket.send(WsMessage::text(response.to_string())).await.is_err() {
+            return;
+        }
+        if request["method"] != "subscribe" {
+            continue;
+        }
+        // "order_book.<market>.snapshot@15@50ms"
+        let snapshot = {
+            let mut state = state.lock().unwrap();
+            state.subscriptions.push(channel.clone());
+            channel
+                .strip_prefix("order_book.")
+                .and_then(|name| name.split_once('.'))
+                .and_then(|(market, _)| state.books.get(market).cloned())
+        };
+        if let Some(book) = snapshot {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": "subscription",
+                "params": { "channel": channel, "data": book }
+            });
+            if socket.send(WsMessage::text(notification.to_string())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn exchange_config(mock: &MockServer) -> ExchangeConfig {
+    ExchangeConfig {
+        endpoint: Some(mock.endpoint.clone()),
+        ..ExchangeConfig::default()
+    }
+}
+
+#[test]
+fn endpoint_replaces_the_servers_of_the_environment() {
+    let parse = |text: &str| toml::from_str::<BotConfig>(text).unwrap();
+    let config = parse(
+        r#"
+        [exchange]
+        environment = "testnet"
+        [exchange.endpoint]
+        rest = "http://127.0.0.1:8080"
+        websocket = "ws://127.0.0.1:8081/v1"
+        "#,
+    );
+    let url = config.exchange.leak_url();
+    assert_eq!(url.rest(), "http://127.0.0.1:8080");
+    assert_eq!(url.websocket(), "ws://127.0.0.1:8081/v1");
+    assert_eq!(config.exchange.describe(), "testnet at http://127.0.0.1:8080");
+    // key of the environment
+    assert_eq!(config.accounts()[0].key_var, Environment::Testnet.key_var());
+    assert!(config.problems().is_empty(), "{:?}", config.problems());
+    assert_eq!(parse("").exchange.leak_url().rest(), "https://api.prod.paradex.trade");
+
+    let config = parse(
+        r#"
+        [exchange.endpoint]
+        rest = "127.0.0.1:8080"
+        websocket = "http://127.0.0.1:8081"
+        "#,
+    );
+    let problems = config.problems();
+    assert_eq!(problems.len(), 2, "{:?}", problems);
+    assert!(problems[0].contains("exchange.endpoint.rest"), "{:?}", problems);
+    assert!(problems[1].contains("exchange.endpoint.websocket"), "{:?}", problems);
+    assert!(toml::from_str::<BotConfig>("[exchange.endpoint]\nrest = \"http://127.0.0.1\"\n").is_err());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn paradex_exchange_talks_to_the_mock() {
+    let mock = MockServer::start().await;
+    let (log_sender, _) = unbounded();
+    let logger = Logger::new(log_sender);
+    let mut exchange = exchange::connect(&logger, exchange_config(&mock).leak_url(), Some(L2_KEY.to_string()), Clock::System)
+        .await
+        .unwrap();
+
+    assert!(exchange.positions().await.unwrap().results.is_empty());
+    let orders = exchange.open_orders().await.unwrap();
+    assert_eq!(orders.results.len(), 1);
+    assert_eq!(orders.results[0].price, Some(Decimal::from_str("10.0").unwrap()));
+    // one JWT for the private requests
+    assert_eq!(mock.state().count("POST /v1/auth"), 1);
+
+    let book = exchange.book_top(MARKET.to_string()).await.unwrap();
+    assert_eq!(book.first_bid.map(|level| (level.price, level.size)), Some((10.0, 5.0)));
+    assert_eq!(book.third_bid.map(|level| level.price), Some(9.8));
+    assert_eq!(book.first_ask.map(|level| level.price), Some(11.0));
+    assert!(mock.state().subscriptions.iter().any(|channel| channel.starts_with("order_book.BTC-USD-100000-C.")));
+
+    let modified = exchange
+        .modify_order(ModifyOrderRequest {
+            id: "order-1".to_string(),
+            market: MARKET.to_string(),
+            price: Some(Decimal::from_str("10.1").unwrap()),
+            side: Side::BUY,
+            size: Decimal::ONE,
+            order_type: OrderType::LIMIT,
+        })
+        .await
+        .unwrap();
+    assert_eq!(modified.price, Some(Decimal::from_str("10.1").unwrap()));
+
+    let cancelled = exchange.cancel_all_orders_for_market(MARKET.to_string()).await.unwrap();
+    assert_eq!(cancelled.market, MARKET);
+    assert!(exchange.open_orders().await.unwrap().results.is_empty());
+    // unknown order: error of the server
+    assert!(exchange.cancel_order("order-2".to_string()).await.is_err());
+    exchange.stop().await.unwrap();
+}
+
+// Market data stamped with the time of the recorder clock
+#[tokio::test(flavor = "multi_thread")]
+async fn recorder_times_the_messages_with_its_clock() {
+    let mock = MockServer::start().await;
+    let directory = std::env::temp_dir().join(format!("bot_paradex_recorder_mock_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    let (log_sender, _) = unbounded();
+    let start = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+    let config = RecorderConfig {
+        directory: directory.clone(),
+        markets: vec![MARKET.to_string()],
+        trades: false,
+        bbo: false,
+        ..RecorderConfig::default()
+    };
+    let shutdown = CancellationToken::new();
+    let recorder = tokio::spawn(recorder::run_recorder(
+        Logger::new(log_sender),
+        config,
+        exchange_config(&mock).leak_url(),
+        Clock::Manual(ManualClock::new(start)),
+        shutdown.clone(),
+    ));
+
+    assert!(mock.wait_for(Duration::from_secs(10), |state| !state.subscriptions.is_empty()).await);
+    // snapshot sent right after the subscription
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    shutdown.cancel();
+    recorder.await.unwrap().unwrap();
+
+    let end = start + chrono::TimeDelta::seconds(1);
+    let records = recorder::read_records(&directory, Some(MARKET), start, end).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].received_at, start);
+    assert!(matches!(records[0].data, MarketData::OrderBook(_)));
+    let _ = std::fs::remove_dir_all(&directory);
+}
+
+// The daemon reprices the bid from the order book channel, then cancels it on SIGTERM
+#[cfg(unix)]
+#[tokio::test(flavor = "multi_thread")]
+async fn daemon_trades_against_the_mock() {
+    let mock = MockServer::start().await;
+    let directory = std::env::temp_dir().join(format!("bot_paradex_daemon_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    let config = format!(
+        "[exchange]\nenvironment = \"testnet\"\n\n[exchange.endpoint]\nrest = \"{}\"\nwebsocket = \"{}\"\n\n\
+         [keystore]\npath = \"keystore.json\"\nenv_fallback = true\n\n[logging]\nfile_enabled = false\n",
+        mock.endpoint.rest, mock.endpoint.websocket
+    );
+    std::fs::write(directory.join("bot_paradex.toml"), config).unwrap();
+    let output = directory.join("daemon.log");
+
+    let mut daemon = std::process::Command::new(env!("CARGO_BIN_EXE_daemon"))
+        .current_dir(&directory)
+        .env("PARADEX_TESTNET_L2_KEY", L2_KEY)
+        .stdout(std::fs::File::create(&output).unwrap())
+        .stderr(std::fs::File::create(directory.join("daemon.err")).unwrap())
+        .spawn()
+        .unwrap();
+    let log = || std::fs::read_to_string(&output).unwrap_or_default();
+
+    // first bid shared: one step above it
+    let repriced = mock
+        .wait_for(Duration::from_secs(30), |state| {
+            state.orders.first().and_then(|order| order.price) == Some(Decimal::from_str("10.1").unwrap())
+        })
+        .await;
+    if !repriced {
+        let _ = daemon.kill();
+    }
+    assert!(repriced, "no reprice, daemon output:\n{}", log());
+
+    let status = std::process::Command::new("kill")
+        .args(["-TERM", &daemon.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let mut exit = None;
+    for _ in 0..300 {
+        exit = daemon.try_wait().unwrap();
+        if exit.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let Some(exit) = exit else {
+        let _ = daemon.kill();
+        panic!("daemon still running after SIGTERM, output:\n{}", log());
+    };
+    assert!(exit.success(), "{:?}, daemon output:\n{}", exit, log());
+
+    let state = mock.state();
+    assert!(state.orders.is_empty(), "{:?}", state.requests);
+    assert_eq!(state.count("DELETE /v1/orders/?market=BTC-USD-100000-C"), 1, "{:?}", state.requests);
+    assert!(state.count("GET /v1/positions") >= 1);
+    assert!(state.count("GET /v1/fills") >= 1);
+    assert!(state.subscriptions.iter().any(|channel| channel.starts_with("order_book.BTC-USD-100000-C.")));
+    // journaled by the daemon
+    let journal = std::fs::read_to_string(directory.join("journal/trades.jsonl")).unwrap();
+    assert!(journal.contains("order-1"), "{}", journal);
+}
@@ -0,0 +1,142 @@
+use backend::recorder::{self, MarketData, Record, RecordingWriter};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use paradex::structs::{Side, Trade, TradeType};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// Recording files: rotation, index, recovery of the files of a crash and reading back
+
+const BTC_CALL: &str = "BTC-USD-100000-C";
+const ETH_CALL: &str = "ETH-USD-4000-C";
+
+// Fresh directory per test, removed first in case of a previous failed run
+fn directory(name: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("bot_paradex_recorder_{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
+
+fn at(seconds: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 6, 15, 10, 0, 0).unwrap() + Duration::seconds(seconds)
+}
+
+fn trade(market: &str, price: f64, received_at: DateTime<Utc>) -> Record {
+    Record {
+        received_at,
+        market: market.to_string(),
+        data: MarketData::Trade(Trade {
+            created_at: received_at.timestamp_millis() as u64,
+            id: format!("{}-{}", market, received_at.timestamp()),
+            market: market.to_string(),
+            price,
+            side: Side::BUY,
+            size: 1.0,
+            trade_type: TradeType::FILL,
+        }),
+    }
+}
+
+fn line(record: &Record) -> String {
+    format!("{}\n", serde_json::to_string(record).unwrap())
+}
+
+fn seconds(records: &[Record]) -> Vec<i64> {
+    records.iter().map(|record| (record.received_at - at(0)).num_seconds()).collect()
+}
+
+fn write_gzip(path: &Path, content: &str) {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(content.as_bytes()).unwrap();
+    std::fs::write(path, encoder.finish().unwrap()).unwrap();
+}
+
+#[test]
+fn files_are_rotated_and_indexed() {
+    let directory = directory("rotation");
+    let mut writer = RecordingWriter::open(&directory, 1).unwrap();
+    writer.write(&trade(BTC_CALL, 10.0, at(0))).unwrap();
+    writer.write(&trade(ETH_CALL, 5.0, at(30))).unwrap();
+    writer.write(&trade(BTC_CALL, 11.0, at(59))).unwrap();
+    // a minute after the start of the file
+    writer.write(&trade(BTC_CALL, 12.0, at(60))).unwrap();
+    // indexed when finished
+    assert_eq!(recorder::read_index(&directory).unwrap().len(), 1);
+    drop(writer);
+
+    let index = recorder::read_index(&directory).unwrap();
+    assert_eq!(index.len(), 2);
+    let (first, second) = (&index[0], &index[1]);
+    assert_eq!((first.start, first.end, first.records), (at(0), at(59), 3));
+    assert_eq!(first.markets[BTC_CALL], 2);
+    assert_eq!(first.markets[ETH_CALL], 1);
+    assert_eq!((second.start, second.end, second.records), (at(60), at(60), 1));
+    assert_ne!(first.file, second.file);
+
+    assert_eq!(seconds(&recorder::read_file(&directory.join(&first.file)).unwrap()), vec![0, 30, 59]);
+    assert_eq!(seconds(&recorder::read_file(&directory.join(&second.file)).unwrap()), vec![60]);
+}
+
+#[test]
+fn file_reading_stops_at_a_corrupt_line() {
+    let directory = directory("corrupt_line");
+    let path = directory.join("market_data.jsonl.gz");
+    let content = format!(
+        "{}{}{{\"received_at\":\n{}",
+        line(&trade(BTC_CALL, 10.0, at(0))),
+        line(&trade(BTC_CALL, 11.0, at(1))),
+        line(&trade(BTC_CALL, 12.0, at(2)))
+    );
+    write_gzip(&path, &content);
+    assert_eq!(seconds(&recorder::read_file(&path).unwrap()), vec![0, 1]);
+}
+
+#[test]
+fn truncated_file_is_indexed_with_its_readable_records() {
+    let directory = directory("recovery");
+    // gzip stream cut by a crash: no end of stream, last line incomplete
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(line(&trade(BTC_CALL, 10.0, at(0))).as_bytes()).unwrap();
+    encoder.write_all(line(&trade(ETH_CALL, 5.0, at(10))).as_bytes()).unwrap();
+    encoder.write_all(b"{\"received_at\":\"2026").unwrap();
+    encoder.flush().unwrap();
+    std::fs::write(directory.join("market_data_20260615T100000.jsonl.gz"), encoder.get_ref()).unwrap();
+    // nothing readable: not indexed
+    std::fs::write(directory.join("market_data_20260615T110000.jsonl.gz"), b"").unwrap();
+
+    drop(RecordingWriter::open(&directory, 60).unwrap());
+    let index = recorder::read_index(&directory).unwrap();
+    assert_eq!(index.len(), 1);
+    let entry = &index[0];
+    assert_eq!(entry.file, "market_data_20260615T100000.jsonl.gz");
+    assert_eq!((entry.start, entry.end, entry.records), (at(0), at(10), 2));
+    assert_eq!(entry.markets.len(), 2);
+
+    // indexed once
+    drop(RecordingWriter::open(&directory, 60).unwrap());
+    assert_eq!(recorder::read_index(&directory).unwrap().len(), 1);
+}
+
+#[test]
+fn records_are_filtered_by_time_and_market() {
+    let directory = directory("read_records");
+    let mut writer = RecordingWriter::open(&directory, 1).unwrap();
+    for (market, second) in [(BTC_CALL, 0), (ETH_CALL, 20), (BTC_CALL, 40), (BTC_CALL, 70), (ETH_CALL, 130)] {
+        writer.write(&trade(market, 10.0, at(second))).unwrap();
+    }
+    drop(writer);
+    assert_eq!(recorder::read_index(&directory).unwrap().len(), 3);
+
+    let every = recorder::read_records(&directory, None, at(0), at(200)).unwrap();
+    assert_eq!(seconds(&every), vec![0, 20, 40, 70, 130]);
+    // end excluded, across the files
+    let btc = recorder::read_records(&directory, Some(BTC_CALL), at(20), at(70)).unwrap();
+    assert_eq!(seconds(&btc), vec![40]);
+    let btc = recorder::read_records(&directory, Some(BTC_CALL), at(20), at(71)).unwrap();
+    assert_eq!(seconds(&btc), vec![40, 70]);
+    let eth = recorder::read_records(&directory, Some(ETH_CALL), at(0), at(200)).unwrap();
+    assert_eq!(seconds(&eth), vec![20, 130]);
+    assert!(recorder::read_records(&directory, Some("SOL-USD-200-C"), at(0), at(200)).unwrap().is_empty());
+}
@@ -0,0 +1,499 @@
+use backend::clock::{Clock, ManualClock};
+use backend::config::{Account, BotConfig};
+use backend::events::{AccountEventSender, BackendEvent};
+use backend::exchange::Exchange;
+use backend::fees::FeeModel;
+use backend::journal::{Journal, JournalAction, ReasonCode};
+use backend::sim::{SimEvent, SimExchange, SimFaults, Trader};
+use backend::{Logger, run_trading_loop};
+use chrono::{TimeDelta, TimeZone, Utc};
+use crossbeam::channel::{Receiver, unbounded};
+use paradex::structs::{FillLiquidity, OrderStatus, PositionStatus, Side};
+use rust_decimal::Decimal;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+// Full-loop scenarios of the bot against the simulated exchange
+
+const MARKET: &str = "ETH-USD-30DEC26-3000-C";
+
+struct Harness {
+    logger: Logger,
+    events: AccountEventSender,
+    received: Receiver<BackendEvent>,
+    config: BotConfig,
+    account: Account,
+    clock: Clock,
+}
+
+impl Harness {
+    fn new() -> Self {
+        Self::with_clock(Clock::System)
+    }
+
+    fn with_clock(clock: Clock) -> Self {
+        let (log_sender, _) = unbounded();
+        let (event_sender, received) = unbounded();
+        let config = BotConfig::default();
+        let mut account = config.accounts().remove(0);
+        account.journal.enabled = false;
+        Self {
+            logger: Logger::new(log_sender).with_clock(clock.clone()),
+            events: AccountEventSender::new(account.name.clone(), event_sender),
+            received,
+            config,
+            account,
+            clock,
+        }
+    }
+
+    async fn run(&self, sim: &SimExchange, shutdown: &CancellationToken) -> Result<(), String> {
+        let mut exchange = sim.clone();
+        run_trading_loop(
+            &self.logger,
+            &self.events,
+            &self.config,
+            &self.account,
+            &mut exchange,
+            &self.clock,
+            shutdown,
+        )
+        .await
+    }
+
+    // Run until the loop ends, the clock moved to the end of each sleep
+    async fn run_on_clock(&self, sim: &SimExchange, clock: &ManualClock) -> Result<(), String> {
+        let shutdown = CancellationToken::new();
+        let run = self.run(sim, &shutdown);
+        tokio::pin!(run);
+        for _ in 0..10_000 {
+            tokio::select! {
+                result = &mut run => return result,
+                _ = clock.advance_to_next_sleep() => {}
+            }
+        }
+        panic!("loop still running at {}", clock.now());
+    }
+
+    fn liquidations(&self) -> Vec<Decimal> {
+        self.received
+            .try_iter()
+            .filter_map(|event| match event {
+                BackendEvent::Account { event, .. } => match *event {
+                    BackendEvent::Liquidation { size, .. } => Some(size),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+// Counterparty bids 10.0 / 9.9 / 9.8 with the given size and an ask at 11.0
+fn book(sim: &SimExchange, bid_size: f64) {
+    sim.place_limit(Trader::Counterparty, MARKET, Side::BUY, 10.0, bid_size);
+    sim.place_limit(Trader::Counterparty, MARKET, Side::BUY, 9.9, bid_size);
+    sim.place_limit(Trader::Counterparty, MARKET, Side::BUY, 9.8, bid_size);
+    sim.place_limit(Trader::Counterparty, MARKET, Side::SELL, 11.0, 5.0);
+}
+
+// Bid levels of the snapshot, best first
+fn bid_prices(sim: &SimExchange) -> Vec<f64> {
+    sim.snapshot(MARKET)
+        .inserts
+        .iter()
+        .filter(|level| level.side == Side::BUY)
+        .map(|level| level.price)
+        .collect()
+}
+
+fn price(value: &str) -> Option<Decimal> {
+    Some(value.parse().unwrap())
+}
+
+#[test]
+fn matching_follows_price_then_time_priority() {
+    let sim = SimExchange::default();
+    let first = sim.place_limit(Trader::Counterparty, MARKET, Side::BUY, 10.0, 1.0);
+    let bot = sim.place_limit(Trader::Bot, MARKET, Side::BUY, 10.0, 1.0);
+    let better = sim.place_limit(Trader::Counterparty, MARKET, Side::BUY, 10.1, 1.0);
+
+    sim.place_market(Trader::Counterparty, MARKET, Side::SELL, 2.5);
+
+    assert_eq!(sim.order(&better).unwrap().status, OrderStatus::CLOSED);
+    assert_eq!(sim.order(&first).unwrap().status, OrderStatus::CLOSED);
+    let bot = sim.order(&bot).unwrap();
+    assert_eq!(bot.status, OrderStatus::OPEN);
+    assert_eq!(bot.remaining_size, price("0.5").unwrap());
+
+    let fills = sim.bot_fills();
+    assert_eq!(fills.len(), 1);
+    assert_eq!(fills[0].liquidity, FillLiquidity::MAKER);
+    assert_eq!((fills[0].price, fills[0].size), (10.0, 0.5));
+    assert_eq!(sim.position(MARKET).unwrap().size, 0.5);
+}
+
+#[test]
+fn market_order_without_liquidity_is_cancelled() {
+    let sim = SimExchange::default();
+    sim.place_limit(Trader::Counterparty, MARKET, Side::BUY, 10.0, 1.0);
+    let sell = sim.place_market(Trader::Bot, MARKET, Side::SELL, 3.0);
+
+    let sell = sim.order(&sell).unwrap();
+    assert_eq!(sell.status, OrderStatus::CLOSED);
+    assert_eq!(sell.remaining_size, Decimal::from(2));
+    assert_eq!(sell.avg_fill_price, 10.0);
+    assert!(bid_prices(&sim).is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn bid_is_repriced_then_cancelled_on_shutdown() {
+    let harness = Harness::new();
+    let sim = SimExchange::default();
+    book(&sim, 20.0);
+    let bot = sim.place_limit(Trader::Bot, MARKET, Side::BUY, 9.5, 1.0);
+
+    let shutdown = CancellationToken::new();
+    let (result, ()) = tokio::join!(harness.run(&sim, &shutdown), async {
+        // 1st loop: join the first bid, 2nd loop: first bid alone, then stable
+        tokio::time::sleep(Duration::from_secs(35)).await;
+        let order = sim.order(&bot).unwrap();
+        assert_eq!(order.status, OrderStatus::OPEN);
+        assert_eq!(order.price, price("10.1"));
+        assert_eq!(bid_prices(&sim), vec![10.1, 10.0, 9.9, 9.8]);
+        shutdown.cancel();
+    });
+
+    assert_eq!(result, Ok(()));
+    let order = sim.order(&bot).unwrap();
+    assert_eq!(order.status, OrderStatus::CLOSED);
+    assert_eq!(order.cancel_reason, "USER_CANCELED");
+    assert!(sim.is_stopped());
+    assert!(sim.bot_fills().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn low_liquidity_cancels_the_bid_and_ends_the_loop() {
+    let harness = Harness::new();
+    let sim = SimExchange::default();
+    // 3 of size inside the exit window, 5 needed for a bid of 1
+    book(&sim, 1.0);
+    let bot = sim.place_limit(Trader::Bot, MARKET, Side::BUY, 9.0, 1.0);
+
+    let result = harness.run(&sim, &CancellationToken::new()).await;
+
+    assert_eq!(result, Ok(()));
+    let order = sim.order(&bot).unwrap();
+    assert_eq!(order.status, OrderStatus::CLOSED);
+    assert_eq!(order.price, price("10.0"));
+    assert!(sim.is_stopped());
+}
+
+#[tokio::test(start_paused = true)]
+async fn filled_bid_is_liquidated_and_the_rest_cancelled() {
+    let harness = Harness::new();
+    let sim = SimExchange::default();
+    book(&sim, 20.0);
+    let bot = sim.place_limit(Trader::Bot, MARKET, Side::BUY, 10.5, 2.0);
+    sim.take_events();
+
+    // partial fill of our bid by a counterparty market sell
+    sim.place_market(Trader::Counterparty, MARKET, Side::SELL, 1.0);
+    let events = sim.take_events();
+    assert!(matches!(&events[..], [SimEvent::Order(order), SimEvent::Fill(fill), SimEvent::Position(position)]
+        if order.remaining_size == Decimal::ONE && fill.size == 1.0 && position.status == PositionStatus::OPEN));
+
+    let result = harness.run(&sim, &CancellationToken::new()).await;
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(harness.liquidations(), vec![Decimal::ONE]);
+    assert_eq!(sim.order(&bot).unwrap().status, OrderStatus::CLOSED);
+    let fills = sim.bot_fills();
+    assert_eq!(fills.len(), 2);
+    assert_eq!((fills[1].side, fills[1].liquidity.clone()), (Side::SELL, FillLiquidity::TAKER));
+    assert_eq!((fills[1].price, fills[1].size), (10.0, 1.0));
+    assert!((fills[1].realized_pnl + 0.5).abs() < 1e-9);
+    assert!(fills[1].fee > 0.0);
+    let position = sim.position(MARKET).unwrap();
+    assert_eq!(position.status, PositionStatus::CLOSED);
+    assert_eq!(position.size, 0.0);
+}
+
+#[tokio::test]
+async fn manual_clock_drives_the_refresh_and_the_timestamps() {
+    let start = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+    let clock = ManualClock::new(start);
+    let harness = Harness::with_clock(Clock::Manual(clock.clone()));
+    let sim = SimExchange::new(FeeModel::from_config(&Default::default()), Clock::Manual(clock.clone()));
+    book(&sim, 20.0);
+    let bot = sim.place_limit(Trader::Bot, MARKET, Side::BUY, 9.5, 1.0);
+
+    let shutdown = CancellationToken::new();
+    let (result, ()) = tokio::join!(harness.run(&sim, &shutdown), async {
+        // each loop ends with the refresh sleep
+        assert_eq!(clock.advance_to_next_sleep().await, start + TimeDelta::seconds(10));
+        assert_eq!(sim.order(&bot).unwrap().price, price("10.0"));
+        assert_eq!(clock.advance_to_next_sleep().await, start + TimeDelta::seconds(20));
+        // filled before the 3rd loop, which liquidates, the 4th one ends without order nor position
+        sim.place_market(Trader::Counterparty, MARKET, Side::SELL, 1.0);
+        assert_eq!(clock.advance_to_next_sleep().await, start + TimeDelta::seconds(30));
+    });
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(clock.pending_sleeps(), 0);
+    let fills = sim.bot_fills();
+    assert_eq!(fills.len(), 2);
+    let expected = (start + TimeDelta::seconds(20)).timestamp_millis() as u64;
+    assert!(fills.iter().all(|fill| fill.created_at == expected));
+    assert_eq!(harness.liquidations(), vec![Decimal::ONE]);
+}
+
+#[tokio::test]
+async fn journal_entries_carry_the_simulated_time() {
+    let start = Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap();
+    let clock = ManualClock::new(start);
+    let mut harness = Harness::with_clock(Clock::Manual(clock.clone()));
+    let path = std::env::temp_dir().join(format!("bot_paradex_sim_journal_{}.jsonl", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    harness.account.journal.enabled = true;
+    harness.account.journal.path = path.clone();
+    let sim = SimExchange::new(FeeModel::from_config(&Default::default()), Clock::Manual(clock.clone()));
+    book(&sim, 20.0);
+    sim.place_limit(Trader::Bot, MARKET, Side::BUY, 9.5, 1.0);
+
+    let shutdown = CancellationToken::new();
+    let (result, ()) = tokio::join!(harness.run(&sim, &shutdown), async {
+        clock.advance_to_next_sleep().await;
+        clock.advance_to_next_sleep().await;
+        sim.place_market(Trader::Counterparty, MARKET, Side::SELL, 1.0);
+        clock.advance_to_next_sleep().await;
+    });
+    assert_eq!(result, Ok(()));
+
+    let entries = Journal::read(&path).unwrap();
+    let timestamp = |action: JournalAction| {
+        entries
+            .iter()
+            .find(|entry| entry.action == action)
+            .map(|entry| entry.timestamp)
+    };
+    // repriced at the first loop, liquidated at the 3rd one
+    assert_eq!(timestamp(JournalAction::Modify), Some(start));
+    assert_eq!(timestamp(JournalAction::Liquidation), Some(start + TimeDelta::seconds(20)));
+    assert!(entries.iter().all(|entry| entry.timestamp <= start + TimeDelta::seconds(30)));
+    let _ = std::fs::remove_file(&path);
+}
+
+// Bid of 2 at 10.5 above the book, filled for 1: a position to sell and a bid to cancel
+fn filled_bid(faults: SimFaults) -> (SimExchange, ManualClock, Harness) {
+    let clock = ManualClock::new(Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap());
+    let harness = Harness::with_clock(Clock::Manual(clock.clone()));
+    let sim = SimExchange::new(FeeModel::from_config(&Default::default()), Clock::Manual(clock.clone()));
+    book(&sim, 20.0);
+    sim.place_limit(Trader::Bot, MARKET, Side::BUY, 10.5, 2.0);
+    sim.place_market(Trader::Counterparty, MARKET, Side::SELL, 1.0);
+    sim.set_faults(faults);
+    (sim, clock, harness)
+}
+
+// Position sold and bid cancelled
+async fn assert_flat(sim: &SimExchange) {
+    assert_eq!(sim.position(MARKET).unwrap().status, PositionStatus::CLOSED);
+    sim.set_faults(SimFaults::default());
+    assert!(sim.open_orders().await.unwrap().results.is_empty());
+}
+
+#[tokio::test]
+async fn errors_and_lost_responses_are_reconciled() {
+    let (sim, clock, harness) = filled_bid(SimFaults {
+        server_error_rate: 0.3,
+        lost_response_share: 0.5,
+        rate_limit_rate: 0.1,
+        dropped_message_rate: 0.2,
+        seed: 3,
+        ..SimFaults::default()
+    });
+
+    assert_eq!(harness.run_on_clock(&sim, &clock).await, Ok(()));
+    assert_flat(&sim).await;
+}
+
+#[tokio::test]
+async fn latency_and_disconnect_delay_the_liquidation() {
+    let (sim, clock, harness) = filled_bid(SimFaults {
+        latency: Duration::from_secs(2),
+        ..SimFaults::default()
+    });
+    let start = clock.now();
+    sim.disconnect(Duration::from_secs(60));
+
+    assert_eq!(harness.run_on_clock(&sim, &clock).await, Ok(()));
+    assert_flat(&sim).await;
+    let sell = sim.bot_fills().pop().unwrap();
+    assert_eq!(sell.side, Side::SELL);
+    assert!(sell.created_at >= (start + TimeDelta::seconds(60)).timestamp_millis() as u64);
+}
+
+#[tokio::test]
+async fn partially_filled_liquidation_is_sold_at_the_next_loops() {
+    let (sim, clock, harness) = filled_bid(SimFaults {
+        partial_fill_rate: 0.5,
+        seed: 2,
+        ..SimFaults::default()
+    });
+
+    assert_eq!(harness.run_on_clock(&sim, &clock).await, Ok(()));
+    assert_flat(&sim).await;
+    let liquidations = harness.liquidations();
+    assert!(liquidations.len() > 1, "liquidations {:?}", liquidations);
+    let sold: f64 = sim
+        .bot_fills()
+        .iter()
+        .filter(|fill| fill.side == Side::SELL)
+        .map(|fill| fill.size)
+        .sum();
+    assert_eq!(sold, 1.0);
+}
+
+#[tokio::test]
+async fn dropped_snapshots_leave_the_bid_untouched() {
+    let clock = ManualClock::new(Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap());
+    let harness = Harness::with_clock(Clock::Manual(clock.clone()));
+    let sim = SimExchange::new(FeeModel::from_config(&Default::default()), Clock::Manual(clock.clone()));
+    book(&sim, 20.0);
+    let bot = sim.place_limit(Trader::Bot, MARKET, Side::BUY, 9.5, 1.0);
+    sim.take_events();
+    sim.set_faults(SimFaults {
+        dropped_message_rate: 1.0,
+        ..SimFaults::default()
+    });
+
+    let shutdown = CancellationToken::new();
+    let (result, ()) = tokio::join!(harness.run(&sim, &shutdown), async {
+        for _ in 0..3 {
+            clock.advance_to_next_sleep().await;
+        }
+        assert_eq!(sim.order(&bot).unwrap().price, price("9.5"));
+        shutdown.cancel();
+    });
+
+    assert_eq!(result, Ok(()));
+    assert_eq!(sim.order(&bot).unwrap().status, OrderStatus::CLOSED);
+    assert!(sim.take_events().iter().all(|event| !matches!(event, SimEvent::Order(_))));
+}
+
+#[tokio::test]
+async fn stale_snapshot_repeats_the_previous_book() {
+    let mut sim = SimExchange::default();
+    let top = sim.place_limit(Trader::Counterparty, MARKET, Side::BUY, 10.0, 1.0);
+    sim.place_limit(Trader::Counterparty, MARKET, Side::BUY, 9.0, 1.0);
+    sim.set_faults(SimFaults {
+        stale_snapshot_rate: 1.0,
+        ..SimFaults::default()
+    });
+
+    let first_bid = |book: backend::strategy::BookTop| book.first_bid.map(|level| level.price);
+    assert_eq!(first_bid(sim.book_top(MARKET.to_string()).await.unwrap()), Some(10.0));
+    sim.cancel(&top);
+    assert_eq!(first_bid(sim.book_top(MARKET.to_string()).await.unwrap()), Some(10.0));
+    sim.set_faults(SimFaults::default());
+    assert_eq!(first_bid(sim.book_top(MARKET.to_string()).await.unwrap()), Some(9.0));
+}
+
+#[tokio::test]
+async fn short_position_is_bought_back() {
+    let harness = Harness::new();
+    let journal = backend::open_journal(&harness.logger, &harness.account.journal, &harness.clock, harness.clock.now());
+    let sim = SimExchange::default();
+    book(&sim, 5.0);
+    sim.place_market(Trader::Bot, MARKET, Side::SELL, 1.0);
+    let position = sim.position(MARKET).unwrap();
+    assert_eq!(position.size, -1.0);
+
+    let mut exchange = sim.clone();
+    let order =
+        backend::liquidate_position(&harness.logger, &journal, &mut exchange, &position, ReasonCode::PositionOpen)
+            .await
+            .unwrap();
+    assert_eq!(order.side, Side::BUY);
+    assert_eq!(order.size, Decimal::ONE);
+    assert_eq!(sim.position(MARKET).unwrap().status, PositionStatus::CLOSED);
+}
+
+#[tokio::test]
+async fn position_size_not_convertible_sends_no_order() {
+    let harness = Harness::new();
+    let journal = backend::open_journal(&harness.logger, &harness.account.journal, &harness.clock, harness.clock.now());
+    let sim = SimExchange::default();
+    book(&sim, 5.0);
+    sim.place_market(Trader::Bot, MARKET, Side::SELL, 1.0);
+    let mut position = sim.position(MARKET).unwrap();
+    position.size = f64::NAN;
+    sim.take_events();
+
+    let mut exchange = sim.clone();
+    let result =
+        backend::liquidate_position(&harness.logger, &journal, &mut exchange, &position, ReasonCode::PositionOpen).await;
+    assert!(result.is_err());
+    assert!(!sim.take_events().iter().any(|event| matches!(event, SimEvent::Order(_))));
+    assert_eq!(sim.position(MARKET).unwrap().size, -1.0);
+}
+
+#[tokio::test]
+async fn shutdown_fails_when_the_cancel_keeps_failing() {
+    let clock = ManualClock::new(Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap());
+    let harness = Harness::with_clock(Clock::Manual(clock.clone()));
+    let sim = SimExchange::new(FeeModel::from_config(&Default::default()), Clock::Manual(clock.clone()));
+    book(&sim, 20.0);
+    let bot = sim.place_limit(Trader::Bot, MARKET, Side::BUY, 9.5, 1.0);
+    sim.set_faults(SimFaults {
+        server_error_rate: 1.0,
+        ..SimFaults::default()
+    });
+
+    // shutdown requested before the first loop: straight to the cancel, retried on the clock
+    let shutdown = CancellationToken::new();
+    shutdown.cancel();
+    let run = harness.run(&sim, &shutdown);
+    tokio::pin!(run);
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            _ = clock.advance_to_next_sleep() => {}
+        }
+    };
+
+    let err = result.unwrap_err();
+    assert!(err.contains("after 3 attempts"), "{}", err);
+    assert_eq!(sim.order(&bot).unwrap().status, OrderStatus::OPEN);
+}
+
+#[tokio::test]
+async fn shutdown_fails_when_the_cancelled_orders_stay_open() {
+    let clock = ManualClock::new(Utc.with_ymd_and_hms(2026, 3, 2, 12, 0, 0).unwrap());
+    let harness = Harness::with_clock(Clock::Manual(clock.clone()));
+    let sim = SimExchange::new(FeeModel::from_config(&Default::default()), Clock::Manual(clock.clone()));
+    book(&sim, 20.0);
+    let bot = sim.place_limit(Trader::Bot, MARKET, Side::BUY, 9.5, 1.0);
+    // every cancel acknowledged, the order still resting
+    sim.set_faults(SimFaults {
+        ignored_cancel_rate: 1.0,
+        ..SimFaults::default()
+    });
+
+    let shutdown = CancellationToken::new();
+    shutdown.cancel();
+    let run = harness.run(&sim, &shutdown);
+    tokio::pin!(run);
+    let result = loop {
+        tokio::select! {
+            result = &mut run => break result,
+            _ = clock.advance_to_next_sleep() => {}
+        }
+    };
+
+    let err = result.unwrap_err();
+    assert!(err.contains("after 3 attempts"), "{}", err);
+    assert!(err.contains("1 bot orders still open"), "{}", err);
+    assert_eq!(sim.order(&bot).unwrap().status, OrderStatus::OPEN);
+}
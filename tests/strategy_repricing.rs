@@ -0,0 +1,290 @@
+use backend::strategy::{self, BookTop, StrategyParams};
+use paradex::structs::{Level, Side};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+
+// Scenarios of the repricing rules of `strategy::determine_new_bid_price`
+
+struct Case {
+    name: &'static str,
+    // Our bid
+    price: f64,
+    size: f64,
+    // Levels (price, size) of the book, bids from the best one
+    bids: &'static [(f64, f64)],
+    ask: Option<(f64, f64)>,
+    step_size: f64,
+    expected: &'static str,
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "not first bid: join the first bid",
+        price: 9.5,
+        size: 1.0,
+        bids: &[(10.0, 5.0), (9.9, 20.0), (9.5, 1.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "above a stale book: join the first bid",
+        price: 10.5,
+        size: 1.0,
+        bids: &[(10.0, 5.0), (9.9, 20.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "first bid alone far above the second bid: come back to second bid + step",
+        price: 10.5,
+        size: 1.0,
+        bids: &[(10.5, 1.0), (10.0, 5.0), (9.9, 20.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.1",
+    },
+    Case {
+        name: "first bid alone one step above the second bid: keep",
+        price: 10.1,
+        size: 1.0,
+        bids: &[(10.1, 1.0), (10.0, 5.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.1",
+    },
+    Case {
+        name: "first bid alone less than a step above the second bid: keep",
+        price: 10.05,
+        size: 1.0,
+        bids: &[(10.05, 1.0), (10.0, 5.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.05",
+    },
+    Case {
+        name: "first bid alone without second bid: keep",
+        price: 10.0,
+        size: 1.0,
+        bids: &[(10.0, 1.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "first bid alone with a larger step: second bid + step",
+        price: 11.0,
+        size: 2.0,
+        bids: &[(11.0, 2.0), (10.0, 5.0)],
+        ask: Some((12.0, 5.0)),
+        step_size: 0.5,
+        expected: "10.5",
+    },
+    Case {
+        name: "first bid shared: go one step above",
+        price: 10.0,
+        size: 1.0,
+        bids: &[(10.0, 6.0), (9.9, 20.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.1",
+    },
+    Case {
+        name: "first bid shared, ask one step above: keep",
+        price: 10.0,
+        size: 1.0,
+        bids: &[(10.0, 6.0), (9.9, 20.0)],
+        ask: Some((10.1, 5.0)),
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "first bid shared, ask less than a step above: keep",
+        price: 10.0,
+        size: 1.0,
+        bids: &[(10.0, 6.0), (9.9, 20.0)],
+        ask: Some((10.05, 5.0)),
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "first bid shared without ask: keep",
+        price: 10.0,
+        size: 1.0,
+        bids: &[(10.0, 6.0), (9.9, 20.0)],
+        ask: None,
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "empty book: keep",
+        price: 10.0,
+        size: 1.0,
+        bids: &[],
+        ask: None,
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "crossed book, first bid shared: keep",
+        price: 10.0,
+        size: 1.0,
+        bids: &[(10.0, 6.0), (9.9, 20.0)],
+        ask: Some((9.9, 1.0)),
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "crossed book, first bid alone: come back to second bid + step",
+        price: 10.5,
+        size: 1.0,
+        bids: &[(10.5, 1.0), (10.0, 5.0)],
+        ask: Some((10.2, 1.0)),
+        step_size: 0.1,
+        expected: "10.1",
+    },
+    Case {
+        name: "first bid price not convertible: keep",
+        price: 9.5,
+        size: 1.0,
+        bids: &[(f64::NAN, 5.0), (9.9, 20.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "9.5",
+    },
+    Case {
+        name: "first bid size not convertible: keep",
+        price: 10.0,
+        size: 1.0,
+        bids: &[(10.0, f64::INFINITY), (9.9, 20.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.0",
+    },
+    Case {
+        name: "second bid price not convertible: keep",
+        price: 10.5,
+        size: 1.0,
+        bids: &[(10.5, 1.0), (f64::NAN, 5.0)],
+        ask: Some((11.0, 5.0)),
+        step_size: 0.1,
+        expected: "10.5",
+    },
+    Case {
+        name: "ask price not convertible: keep",
+        price: 10.0,
+        size: 1.0,
+        bids: &[(10.0, 6.0), (9.9, 20.0)],
+        ask: Some((f64::NAN, 5.0)),
+        step_size: 0.1,
+        expected: "10.0",
+    },
+];
+
+fn level(side: Side, (price, size): (f64, f64)) -> Level {
+    Level { side, price, size }
+}
+
+fn book(bids: &[(f64, f64)], ask: Option<(f64, f64)>) -> BookTop {
+    let bid = |index: usize| bids.get(index).map(|bid| level(Side::BUY, *bid));
+    BookTop {
+        first_bid: bid(0),
+        second_bid: bid(1),
+        third_bid: bid(2),
+        first_ask: ask.map(|ask| level(Side::SELL, ask)),
+    }
+}
+
+fn decimal(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap()
+}
+
+fn params(step_size: f64) -> StrategyParams {
+    StrategyParams {
+        step_size,
+        ..StrategyParams::default()
+    }
+}
+
+#[test]
+fn repricing_scenarios() {
+    for case in CASES {
+        let new_price = strategy::determine_new_bid_price(
+            Some(decimal(case.price)),
+            decimal(case.size),
+            &book(case.bids, case.ask),
+            &params(case.step_size),
+        );
+        assert_eq!(new_price, Some(case.expected.parse().unwrap()), "{}", case.name);
+    }
+}
+
+// Regression: a shared first bid stepped up whenever the ask was not exactly one step above, so an
+// ask less than a step above (or a crossed book) made the bid join or cross the ask
+#[test]
+fn shared_first_bid_does_not_step_into_the_ask() {
+    for ask in [10.05, 10.0, 9.9] {
+        let new_price = strategy::determine_new_bid_price(
+            Some(decimal(10.0)),
+            decimal(1.0),
+            &book(&[(10.0, 6.0), (9.9, 20.0)], Some((ask, 5.0))),
+            &params(0.1),
+        );
+        assert_eq!(new_price, Some(decimal(10.0)), "ask {}", ask);
+    }
+}
+
+// Random valid books (ask above the bids) on a 0.05 tick: whatever our place in the book, the new
+// price stays below the best ask
+#[test]
+fn new_price_never_crosses_the_best_ask() {
+    const TICK: f64 = 0.05;
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..20_000 {
+        let ask_ticks: u32 = rng.gen_range(20..400);
+        let mut bid_ticks = ask_ticks - rng.gen_range(1..=10);
+        let mut bids = Vec::new();
+        for _ in 0..rng.gen_range(1..=3) {
+            bids.push((bid_ticks as f64 * TICK, rng.gen_range(1..=20) as f64));
+            bid_ticks = bid_ticks.saturating_sub(rng.gen_range(1..=10)).max(1);
+        }
+        let size = rng.gen_range(1..=5) as f64;
+        let price = match rng.gen_range(0..3) {
+            // first bid alone
+            0 => {
+                bids[0].1 = size;
+                bids[0].0
+            }
+            // first bid shared
+            1 => {
+                bids[0].1 += size;
+                bids[0].0
+            }
+            // below the first bid
+            _ => (bids[0].0 - rng.gen_range(1..=20) as f64 * TICK).max(TICK),
+        };
+        let step_size = rng.gen_range(1..=4) as f64 * 5.0 / 100.0;
+        let ask = (ask_ticks as f64 * TICK, 1.0);
+
+        let new_price = strategy::determine_new_bid_price(
+            Some(decimal(price)),
+            decimal(size),
+            &book(&bids, Some(ask)),
+            &params(step_size),
+        )
+        .unwrap();
+        assert!(
+            new_price < decimal(ask.0),
+            "bid {} x {} repriced to {} on bids {:?} ask {} step {}",
+            price,
+            size,
+            new_price,
+            bids,
+            ask.0,
+            step_size
+        );
+    }
+}
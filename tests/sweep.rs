@@ -0,0 +1,156 @@
+use backend::backtest::BacktestConfig;
+use backend::fees::FeeModel;
+use backend::recorder::{MarketData, Record};
+use backend::strategy::{QuoteRules, StrategyParams};
+use backend::sweep::{self, Dataset, ParamRange, SearchMethod, SweepConfig};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use paradex::structs::{Side, Trade, TradeType};
+
+// Candidates of the parameter search, split of the recorded days and ranking of the results
+
+fn range(min: f64, max: f64, step: f64) -> ParamRange {
+    ParamRange { min, max, step }
+}
+
+fn sweep_config(method: SearchMethod, samples: usize) -> SweepConfig {
+    SweepConfig {
+        method,
+        step_size: range(0.1, 0.1, 0.1),
+        max_spread_price: range(1.0, 3.0, 1.0),
+        size_multiplier: range(1.0, 2.0, 1.0),
+        samples,
+        ..SweepConfig::default()
+    }
+}
+
+fn params(step_size: f64, max_spread_price: f64, size_multiplier: f64) -> StrategyParams {
+    StrategyParams {
+        step_size,
+        max_spread_price,
+        size_multiplier,
+    }
+}
+
+fn at(day: u32, hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2026, 6, day, hour, 0, 0).unwrap()
+}
+
+fn trade(market: &str, received_at: DateTime<Utc>) -> Record {
+    Record {
+        received_at,
+        market: market.to_string(),
+        data: MarketData::Trade(Trade {
+            created_at: received_at.timestamp_millis() as u64,
+            id: received_at.timestamp().to_string(),
+            market: market.to_string(),
+            price: 10.0,
+            side: Side::SELL,
+            size: 1.0,
+            trade_type: TradeType::FILL,
+        }),
+    }
+}
+
+fn day(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, 6, day).unwrap()
+}
+
+#[test]
+fn range_values_are_rounded_to_the_step() {
+    assert_eq!(range(0.1, 0.5, 0.1).values(), vec![0.1, 0.2, 0.3, 0.4, 0.5]);
+    // 0.2 / 0.1 is just below 2
+    assert_eq!(range(0.1, 0.3, 0.1).values(), vec![0.1, 0.2, 0.3]);
+    // max not on the step
+    assert_eq!(range(1.0, 2.5, 1.0).values(), vec![1.0, 2.0]);
+    // single value
+    assert_eq!(range(5.0, 5.0, 1.0).values(), vec![5.0]);
+    assert_eq!(range(5.0, 4.0, 1.0).values(), vec![5.0]);
+    assert_eq!(range(5.0, 8.0, 0.0).values(), vec![5.0]);
+}
+
+#[test]
+fn grid_has_every_combination() {
+    let candidates = sweep::candidates(&sweep_config(SearchMethod::Grid, 0));
+    assert_eq!(candidates.len(), 6);
+    assert_eq!(candidates[0], params(0.1, 1.0, 1.0));
+    assert_eq!(candidates[1], params(0.1, 1.0, 2.0));
+    assert_eq!(candidates[5], params(0.1, 3.0, 2.0));
+    for (index, candidate) in candidates.iter().enumerate() {
+        assert!(!candidates[index + 1..].contains(candidate), "{:?} twice", candidate);
+    }
+}
+
+#[test]
+fn random_search_draws_distinct_candidates() {
+    let config = sweep_config(SearchMethod::Random, 4);
+    let candidates = sweep::candidates(&config);
+    assert_eq!(candidates.len(), 4);
+    let grid = sweep::candidates(&sweep_config(SearchMethod::Grid, 0));
+    for (index, candidate) in candidates.iter().enumerate() {
+        assert!(grid.contains(candidate));
+        assert!(!candidates[index + 1..].contains(candidate), "{:?} twice", candidate);
+    }
+    // same seed, same draw
+    assert_eq!(sweep::candidates(&config), candidates);
+
+    // more samples than combinations: each combination once
+    let candidates = sweep::candidates(&sweep_config(SearchMethod::Random, 20));
+    assert_eq!(candidates.len(), 6);
+}
+
+#[test]
+fn last_days_are_kept_for_the_validation() {
+    let mut records = Vec::new();
+    for day in 15..=18 {
+        records.push(trade("ETH-USD-4000-C", at(day, 12)));
+        records.push(trade("BTC-USD-100000-C", at(day, 12)));
+        records.push(trade("BTC-USD-100000-C", at(day, 10)));
+    }
+    let dataset = Dataset::split(records, 0.3).unwrap();
+    assert_eq!(dataset.training_days, vec![day(15), day(16)]);
+    assert_eq!(dataset.validation_days, vec![day(17), day(18)]);
+    // one replay per market and day, in reception order
+    assert_eq!(dataset.training.len(), 4);
+    assert_eq!(dataset.validation.len(), 4);
+    for replay in dataset.training.iter().chain(&dataset.validation) {
+        let market = &replay[0].market;
+        assert!(replay.iter().all(|record| &record.market == market));
+        assert!(replay.windows(2).all(|pair| pair[0].received_at <= pair[1].received_at));
+    }
+
+    // at least one day on each side
+    let records = || (15..=18).map(|day| trade("ETH-USD-4000-C", at(day, 12))).collect::<Vec<_>>();
+    assert_eq!(Dataset::split(records(), 0.0).unwrap().validation_days, vec![day(18)]);
+    assert_eq!(Dataset::split(records(), 1.0).unwrap().training_days, vec![day(15)]);
+    assert!(Dataset::split(vec![trade("ETH-USD-4000-C", at(15, 12))], 0.3).is_err());
+}
+
+#[test]
+fn ties_are_ranked_by_parameters() {
+    // no book: no volume for any candidate
+    let records = (0..4)
+        .map(|hour| trade("ETH-USD-4000-C", at(15, 0) + Duration::hours(hour * 12)))
+        .collect();
+    let dataset = Dataset::split(records, 0.5).unwrap();
+    let rules = QuoteRules {
+        params: StrategyParams::default(),
+        fee_model: FeeModel {
+            maker_rate: 0.0,
+            taker_rate: 0.0003,
+        },
+        max_cost_per_1k: None,
+    };
+    let config = SweepConfig {
+        threads: 4,
+        top: 2,
+        ..sweep_config(SearchMethod::Grid, 0)
+    };
+    let grid = sweep::candidates(&config);
+    for _ in 0..5 {
+        let results = sweep::run_sweep(&config, &dataset, &rules, &BacktestConfig::default());
+        let ranked: Vec<StrategyParams> = results.iter().map(|result| result.params).collect();
+        assert_eq!(ranked, grid);
+        assert!(results[1].validation.is_some());
+        assert!(results[2].validation.is_none());
+    }
+}
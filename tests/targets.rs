@@ -0,0 +1,109 @@
+use backend::accounting::Accounting;
+use backend::targets::{StopReason, TargetConfig, TargetPeriod, TargetProgress};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use paradex::structs::{Fill, FillLiquidity, FillType, Side};
+
+// Period boundaries and the stop conditions of the volume target / spending budget
+
+fn local(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+    Local.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+}
+
+fn start(period: TargetPeriod, now: DateTime<Local>) -> DateTime<Local> {
+    period.start(now).with_timezone(&Local)
+}
+
+#[test]
+fn day_starts_at_local_midnight() {
+    let midnight = local(2026, 6, 17, 0, 0);
+    assert_eq!(start(TargetPeriod::Day, local(2026, 6, 17, 15, 30)), midnight);
+    assert_eq!(start(TargetPeriod::Day, midnight), midnight);
+    assert_eq!(start(TargetPeriod::Day, local(2026, 6, 17, 23, 59)), midnight);
+}
+
+#[test]
+fn week_starts_on_monday() {
+    let monday = local(2026, 6, 15, 0, 0);
+    // Wednesday, Monday itself, Sunday evening
+    assert_eq!(start(TargetPeriod::Week, local(2026, 6, 17, 15, 30)), monday);
+    assert_eq!(start(TargetPeriod::Week, monday), monday);
+    assert_eq!(start(TargetPeriod::Week, local(2026, 6, 21, 23, 59)), monday);
+    assert_eq!(start(TargetPeriod::Week, local(2026, 6, 22, 0, 0)), local(2026, 6, 22, 0, 0));
+    // across the year
+    assert_eq!(start(TargetPeriod::Week, local(2026, 1, 1, 12, 0)), local(2025, 12, 29, 0, 0));
+}
+
+#[test]
+fn month_starts_on_the_first_day() {
+    assert_eq!(start(TargetPeriod::Month, local(2026, 6, 17, 15, 30)), local(2026, 6, 1, 0, 0));
+    assert_eq!(start(TargetPeriod::Month, local(2026, 6, 1, 0, 0)), local(2026, 6, 1, 0, 0));
+    assert_eq!(start(TargetPeriod::Month, local(2026, 6, 30, 23, 59)), local(2026, 6, 1, 0, 0));
+}
+
+fn fill(id: &str, side: Side, price: f64, at: DateTime<Local>) -> Fill {
+    Fill {
+        client_id: String::new(),
+        created_at: at.with_timezone(&Utc).timestamp_millis() as u64,
+        fee: 0.0,
+        fee_currency: "USDC".to_string(),
+        id: id.to_string(),
+        liquidity: FillLiquidity::MAKER,
+        market: "BTC-USD-100000-C".to_string(),
+        order_id: id.to_string(),
+        price,
+        side,
+        size: 1.0,
+        remaining_size: 0.0,
+        fill_type: FillType::FILL,
+        realized_pnl: 0.0,
+    }
+}
+
+// One round trip closed on Sunday (previous week), one on Tuesday: volume 19 and cost 1 each
+fn accounting() -> Accounting {
+    let mut accounting = Accounting::new(local(2026, 6, 14, 0, 0).with_timezone(&Utc));
+    accounting.add_fill(&fill("1", Side::BUY, 10.0, local(2026, 6, 14, 10, 0)));
+    accounting.add_fill(&fill("2", Side::SELL, 9.0, local(2026, 6, 14, 10, 1)));
+    accounting.add_fill(&fill("3", Side::BUY, 10.0, local(2026, 6, 16, 10, 0)));
+    accounting.add_fill(&fill("4", Side::SELL, 9.0, local(2026, 6, 16, 10, 1)));
+    accounting
+}
+
+fn progress(volume: Option<f64>, max_cost: Option<f64>) -> TargetProgress {
+    let config = TargetConfig {
+        period: TargetPeriod::Week,
+        volume,
+        max_cost,
+    };
+    TargetProgress::compute(&config, &accounting(), local(2026, 6, 17, 15, 30))
+}
+
+#[test]
+fn progress_counts_the_round_trips_of_the_period() {
+    let progress = progress(Some(100.0), Some(5.0));
+    assert_eq!(progress.period_start, local(2026, 6, 15, 0, 0).with_timezone(&Utc));
+    assert_eq!(progress.volume, 19.0);
+    assert_eq!(progress.cost, 1.0);
+    assert_eq!(progress.stop_reason(), None);
+}
+
+#[test]
+fn volume_reached_at_the_target() {
+    assert_eq!(progress(Some(19.0), None).stop_reason(), Some(StopReason::VolumeReached));
+    assert_eq!(progress(Some(19.5), None).stop_reason(), None);
+}
+
+#[test]
+fn budget_exhausted_at_the_max_cost() {
+    assert_eq!(progress(None, Some(1.0)).stop_reason(), Some(StopReason::BudgetExhausted));
+    assert_eq!(progress(None, Some(1.5)).stop_reason(), None);
+    // the budget comes first when both are hit
+    assert_eq!(progress(Some(10.0), Some(1.0)).stop_reason(), Some(StopReason::BudgetExhausted));
+}
+
+#[test]
+fn no_stop_without_target_nor_budget() {
+    let config = TargetConfig::default();
+    assert!(!config.is_set());
+    assert_eq!(progress(None, None).stop_reason(), None);
+}